@@ -1,7 +1,10 @@
 use core::fmt;
+use std::str::FromStr;
 
+use chrono::{DateTime, Local, TimeZone};
 use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::theme::{hex_to_color, Colors, Subpalette};
 use crate::user::Nick;
 use crate::{channel, config, message, Server};
 
@@ -49,12 +52,88 @@ impl Buffer {
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Settings {
     pub channel: channel::Settings,
+    /// Guards against sending messages to the buffer by accident
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default)]
+    pub appearance: Appearance,
+    /// Hides routine numeric replies, if this is a server buffer
+    #[serde(default)]
+    pub compact: bool,
 }
 
 impl From<config::Buffer> for Settings {
     fn from(config: config::Buffer) -> Self {
         Self {
             channel: channel::Settings::from(config.channel),
+            read_only: false,
+            appearance: Appearance::default(),
+            compact: config.compact_server_buffer.enabled,
+        }
+    }
+}
+
+/// Colors of a single buffer overriding the theme's, as hex strings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Appearance {
+    pub background: Option<String>,
+    pub accent: Option<String>,
+    pub nickname: Option<String>,
+}
+
+impl Appearance {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Sets the color of `part`, or resets it to the theme's when `None`
+    pub fn set(&mut self, part: AppearancePart, hex: Option<String>) {
+        match part {
+            AppearancePart::Background => self.background = hex,
+            AppearancePart::Accent => self.accent = hex,
+            AppearancePart::Nickname => self.nickname = hex,
+        }
+    }
+
+    /// `colors` with the set colors in place of theirs
+    pub fn apply(&self, colors: &Colors) -> Colors {
+        let background = self.background.as_deref().and_then(hex_to_color);
+        let accent = self.accent.as_deref().and_then(hex_to_color);
+        let background_base = background.unwrap_or(colors.background.base);
+
+        Colors {
+            background: background
+                .map(|color| Subpalette::on_background(color, color))
+                .unwrap_or_else(|| colors.background.clone()),
+            accent: accent
+                .map(|color| Subpalette::on_background(color, background_base))
+                .unwrap_or_else(|| colors.accent.clone()),
+            nickname: self
+                .nickname
+                .as_deref()
+                .and_then(hex_to_color)
+                .or(colors.nickname),
+            ..colors.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppearancePart {
+    Background,
+    Accent,
+    Nickname,
+}
+
+impl FromStr for AppearancePart {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "background" => Ok(AppearancePart::Background),
+            "accent" => Ok(AppearancePart::Accent),
+            "nickname" | "nick" => Ok(AppearancePart::Nickname),
+            _ => Err(()),
         }
     }
 }
@@ -62,6 +141,9 @@ impl From<config::Buffer> for Settings {
 #[derive(Debug, Clone, Copy, Default, Deserialize)]
 pub struct TextInput {
     pub visibility: TextInputVisibility,
+    /// Keep unsent drafts across restarts
+    #[serde(default)]
+    pub persist_drafts: bool,
 }
 
 #[derive(Debug, Clone, Copy, Default, Deserialize)]
@@ -72,23 +154,185 @@ pub enum TextInputVisibility {
     Always,
 }
 
+/// Timestamp configuration, resolved once when loaded as it's used on every render
 #[derive(Debug, Clone, Deserialize)]
+#[serde(from = "TimestampConfig")]
 pub struct Timestamp {
-    #[serde(default = "default_timestamp")]
+    /// `format` with its hours in the configured clock
     pub format: String,
-    #[serde(default)]
     pub brackets: Brackets,
+    pub alignment: Option<Alignment>,
+    /// Width of the widest time in `format`, which aligned timestamps are padded to
+    pub width: usize,
 }
 
 impl Default for Timestamp {
+    fn default() -> Self {
+        Self::from(TimestampConfig::default())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TimestampConfig {
+    #[serde(default = "default_timestamp")]
+    format: String,
+    #[serde(default)]
+    brackets: Brackets,
+    #[serde(default)]
+    clock: Clock,
+    #[serde(default)]
+    alignment: Option<Alignment>,
+}
+
+impl Default for TimestampConfig {
     fn default() -> Self {
         Self {
             format: default_timestamp(),
             brackets: Default::default(),
+            clock: Default::default(),
+            alignment: None,
+        }
+    }
+}
+
+impl From<TimestampConfig> for Timestamp {
+    fn from(config: TimestampConfig) -> Self {
+        let format = match config.clock.is_twelve_hour() {
+            Some(twelve_hour) => with_clock(&config.format, twelve_hour),
+            None => config.format,
+        };
+        let width = widest_time().format(&format).to_string().chars().count();
+
+        Self {
+            format,
+            brackets: config.brackets,
+            alignment: config.alignment,
+            width,
+        }
+    }
+}
+
+/// A time with two digit hours on a 12 and 24 hour clock, in the longest month and weekday
+fn widest_time() -> DateTime<Local> {
+    Local
+        .with_ymd_and_hms(2024, 9, 25, 22, 58, 58)
+        .earliest()
+        .unwrap_or_else(Local::now)
+}
+
+/// Hour clock of timestamps, regardless of the hours used in their format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Clock {
+    /// The hours used in the format
+    #[default]
+    #[serde(rename = "format")]
+    Format,
+    /// The clock of the time locale, or the format's when it isn't known
+    #[serde(rename = "auto")]
+    Auto,
+    #[serde(rename = "12h")]
+    TwelveHour,
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+}
+
+impl Clock {
+    fn is_twelve_hour(self) -> Option<bool> {
+        match self {
+            Clock::Format => None,
+            Clock::Auto => locale_is_twelve_hour(),
+            Clock::TwelveHour => Some(true),
+            Clock::TwentyFourHour => Some(false),
         }
     }
 }
 
+/// Side timestamps are aligned to, when their width varies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Alignment {
+    Left,
+    Right,
+}
+
+/// Whether the region of the time locale uses a 12 hour clock, if the locale is set
+fn locale_is_twelve_hour() -> Option<bool> {
+    let locale = ["LC_ALL", "LC_TIME", "LANG"]
+        .into_iter()
+        .find_map(|var| std::env::var(var).ok().filter(|value| !value.is_empty()))?;
+
+    let language = locale.split(['.', '@']).next().unwrap_or_default();
+    let (_, region) = language.split_once(['_', '-'])?;
+
+    Some(matches!(
+        region,
+        "US" | "CA" | "AU" | "NZ" | "PH" | "IN" | "PK" | "BD" | "EG" | "SA" | "MY"
+    ))
+}
+
+/// Rewrites the hours of a strftime `format` to a 12 or 24 hour clock, adding or removing the
+/// AM/PM marker
+fn with_clock(format: &str, twelve_hour: bool) -> String {
+    let mut output = String::with_capacity(format.len() + 3);
+    let mut has_marker = false;
+    // Where the marker goes when it's missing, after the last part of the time
+    let mut time_end = None;
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+
+        let mut flags = String::new();
+        while let Some(&flag) = chars.peek().filter(|c| matches!(c, '-' | '_' | '0')) {
+            flags.push(flag);
+            chars.next();
+        }
+
+        let Some(spec) = chars.next() else {
+            output.push('%');
+            output.push_str(&flags);
+            break;
+        };
+
+        let replaced = match (twelve_hour, spec) {
+            (true, 'H') => format!("%{flags}I"),
+            (true, 'k') => format!("%{flags}l"),
+            (true, 'R') => "%I:%M".to_string(),
+            (true, 'T') => "%I:%M:%S".to_string(),
+            (true, 'p' | 'P' | 'r') => {
+                has_marker = true;
+                format!("%{flags}{spec}")
+            }
+            (false, 'I') => format!("%{flags}H"),
+            (false, 'l') => format!("%{flags}k"),
+            (false, 'r') => "%T".to_string(),
+            (false, 'p' | 'P') => {
+                // Along with the space separating it from the time
+                if output.ends_with(' ') {
+                    output.pop();
+                }
+                String::new()
+            }
+            _ => format!("%{flags}{spec}"),
+        };
+
+        output.push_str(&replaced);
+
+        if matches!(spec, 'H' | 'I' | 'k' | 'l' | 'M' | 'S' | 'R' | 'T') {
+            time_end = Some(output.len());
+        }
+    }
+
+    if let Some(time_end) = time_end.filter(|_| twelve_hour && !has_marker) {
+        output.insert_str(time_end, " %p");
+    }
+
+    output
+}
+
 #[derive(Debug, Clone, Default, Deserialize)]
 pub struct Nickname {
     #[serde(default)]
@@ -174,3 +418,27 @@ impl Resize {
 fn default_timestamp() -> String {
     "%R".to_string()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clock() {
+        let tests = [
+            ("%R", "%I:%M %p", "%R"),
+            ("%T", "%I:%M:%S %p", "%T"),
+            ("%-I:%M %p", "%-I:%M %p", "%-H:%M"),
+            ("[%H:%M]", "[%I:%M %p]", "[%H:%M]"),
+            ("%r", "%r", "%T"),
+            ("%d/%m %k:%M", "%d/%m %l:%M %p", "%d/%m %k:%M"),
+            ("%F %H:%M", "%F %I:%M %p", "%F %H:%M"),
+            ("100%%", "100%%", "100%%"),
+        ];
+
+        for (format, twelve_hour, twenty_four_hour) in tests {
+            assert_eq!(with_clock(format, true), twelve_hour, "{format}");
+            assert_eq!(with_clock(format, false), twenty_four_hour, "{format}");
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+
+pub const MULTILINE: &str = "draft/multiline";
+
+/// Capabilities advertised by the server with `CAP LS` and `CAP NEW`, along with their values
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Listed(BTreeMap<String, Option<String>>);
+
+impl Listed {
+    /// Adds the space separated `name[=value]` capabilities of a `CAP LS` line, replacing the
+    /// values of capabilities already listed
+    pub fn extend(&mut self, caps: &str) {
+        self.0
+            .extend(parse(caps).map(|(name, value)| (name.to_string(), value.map(String::from))));
+    }
+
+    /// Removes the capabilities of a `CAP DEL` line
+    pub fn remove(&mut self, caps: &str) {
+        for (name, _) in parse(caps) {
+            self.0.remove(name);
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// The value of a listed capability, if it has one
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.0.get(name)?.as_deref()
+    }
+
+    /// The comma separated entries of a capability's value, e.g. the mechanisms of `sasl`
+    pub fn values<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a str> {
+        self.value(name)
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .filter(|value| !value.is_empty())
+    }
+
+    /// The value of a `key=value` entry of a capability's value, e.g. `max-bytes` of
+    /// `draft/multiline`
+    pub fn key_value<'a>(&'a self, name: &str, key: &str) -> Option<&'a str> {
+        self.values(name)
+            .find_map(|entry| match entry.split_once('=') {
+                Some((entry_key, value)) => (entry_key == key).then_some(value),
+                None => None,
+            })
+    }
+
+    /// The limits of `draft/multiline` batches, when the server advertises them
+    pub fn multiline_limits(&self) -> Option<MultilineLimits> {
+        let max_bytes = self.key_value(MULTILINE, "max-bytes")?.parse().ok()?;
+        let max_lines = self
+            .key_value(MULTILINE, "max-lines")
+            .and_then(|value| value.parse().ok());
+
+        Some(MultilineLimits {
+            max_bytes,
+            max_lines,
+        })
+    }
+}
+
+/// Limits of a `draft/multiline` batch. `max-bytes` counts the text of every line, along with
+/// the line breaks joining them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultilineLimits {
+    pub max_bytes: usize,
+    pub max_lines: Option<usize>,
+}
+
+impl MultilineLimits {
+    /// Groups `lines` into as few batches as the limits allow. A line longer than `max_bytes`
+    /// is sent in a batch of its own
+    pub fn batches<'a>(&self, lines: &'a [String]) -> Vec<&'a [String]> {
+        let mut batches = vec![];
+        let mut start = 0;
+        let mut bytes = 0;
+
+        for (index, line) in lines.iter().enumerate() {
+            let count = index - start;
+            let joined = bytes + usize::from(count > 0) + line.len();
+
+            let full = joined > self.max_bytes
+                || self.max_lines.is_some_and(|max_lines| count >= max_lines);
+
+            if count > 0 && full {
+                batches.push(&lines[start..index]);
+                start = index;
+                bytes = line.len();
+            } else {
+                bytes = joined;
+            }
+        }
+
+        if start < lines.len() {
+            batches.push(&lines[start..]);
+        }
+
+        batches
+    }
+}
+
+/// Splits space separated `name[=value]` capabilities into names and values
+pub fn parse(caps: &str) -> impl Iterator<Item = (&str, Option<&str>)> {
+    caps.split(' ')
+        .filter(|cap| !cap.is_empty())
+        .map(|cap| match cap.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (cap, None),
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn multiline_ls() {
+        let mut listed = Listed::default();
+
+        // CAP * LS * :multi-prefix sasl=PLAIN,EXTERNAL
+        // CAP * LS :draft/multiline=max-bytes=4096,max-lines=24 server-time
+        listed.extend("multi-prefix sasl=PLAIN,EXTERNAL");
+        listed.extend("draft/multiline=max-bytes=4096,max-lines=24 server-time ");
+
+        assert!(listed.contains("multi-prefix"));
+        assert!(listed.contains("server-time"));
+        assert!(!listed.contains("sasl=PLAIN,EXTERNAL"));
+        assert_eq!(listed.value("multi-prefix"), None);
+        assert_eq!(
+            listed.values("sasl").collect::<Vec<_>>(),
+            vec!["PLAIN", "EXTERNAL"]
+        );
+        assert_eq!(
+            listed.key_value("draft/multiline", "max-bytes"),
+            Some("4096")
+        );
+        assert_eq!(listed.key_value("draft/multiline", "max-lines"), Some("24"));
+        assert_eq!(listed.key_value("draft/multiline", "max-nope"), None);
+    }
+
+    #[test]
+    fn multiline_batches() {
+        let mut listed = Listed::default();
+
+        listed.extend("draft/multiline=max-bytes=10,max-lines=3");
+
+        let limits = listed.multiline_limits().unwrap();
+        let lines = ["aaaa", "bbbb", "c", "d", "e", "ffffffffffff", "g"].map(String::from);
+
+        assert_eq!(
+            limits.batches(&lines),
+            vec![&lines[0..2], &lines[2..5], &lines[5..6], &lines[6..7]]
+        );
+
+        listed.extend("draft/multiline=max-lines=3");
+        assert_eq!(listed.multiline_limits(), None);
+    }
+
+    #[test]
+    fn new_and_del() {
+        let mut listed = Listed::default();
+
+        listed.extend("sasl=PLAIN away-notify");
+        listed.extend("sasl=PLAIN,EXTERNAL");
+        assert_eq!(listed.value("sasl"), Some("PLAIN,EXTERNAL"));
+
+        listed.remove("sasl");
+        assert!(!listed.contains("sasl"));
+        assert!(listed.contains("away-notify"));
+    }
+}
@@ -6,6 +6,10 @@ use crate::config;
 pub struct Settings {
     pub nicklist: Nicklist,
     pub topic: Topic,
+    #[serde(default)]
+    pub threads: Threads,
+    #[serde(default)]
+    pub bot_commands: BotCommands,
 }
 
 impl From<config::Channel> for Settings {
@@ -13,6 +17,8 @@ impl From<config::Channel> for Settings {
         Self {
             nicklist: Nicklist::from(config.nicklist),
             topic: Topic::from(config.topic),
+            threads: Threads::from(config.threads),
+            bot_commands: BotCommands::from(config.bot_commands),
         }
     }
 }
@@ -68,3 +74,41 @@ impl Topic {
         self.enabled = !self.enabled
     }
 }
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+pub struct Threads {
+    pub enabled: bool,
+}
+
+impl From<config::channel::Threads> for Threads {
+    fn from(config: config::channel::Threads) -> Self {
+        Threads {
+            enabled: config.enabled,
+        }
+    }
+}
+
+impl Threads {
+    pub fn toggle_visibility(&mut self) {
+        self.enabled = !self.enabled
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+pub struct BotCommands {
+    pub collapsed: bool,
+}
+
+impl From<config::channel::BotCommands> for BotCommands {
+    fn from(config: config::channel::BotCommands) -> Self {
+        BotCommands {
+            collapsed: config.collapsed,
+        }
+    }
+}
+
+impl BotCommands {
+    pub fn toggle(&mut self) {
+        self.collapsed = !self.collapsed
+    }
+}
@@ -0,0 +1,201 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{compression, environment, server};
+
+/// A channel of a `LIST` reply
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub channel: String,
+    pub users: usize,
+    pub topic: String,
+}
+
+/// The channels of the last full `LIST` of a server, kept so they can be shown right away
+/// while a fresh list is requested
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cached {
+    pub fetched_at: DateTime<Utc>,
+    /// Channels listed by the server, before the largest `entries` were kept
+    pub total: usize,
+    pub entries: Vec<Entry>,
+}
+
+impl Cached {
+    /// Keeps the `max_entries` channels with the most users
+    pub fn new(mut entries: Vec<Entry>, max_entries: usize) -> Self {
+        let total = entries.len();
+
+        entries.sort_by(|a, b| {
+            b.users
+                .cmp(&a.users)
+                .then_with(|| a.channel.cmp(&b.channel))
+        });
+        entries.truncate(max_entries);
+
+        Self {
+            fetched_at: Utc::now(),
+            total,
+            entries,
+        }
+    }
+
+    pub fn is_expired(&self, ttl: Duration, now: DateTime<Utc>) -> bool {
+        chrono::Duration::from_std(ttl).map_or(true, |ttl| self.fetched_at + ttl <= now)
+    }
+
+    /// Whether the channel count the server reports has drifted too far from the listed
+    /// channels for the list to still be useful, e.g. after a netsplit or a network merge
+    pub fn is_outdated(&self, channel_count: usize) -> bool {
+        let (fewer, more) = if self.total < channel_count {
+            (self.total, channel_count)
+        } else {
+            (channel_count, self.total)
+        };
+
+        more > fewer.saturating_mul(2).max(MIN_DRIFT)
+    }
+}
+
+/// Running totals of a `LIST`, updated as its replies arrive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub channels: usize,
+    pub users: usize,
+    /// Channels by size: 1 user, 2–9, 10–99, 100–999 and 1000 or more
+    pub sizes: [usize; 5],
+}
+
+impl Stats {
+    pub fn add(&mut self, users: usize) {
+        self.channels += 1;
+        self.users += users;
+
+        let bucket = match users {
+            0..=1 => 0,
+            2..=9 => 1,
+            10..=99 => 2,
+            100..=999 => 3,
+            _ => 4,
+        };
+
+        self.sizes[bucket] += 1;
+    }
+
+    /// The channel sizes, labelled by their range of users
+    pub fn histogram(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        ["1", "2–9", "10–99", "100–999", "1000+"]
+            .into_iter()
+            .zip(self.sizes)
+    }
+}
+
+/// Channel counts up to which differences are ignored, as a handful of channels coming and
+/// going is a large share on small networks
+const MIN_DRIFT: usize = 50;
+
+pub async fn load(server: &server::Server) -> Result<Cached, Error> {
+    let bytes = fs::read(path(server).await?).await?;
+
+    Ok(compression::decompress(&bytes)?)
+}
+
+pub async fn save(server: server::Server, cached: Cached) -> Result<(), Error> {
+    let bytes = compression::compress(&cached)?;
+
+    fs::write(path(&server).await?, &bytes).await?;
+
+    Ok(())
+}
+
+async fn path(server: &server::Server) -> Result<PathBuf, Error> {
+    let hashed_name = seahash::hash(format!("{server}").as_bytes());
+
+    let parent = environment::data_dir().join("channel-list");
+
+    if !parent.exists() {
+        fs::create_dir_all(&parent).await?;
+    }
+
+    Ok(parent.join(format!("{hashed_name}.json.gz")))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Compression(#[from] compression::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(channel: &str, users: usize) -> Entry {
+        Entry {
+            channel: channel.to_string(),
+            users,
+            topic: String::new(),
+        }
+    }
+
+    #[test]
+    fn keeps_largest() {
+        let cached = Cached::new(
+            vec![
+                entry("#small", 3),
+                entry("#large", 300),
+                entry("#medium", 30),
+            ],
+            2,
+        );
+
+        assert_eq!(cached.total, 3);
+        assert_eq!(
+            cached.entries,
+            vec![entry("#large", 300), entry("#medium", 30)]
+        );
+    }
+
+    #[test]
+    fn stats() {
+        let mut stats = Stats::default();
+
+        for users in [0, 1, 2, 9, 10, 150, 1000, 4000] {
+            stats.add(users);
+        }
+
+        assert_eq!(stats.channels, 8);
+        assert_eq!(stats.users, 5172);
+        assert_eq!(
+            stats.histogram().collect::<Vec<_>>(),
+            vec![
+                ("1", 2),
+                ("2–9", 2),
+                ("10–99", 1),
+                ("100–999", 1),
+                ("1000+", 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn outdated() {
+        let cached = Cached::new((0..1000).map(|n| entry(&format!("#{n}"), n)).collect(), 10);
+
+        assert!(!cached.is_outdated(1000));
+        assert!(!cached.is_outdated(1800));
+        assert!(cached.is_outdated(2500));
+        assert!(cached.is_outdated(400));
+
+        let cached = Cached::new(vec![entry("#halloy", 10)], 10);
+
+        assert!(!cached.is_outdated(40));
+        assert!(cached.is_outdated(60));
+    }
+}
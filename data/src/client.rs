@@ -8,17 +8,27 @@ use std::time::{Duration, Instant};
 
 use crate::message::server_time;
 use crate::time::Posix;
-use crate::user::{Nick, NickRef};
-use crate::{config, dcc, isupport, message, mode, Buffer, Server, User};
-use crate::{file_transfer, server};
+use crate::user::{Account, Nick, NickRef};
+use crate::{capability, config, dcc, isupport, message, mode, presence, Buffer, Server, User};
+use crate::{channel_list, file_transfer, server};
 
 const HIGHLIGHT_BLACKOUT_INTERVAL: Duration = Duration::from_secs(5);
+/// How long after messaging a user a "no such nick" reply is taken to be about the message
+const UNDELIVERED_REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+const SEARCH_CAP: &str = "soju.im/search";
+const SEARCH_BATCH_TYPE: &str = "soju.im/search";
+const READ_RECEIPT_TAG: &str = "+draft/read";
+/// Variations of our nickname tried once alt nicks are all in use
+const MAX_NICK_FALLBACKS: usize = 9;
+/// `NICKLEN` assumed until the server sends its own, the shortest allowed by RFC 1459
+const DEFAULT_NICKLEN: usize = 9;
 
 #[derive(Debug, Clone, Copy)]
 pub enum Status {
     Unavailable,
     Connected,
     Disconnected,
+    Failed,
 }
 
 impl Status {
@@ -30,6 +40,8 @@ impl Status {
 #[derive(Debug)]
 pub enum State {
     Disconnected,
+    /// Disconnected and the last attempt to reconnect failed
+    Failed,
     Ready(Client),
 }
 
@@ -38,6 +50,21 @@ pub enum Notification {
     Highlight(User, String),
 }
 
+impl Notification {
+    /// The buffer which triggered the notification
+    pub fn buffer(&self, server: &Server) -> Buffer {
+        match self {
+            Notification::Highlight(user, target) => {
+                if proto::is_channel(target) {
+                    Buffer::Channel(server.clone(), target.clone())
+                } else {
+                    Buffer::Query(server.clone(), user.nickname().to_owned())
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Broadcast {
     Quit {
@@ -59,6 +86,25 @@ pub enum Broadcast {
         user_channels: Vec<String>,
         sent_time: DateTime<Utc>,
     },
+    ChangeHost {
+        old_user: User,
+        new_username: String,
+        new_hostname: String,
+        ourself: bool,
+        logged_in: bool,
+        channels: Vec<String>,
+        sent_time: DateTime<Utc>,
+    },
+    SaslAuthenticated {
+        mechanism: &'static str,
+        account: Option<String>,
+        sent_time: DateTime<Utc>,
+    },
+    SaslFailed {
+        mechanism: &'static str,
+        reason: String,
+        sent_time: DateTime<Utc>,
+    },
 }
 
 #[derive(Debug)]
@@ -68,6 +114,19 @@ pub enum Event {
     Broadcast(Broadcast),
     Notification(message::Encoded, Nick, Notification),
     FileTransferRequest(file_transfer::ReceiveRequest),
+    SearchResults(Buffer, Vec<message::Encoded>, Nick),
+    Help(String, Vec<String>),
+    Links(Vec<message::Link>),
+    UserModesNotSet(String, String),
+    Undelivered(Nick, String),
+    Delivered(Nick, usize),
+    Presence(presence::Update),
+    Unsent(Buffer, String),
+    /// Server asked us to connect to `host` and `port` instead
+    Redirect(String, u16, String),
+    /// A full channel list was received, to be cached
+    ChannelList(channel_list::Cached),
+    ListStats(channel_list::Stats),
 }
 
 pub struct Client {
@@ -83,12 +142,38 @@ pub struct Client {
     batches: HashMap<String, Batch>,
     reroute_responses_to: Option<Buffer>,
     registration_step: RegistrationStep,
-    listed_caps: Vec<String>,
+    listed_caps: capability::Listed,
     supports_labels: bool,
     supports_away_notify: bool,
+    supports_account_tag: bool,
+    supports_account_notify: bool,
+    supports_extended_join: bool,
+    supports_search: bool,
+    supports_multiline: bool,
+    supports_message_tags: bool,
+    search_requested_from: Option<Buffer>,
+    awaiting_login_cloak: bool,
+    logged_in_account: Option<String>,
     highlight_blackout: HighlightBlackout,
     registration_required_channels: Vec<String>,
     isupport: HashMap<isupport::Kind, isupport::Parameter>,
+    presence: Option<presence::Backend>,
+    last_sent: HashMap<String, String>,
+    help_requested: Option<Option<String>>,
+    help: Option<(String, Vec<String>)>,
+    links: Vec<message::Link>,
+    requested_umodes: Option<String>,
+    umodes_sent: bool,
+    sent_to_users: HashMap<Nick, (String, Instant)>,
+    queued_for_users: HashMap<Nick, Vec<String>>,
+    /// When users last told us they read their query with us
+    read_receipts: HashMap<Nick, DateTime<Utc>>,
+    /// Latest messages of users which we haven't told them we read yet
+    unread_from_users: HashMap<Nick, Option<String>>,
+    channel_list: Option<channel_list::Cached>,
+    /// Channels of a full `LIST` being received
+    listing: Option<Vec<channel_list::Entry>>,
+    list_stats: Option<channel_list::Stats>,
 }
 
 impl fmt::Debug for Client {
@@ -102,6 +187,7 @@ impl Client {
         server: Server,
         config: config::Server,
         mut sender: mpsc::Sender<proto::Message>,
+        channel_list: Option<channel_list::Cached>,
     ) -> Self {
         // Begin registration
         let _ = sender.try_send(command!("CAP", "LS", "302"));
@@ -133,15 +219,60 @@ impl Client {
             batches: HashMap::new(),
             reroute_responses_to: None,
             registration_step,
-            listed_caps: vec![],
+            listed_caps: capability::Listed::default(),
             supports_labels: false,
             supports_away_notify: false,
+            supports_account_tag: false,
+            supports_account_notify: false,
+            supports_extended_join: false,
+            supports_search: false,
+            supports_multiline: false,
+            supports_message_tags: false,
+            search_requested_from: None,
+            awaiting_login_cloak: false,
+            logged_in_account: None,
             highlight_blackout: HighlightBlackout::Blackout(Instant::now()),
             registration_required_channels: vec![],
             isupport: HashMap::new(),
+            presence: None,
+            last_sent: HashMap::new(),
+            help_requested: None,
+            help: None,
+            links: vec![],
+            requested_umodes: None,
+            umodes_sent: false,
+            sent_to_users: HashMap::new(),
+            queued_for_users: HashMap::new(),
+            read_receipts: HashMap::new(),
+            unread_from_users: HashMap::new(),
+            channel_list,
+            listing: None,
+            list_stats: None,
+        }
+    }
+
+    /// Applies changes staged while disconnected, before registration completes
+    fn apply_staged(&mut self, staged: Staged) {
+        for channel in staged.channels {
+            if !self.config.channels.contains(&channel) {
+                self.config.channels.push(channel);
+            }
         }
     }
 
+    /// Starts tracking whether `nick` is online, if the server supports it
+    fn monitor(&mut self, nick: &Nick) -> bool {
+        let Some(backend) = self.presence else {
+            return false;
+        };
+
+        for message in backend.add(std::slice::from_ref(nick)) {
+            let _ = self.handle.try_send(message);
+        }
+
+        true
+    }
+
     fn quit(&mut self, reason: Option<String>) {
         if let Err(e) = if let Some(reason) = reason {
             self.handle.try_send(command!("QUIT", reason))
@@ -165,6 +296,11 @@ impl Client {
     }
 
     fn send(&mut self, buffer: &Buffer, mut message: message::Encoded) {
+        // Client-only tags, like `+draft/reply`, need `message-tags`
+        if !self.supports_message_tags {
+            message.tags.retain(|tag| !tag.key.starts_with('+'));
+        }
+
         if self.supports_labels {
             use proto::Tag;
 
@@ -174,14 +310,43 @@ impl Client {
             self.labels.insert(label.clone(), context);
 
             // IRC: Encode tags
-            message.tags = vec![Tag {
+            message.tags.push(Tag {
                 key: "label".to_string(),
                 value: Some(label),
-            }];
+            });
         }
 
         self.reroute_responses_to = start_reroute(&message.command).then(|| buffer.clone());
 
+        if is_search(&message.command) {
+            self.search_requested_from = Some(buffer.clone());
+        }
+
+        if let Command::HELP(topic) = &message.command {
+            self.help_requested = Some(topic.clone());
+        }
+
+        // Only unfiltered lists replace the cached one
+        if let Command::LIST(None, None) = &message.command {
+            self.listing = Some(vec![]);
+        }
+
+        if let Command::LIST(_, _) = &message.command {
+            self.list_stats = Some(channel_list::Stats::default());
+        }
+
+        // Kept so it can be handed back if the channel refuses it, or the user is offline
+        if let Command::PRIVMSG(target, text) = &message.command {
+            if !text.starts_with('\u{1}') {
+                if proto::is_channel(target) {
+                    self.last_sent.insert(target.clone(), text.clone());
+                } else {
+                    self.sent_to_users
+                        .insert(Nick::from(target.as_str()), (text.clone(), Instant::now()));
+                }
+            }
+        }
+
         if let Err(e) = self.handle.try_send(message.into()) {
             log::warn!("Error sending message: {e}");
         }
@@ -225,6 +390,14 @@ impl Client {
                 })
         });
 
+        // Replies to a WHOIS are rerouted to where it was sent from below, but still tell us
+        // which account a user is logged into
+        if let Command::Numeric(RPL_WHOISACCOUNT, args) = &message.command {
+            if let (Some(nick), Some(account)) = (args.get(1), args.get(2)) {
+                self.update_user_account(NickRef::from(nick.as_str()), Account::parse(account));
+            }
+        }
+
         match &message.command {
             Command::BATCH(batch, ..) => {
                 let mut chars = batch.chars();
@@ -233,11 +406,31 @@ impl Client {
 
                 match symbol {
                     '+' => {
-                        let batch = Batch::new(context);
+                        let mut batch = Batch::new(context);
+
+                        if let Command::BATCH(_, params) = &message.command {
+                            batch.is_search =
+                                params.first().is_some_and(|kind| kind == SEARCH_BATCH_TYPE);
+                        }
+
                         self.batches.insert(reference, batch);
                     }
                     '-' => {
                         if let Some(finished) = self.batches.remove(&reference) {
+                            if finished.is_search {
+                                let buffer = finished
+                                    .context
+                                    .map(Context::buffer)
+                                    .or_else(|| self.search_requested_from.take())
+                                    .unwrap_or_else(|| Buffer::Server(self.server.clone()));
+
+                                return Some(vec![Event::SearchResults(
+                                    buffer,
+                                    finished.search_results,
+                                    self.nickname().to_owned(),
+                                )]);
+                            }
+
                             // If nested, extend events into parent batch
                             if let Some(parent) = batch_tag
                                 .as_ref()
@@ -254,6 +447,18 @@ impl Client {
 
                 return None;
             }
+            // Search hits are collected instead of being recorded as new messages
+            _ if batch_tag
+                .as_ref()
+                .and_then(|batch| self.batches.get(batch))
+                .is_some_and(|batch| batch.is_search) =>
+            {
+                if let Some(batch) = batch_tag.and_then(|batch| self.batches.get_mut(&batch)) {
+                    batch.search_results.push(message);
+                }
+
+                return None;
+            }
             _ if batch_tag.is_some() => {
                 let events = self.handle(message, context)?;
 
@@ -299,13 +504,13 @@ impl Client {
                     (None, None) | (None, Some(_)) => return None,
                 };
 
-                self.listed_caps.extend(caps.split(' ').map(String::from));
+                self.listed_caps.extend(caps);
 
                 // Finished
                 if asterisk.is_none() {
                     let mut requested = vec![];
 
-                    let contains = |s| self.listed_caps.iter().any(|cap| cap == s);
+                    let contains = |s| self.listed_caps.contains(s);
 
                     if contains("invite-notify") {
                         requested.push("invite-notify");
@@ -316,6 +521,15 @@ impl Client {
                     if contains("away-notify") {
                         requested.push("away-notify");
                     }
+                    if contains("account-tag") {
+                        requested.push("account-tag");
+                    }
+                    if contains("account-notify") {
+                        requested.push("account-notify");
+                    }
+                    if contains("extended-join") {
+                        requested.push("extended-join");
+                    }
                     if contains("message-tags") {
                         requested.push("message-tags");
                     }
@@ -333,12 +547,22 @@ impl Client {
                             requested.push("echo-message");
                         }
                     }
-                    if self.listed_caps.iter().any(|cap| cap.starts_with("sasl")) {
+                    if contains("sasl") && self.sasl().is_some() {
                         requested.push("sasl");
                     }
                     if contains("multi-prefix") {
                         requested.push("multi-prefix");
                     }
+                    if contains("chghost") {
+                        requested.push("chghost");
+                    }
+                    if contains(SEARCH_CAP) {
+                        requested.push(SEARCH_CAP);
+                    }
+                    // Multiline messages are sent in batches
+                    if contains("batch") && contains(capability::MULTILINE) {
+                        requested.push(capability::MULTILINE);
+                    }
 
                     if !requested.is_empty() {
                         // Request
@@ -350,6 +574,10 @@ impl Client {
                         // If none requested, end negotiation
                         self.registration_step = RegistrationStep::End;
                         let _ = self.handle.try_send(command!("CAP", "END"));
+
+                        return self
+                            .sasl_unavailable(self.sasl_unsupported_reason(), &message)
+                            .map(|event| vec![event]);
                     }
                 }
             }
@@ -365,17 +593,42 @@ impl Client {
                 if caps.contains(&"away-notify") {
                     self.supports_away_notify = true;
                 }
+                if caps.contains(&"account-tag") {
+                    self.supports_account_tag = true;
+                }
+                if caps.contains(&"account-notify") {
+                    self.supports_account_notify = true;
+                }
+                if caps.contains(&"extended-join") {
+                    self.supports_extended_join = true;
+                }
+                if caps.contains(&SEARCH_CAP) {
+                    self.supports_search = true;
+                }
+                if caps.contains(&"message-tags") {
+                    self.supports_message_tags = true;
+                }
+                if caps.contains(&capability::MULTILINE) {
+                    self.supports_multiline = true;
+                }
 
                 let supports_sasl = caps.iter().any(|cap| cap.contains("sasl"));
 
-                if let Some(sasl) = self.config.sasl.as_ref().filter(|_| supports_sasl) {
+                let mechanism = self
+                    .sasl()
+                    .filter(|_| supports_sasl)
+                    .map(|sasl| sasl.command());
+
+                if let Some(mechanism) = mechanism {
                     self.registration_step = RegistrationStep::Sasl;
-                    let _ = self
-                        .handle
-                        .try_send(command!("AUTHENTICATE", sasl.command()));
+                    let _ = self.handle.try_send(command!("AUTHENTICATE", mechanism));
                 } else {
                     self.registration_step = RegistrationStep::End;
                     let _ = self.handle.try_send(command!("CAP", "END"));
+
+                    return self
+                        .sasl_unavailable(self.sasl_unsupported_reason(), &message)
+                        .map(|event| vec![event]);
                 }
             }
             Command::CAP(_, sub, a, b) if sub == "NAK" => {
@@ -386,18 +639,27 @@ impl Client {
                 if self.registration_step < RegistrationStep::Sasl {
                     self.registration_step = RegistrationStep::End;
                     let _ = self.handle.try_send(command!("CAP", "END"));
+
+                    // The whole request is refused, sasl included
+                    if caps.split(' ').any(|cap| cap == "sasl") {
+                        return self
+                            .sasl_unavailable("server refused the SASL capability", &message)
+                            .map(|event| vec![event]);
+                    }
                 }
             }
             Command::CAP(_, sub, a, b) if sub == "NEW" => {
                 let caps = if b.is_none() { a.as_ref() } else { b.as_ref() }?;
 
-                let new_caps = caps.split(' ').map(String::from).collect::<Vec<String>>();
+                let new_caps = capability::parse(caps)
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>();
 
                 let mut requested = vec![];
 
-                let newly_contains = |s| new_caps.iter().any(|cap| cap == s);
+                let newly_contains = |s| new_caps.contains(&s);
 
-                let contains = |s| self.listed_caps.iter().any(|cap| cap == s);
+                let contains = |s| self.listed_caps.contains(s);
 
                 if newly_contains("invite-notify") {
                     requested.push("invite-notify");
@@ -408,6 +670,15 @@ impl Client {
                 if newly_contains("away-notify") {
                     requested.push("away-notify");
                 }
+                if newly_contains("account-tag") {
+                    requested.push("account-tag");
+                }
+                if newly_contains("account-notify") {
+                    requested.push("account-notify");
+                }
+                if newly_contains("extended-join") {
+                    requested.push("extended-join");
+                }
                 if newly_contains("message-tags") {
                     requested.push("message-tags");
                 }
@@ -430,6 +701,17 @@ impl Client {
                 if newly_contains("multi-prefix") {
                     requested.push("multi-prefix");
                 }
+                if newly_contains("chghost") {
+                    requested.push("chghost");
+                }
+                if newly_contains(SEARCH_CAP) {
+                    requested.push(SEARCH_CAP);
+                }
+                if (contains("batch") || newly_contains("batch"))
+                    && newly_contains(capability::MULTILINE)
+                {
+                    requested.push(capability::MULTILINE);
+                }
 
                 if !requested.is_empty() {
                     // Request
@@ -438,7 +720,7 @@ impl Client {
                         .try_send(command!("CAP", "REQ", requested.join(" ")));
                 }
 
-                self.listed_caps.extend(new_caps);
+                self.listed_caps.extend(caps);
             }
             Command::CAP(_, sub, a, b) if sub == "DEL" => {
                 let caps = if b.is_none() { a.as_ref() } else { b.as_ref() }?;
@@ -451,9 +733,26 @@ impl Client {
                 if del_caps.contains(&"away-notify") {
                     self.supports_away_notify = false;
                 }
+                if del_caps.contains(&"account-tag") {
+                    self.supports_account_tag = false;
+                }
+                if del_caps.contains(&"account-notify") {
+                    self.supports_account_notify = false;
+                }
+                if del_caps.contains(&"extended-join") {
+                    self.supports_extended_join = false;
+                }
+                if del_caps.contains(&SEARCH_CAP) {
+                    self.supports_search = false;
+                }
+                if del_caps.contains(&"message-tags") {
+                    self.supports_message_tags = false;
+                }
+                if del_caps.contains(&capability::MULTILINE) {
+                    self.supports_multiline = false;
+                }
 
-                self.listed_caps
-                    .retain(|cap| !del_caps.iter().any(|del_cap| del_cap == cap));
+                self.listed_caps.remove(caps);
             }
             Command::AUTHENTICATE(param) if param == "+" => {
                 if let Some(sasl) = self.config.sasl.as_ref() {
@@ -464,22 +763,65 @@ impl Client {
                     let _ = self.handle.try_send(command!("CAP", "END"));
                 }
             }
-            Command::Numeric(RPL_LOGGEDIN, _) => {
+            Command::Numeric(RPL_LOGGEDIN, args) => {
                 log::info!("[{}] logged in", self.server);
 
+                self.logged_in_account = args.get(2).cloned();
+
+                // Servers commonly apply an account cloak right after login
+                self.awaiting_login_cloak = true;
+
                 if !self.registration_required_channels.is_empty() {
-                    for message in group_joins(
-                        &self.registration_required_channels,
-                        &self.config.channel_keys,
-                    ) {
+                    for message in self.joins(&self.registration_required_channels) {
                         let _ = self.handle.try_send(message);
                     }
 
                     self.registration_required_channels.clear();
                 }
             }
+            Command::Numeric(RPL_LOGGEDOUT, _) => {
+                log::info!("[{}] logged out", self.server);
+
+                self.logged_in_account = None;
+            }
+            Command::Numeric(RPL_SASLSUCCESS, _) => {
+                let sasl = self.config.sasl.as_ref()?;
+
+                return Some(vec![Event::Broadcast(Broadcast::SaslAuthenticated {
+                    mechanism: sasl.command(),
+                    account: self.logged_in_account.clone(),
+                    sent_time: server_time(&message),
+                })]);
+            }
+            Command::Numeric(
+                ERR_NICKLOCKED | ERR_SASLFAIL | ERR_SASLTOOLONG | ERR_SASLABORTED,
+                args,
+            ) => {
+                let sasl = self.config.sasl.as_ref()?;
+                let reason = args.last()?.clone();
+
+                log::warn!("[{}] sasl auth failed: {reason}", self.server);
+
+                return Some(vec![Event::Broadcast(Broadcast::SaslFailed {
+                    mechanism: sasl.command(),
+                    reason,
+                    sent_time: server_time(&message),
+                })]);
+            }
             Command::PRIVMSG(channel, text) | Command::NOTICE(channel, text) => {
                 if let Some(user) = message.user() {
+                    // Without the tag, the user isn't logged in
+                    if self.supports_account_tag {
+                        let account = message
+                            .tags
+                            .iter()
+                            .find(|tag| tag.key == "account")
+                            .and_then(|tag| tag.value.as_deref())
+                            .map_or(Account::LoggedOut, Account::parse);
+
+                        self.update_user_account(user.nickname(), account);
+                    }
+
                     if let Some(command) = dcc::decode(text) {
                         match command {
                             dcc::Command::Send(request) => {
@@ -552,24 +894,50 @@ impl Client {
                     sent_time: server_time(&message),
                 })]);
             }
+            Command::CHGHOST(new_username, new_hostname) => {
+                let old_user = message.user()?;
+                let ourself = self.nickname() == old_user.nickname();
+                let logged_in = ourself && std::mem::take(&mut self.awaiting_login_cloak);
+
+                self.chanmap.values_mut().for_each(|channel| {
+                    if let Some(user) = channel.users.take(&old_user) {
+                        channel.users.insert(user.with_username_and_hostname(
+                            new_username.clone(),
+                            new_hostname.clone(),
+                        ));
+                    }
+                });
+
+                let channels = self.user_channels(old_user.nickname());
+
+                return Some(vec![Event::Broadcast(Broadcast::ChangeHost {
+                    old_user,
+                    new_username: new_username.clone(),
+                    new_hostname: new_hostname.clone(),
+                    ourself,
+                    logged_in,
+                    channels,
+                    sent_time: server_time(&message),
+                })]);
+            }
             Command::Numeric(ERR_NICKNAMEINUSE | ERR_ERRONEUSNICKNAME, _)
                 if self.resolved_nick.is_none() =>
             {
-                // Try alt nicks
-                match &mut self.alt_nick {
-                    Some(index) => {
-                        if *index == self.config.alt_nicks.len() - 1 {
-                            self.alt_nick = None;
-                        } else {
-                            *index += 1;
-                        }
-                    }
-                    None if !self.config.alt_nicks.is_empty() => self.alt_nick = Some(0),
-                    None => {}
-                }
+                // Try alt nicks, then variations of our nickname
+                let index = self.alt_nick.map_or(0, |index| index + 1);
+                self.alt_nick = Some(index);
+
+                let nick = match self.config.alt_nicks.get(index) {
+                    Some(nick) => Some(nick.clone()),
+                    None => self.fallback_nick(index - self.config.alt_nicks.len() + 1),
+                };
 
-                if let Some(nick) = self.alt_nick.and_then(|i| self.config.alt_nicks.get(i)) {
-                    let _ = self.handle.try_send(command!("NICK", nick));
+                // The reply is shown in the server buffer, where a nick can be set with /nick
+                match nick {
+                    Some(nick) => {
+                        let _ = self.handle.try_send(command!("NICK", nick));
+                    }
+                    None => log::warn!("[{}] no nicknames left to try", self.server),
                 }
             }
             Command::Numeric(RPL_WELCOME, args) => {
@@ -625,14 +993,24 @@ impl Client {
                     };
                 }
 
-                // Send user modestring
-                if let Some(modestring) = self.config.umodes.as_ref() {
-                    let _ = self.handle.try_send(command!("MODE", nick, modestring));
+                // Reclaim our nickname, e.g. by having NickServ release it first
+                if nick != &self.config.nickname && !self.config.nick_reclaim_commands.is_empty() {
+                    for command in self.config.nick_reclaim_commands.iter() {
+                        if let Ok(cmd) = crate::command::parse(command, None, &self.isupport) {
+                            if let Ok(command) = proto::Command::try_from(cmd) {
+                                let _ = self.handle.try_send(command.into());
+                            };
+                        };
+                    }
+
+                    let _ = self
+                        .handle
+                        .try_send(command!("NICK", &self.config.nickname));
                 }
 
                 // Loop on connect commands
                 for command in self.config.on_connect.iter() {
-                    if let Ok(cmd) = crate::command::parse(command, None) {
+                    if let Ok(cmd) = crate::command::parse(command, None, &self.isupport) {
                         if let Ok(command) = proto::Command::try_from(cmd) {
                             let _ = self.handle.try_send(command.into());
                         };
@@ -640,7 +1018,7 @@ impl Client {
                 }
 
                 // Send JOIN
-                for message in group_joins(&self.config.channels, &self.config.channel_keys) {
+                for message in self.joins(&self.config.channels) {
                     let _ = self.handle.try_send(message);
                 }
             }
@@ -670,30 +1048,24 @@ impl Client {
                     channel.users.remove(&user);
                 }
             }
-            Command::JOIN(channel, _) => {
-                let user = message.user()?;
+            Command::JOIN(channel, account) => {
+                let mut user = message.user()?;
+
+                // `extended-join` adds the account, or `*` when not logged in
+                if self.supports_extended_join {
+                    if let Some(account) = account {
+                        user.update_account(Account::parse(account));
+                    }
+                }
 
                 if user.nickname() == self.nickname() {
                     self.chanmap.insert(channel.clone(), Channel::default());
 
-                    if let Some(state) = self.chanmap.get_mut(channel) {
-                        // Sends WHO to get away state on users.
-                        if self.isupport.contains_key(&isupport::Kind::WHOX) {
-                            let _ = self.handle.try_send(command!(
-                                "WHO",
-                                channel,
-                                "tcnf",
-                                isupport::WHO_POLL_TOKEN.to_owned()
-                            ));
-                            state.last_who = Some(WhoStatus::Requested(
-                                Instant::now(),
-                                Some(isupport::WHO_POLL_TOKEN),
-                            ));
-                        } else {
-                            let _ = self.handle.try_send(command!("WHO", channel));
-                            state.last_who = Some(WhoStatus::Requested(Instant::now(), None));
-                        }
-                        log::debug!("[{}] {channel} - WHO requested", self.server);
+                    // Sends WHO to get away state on users.
+                    self.request_who(channel);
+
+                    if self.config.request_channel_modes {
+                        let _ = self.handle.try_send(command!("MODE", channel));
                     }
                 } else if let Some(channel) = self.chanmap.get_mut(channel) {
                     channel.users.insert(user);
@@ -734,6 +1106,10 @@ impl Client {
                     if let Some(channel) = self.chanmap.get_mut(target) {
                         channel.update_user_away(args.get(3)?, args.get(4)?);
 
+                        if let Some(account) = args.get(5) {
+                            channel.update_user_account(args.get(3)?, Account::parse(account));
+                        }
+
                         if let Ok(token) = args.get(1)?.parse::<isupport::WhoToken>() {
                             if let Some(WhoStatus::Requested(_, Some(request_token))) =
                                 channel.last_who
@@ -880,6 +1256,257 @@ impl Client {
                 #[cfg(feature = "dev")]
                 return None;
             }
+            // Help is collected until it ends, so it can be shown as one block
+            Command::Numeric(RPL_HELPSTART, args) => {
+                let topic = args.get(1)?;
+
+                self.help = Some((topic.clone(), args.get(2).into_iter().cloned().collect()));
+
+                return None;
+            }
+            Command::Numeric(RPL_HELPTXT, args) => {
+                if let Some((_, lines)) = &mut self.help {
+                    lines.extend(args.get(2).cloned());
+
+                    return None;
+                }
+            }
+            Command::Numeric(RPL_ENDOFHELP, args) => {
+                self.help_requested = None;
+
+                if let Some((topic, mut lines)) = self.help.take() {
+                    lines.extend(args.get(2).cloned());
+
+                    return Some(vec![Event::Help(topic, lines)]);
+                }
+            }
+            // Servers without HELP often provide HELPOP instead
+            Command::Numeric(ERR_UNKNOWNCOMMAND, args)
+                if args
+                    .get(1)
+                    .is_some_and(|command| command.eq_ignore_ascii_case("HELP")) =>
+            {
+                if let Some(topic) = self.help_requested.take() {
+                    let _ = self
+                        .handle
+                        .try_send(proto::command("HELPOP", topic.into_iter().collect()));
+
+                    return None;
+                }
+            }
+            Command::Numeric(ERR_HELPNOTFOUND, _) => {
+                self.help_requested = None;
+            }
+            // User modes are set once the server has listed those it supports
+            Command::Numeric(RPL_MYINFO, args) => {
+                let mut events = self.send_umodes(args.get(3).map(String::as_str));
+
+                events.push(Event::Single(message, self.nickname().to_owned()));
+
+                return Some(events);
+            }
+            Command::Numeric(ERR_UMODEUNKNOWNFLAG, args) => {
+                if let Some(requested) = self.requested_umodes.take() {
+                    let reason = args.last().cloned().unwrap_or_default();
+
+                    log::warn!(
+                        "[{}] user modes {requested} rejected: {reason}",
+                        self.server
+                    );
+
+                    return Some(vec![Event::UserModesNotSet(requested, reason)]);
+                }
+            }
+            // Only the echo of our own MODE answers it, not modes set on us by the server
+            Command::MODE(target, Some(modes), _)
+                if target == self.nickname().as_ref()
+                    && self.requested_umodes.is_some()
+                    && message
+                        .user()
+                        .is_some_and(|user| user.nickname() == self.nickname()) =>
+            {
+                let unapplied = self
+                    .requested_umodes
+                    .take()
+                    .map(|requested| mode::unapplied_user_modes(&requested, modes))
+                    .unwrap_or_default();
+
+                if !unapplied.is_empty() {
+                    log::warn!("[{}] user modes {unapplied} not applied", self.server);
+
+                    return Some(vec![
+                        Event::Single(message, self.nickname().to_owned()),
+                        Event::UserModesNotSet(unapplied, "not applied by the server".to_string()),
+                    ]);
+                }
+            }
+            // `account-notify`, with `*` when logging out
+            Command::Unknown(command, args) if command == "ACCOUNT" => {
+                let user = message.user()?;
+                let account = args.first()?;
+
+                self.update_user_account(user.nickname(), Account::parse(account));
+
+                return None;
+            }
+            // Read receipts from users in our queries
+            Command::TAGMSG(target) => {
+                let user = message.user()?;
+
+                if target == self.nickname().as_ref()
+                    && message.tags.iter().any(|tag| tag.key == READ_RECEIPT_TAG)
+                {
+                    self.read_receipts
+                        .insert(user.nickname().to_owned(), server_time(&message));
+                }
+
+                return None;
+            }
+            // Servers which are full may point us at another one, as
+            // `<client> <hostname> <port> :<info>`
+            Command::Numeric(RPL_BOUNCE, args) => {
+                let redirect = args
+                    .get(1)
+                    .cloned()
+                    .zip(args.get(2).and_then(|port| port.parse::<u16>().ok()));
+
+                if let Some((host, port)) = redirect {
+                    let info = args.get(3).cloned().unwrap_or_default();
+
+                    return Some(vec![
+                        Event::Single(message, self.nickname().to_owned()),
+                        Event::Redirect(host, port, info),
+                    ]);
+                }
+            }
+            // Links are collected until they end, so they can be shown as one tree
+            Command::Numeric(RPL_LINKS, args) => {
+                let server = args.get(1)?;
+                let hub = args.get(2)?;
+                let (hops, info) = args
+                    .get(3)
+                    .map(|text| text.split_once(' ').unwrap_or((text, "")))
+                    .unwrap_or_default();
+
+                self.links.push(message::Link {
+                    server: server.clone(),
+                    hub: hub.clone(),
+                    hops: hops.parse().unwrap_or_default(),
+                    info: info.to_string(),
+                });
+
+                return None;
+            }
+            Command::Numeric(RPL_ENDOFLINKS, _) => {
+                return Some(vec![Event::Links(std::mem::take(&mut self.links))]);
+            }
+            Command::Numeric(RPL_LISTSTART, _) => {
+                // Also covers lists we didn't request ourselves, e.g. from a bouncer
+                self.list_stats = Some(channel_list::Stats::default());
+            }
+            Command::Numeric(RPL_LIST, args) => {
+                let users = args.get(2).and_then(|n| n.parse().ok()).unwrap_or_default();
+
+                if let Some(stats) = &mut self.list_stats {
+                    stats.add(users);
+                }
+
+                if let Some(listing) = &mut self.listing {
+                    let channel = args.get(1)?;
+
+                    listing.push(channel_list::Entry {
+                        channel: channel.clone(),
+                        users,
+                        topic: args.get(3).cloned().unwrap_or_default(),
+                    });
+                }
+            }
+            Command::Numeric(RPL_LISTEND, _) => {
+                let mut events = vec![Event::Single(message, self.nickname().to_owned())];
+
+                if let Some(stats) = self.list_stats.take() {
+                    events.push(Event::ListStats(stats));
+                }
+
+                if let Some(listing) = self.listing.take() {
+                    let cached =
+                        channel_list::Cached::new(listing, self.config.channel_list_max_entries);
+
+                    self.channel_list = Some(cached.clone());
+
+                    events.push(Event::ChannelList(cached));
+                }
+
+                return Some(events);
+            }
+            Command::Numeric(RPL_LUSERCHANNELS, args) => {
+                let count = args.get(1).and_then(|count| count.parse().ok());
+
+                if let (Some(cached), Some(count)) = (&self.channel_list, count) {
+                    if cached.is_outdated(count) {
+                        log::debug!("[{}] cached channel list is outdated", self.server);
+                        self.channel_list = None;
+                    }
+                }
+            }
+            Command::Numeric(RPL_CHANNELMODEIS, args) => {
+                let channel = args.get(1)?;
+
+                if let Some(state) = self.chanmap.get_mut(channel) {
+                    state.modes = Some(args[2..].join(" "));
+                }
+            }
+            Command::Numeric(RPL_BANLIST, args) => {
+                let channel = args.get(1)?;
+                let mask = args.get(2)?;
+
+                if let Some(BanList::Requested(bans)) =
+                    self.chanmap.get_mut(channel).map(|state| &mut state.bans)
+                {
+                    bans.push(mask.clone());
+                    return None;
+                }
+            }
+            Command::Numeric(RPL_ENDOFBANLIST, args) => {
+                let channel = args.get(1)?;
+
+                if let Some(state) = self.chanmap.get_mut(channel) {
+                    if let BanList::Requested(bans) = &mut state.bans {
+                        state.bans = BanList::Received(std::mem::take(bans));
+                        return None;
+                    }
+                }
+            }
+            // Some servers only list bans to operators, so the list we asked for won't come
+            Command::Numeric(ERR_CHANOPRIVSNEEDED, args) => {
+                let channel = args.get(1)?;
+
+                if let Some(state) = self.chanmap.get_mut(channel) {
+                    if let BanList::Requested(_) = state.bans {
+                        state.bans = BanList::Refused;
+                    }
+                }
+            }
+            Command::Numeric(ERR_CANNOTSENDTOCHAN, args) => {
+                let channel = args.get(1)?.clone();
+
+                if let Some(text) = self.last_sent.remove(&channel) {
+                    return Some(vec![
+                        Event::Single(message, self.nickname().to_owned()),
+                        Event::Unsent(Buffer::Channel(self.server.clone(), channel), text),
+                    ]);
+                }
+            }
+            // A message to a user who is offline is reported in their query
+            Command::Numeric(ERR_NOSUCHNICK, args) => {
+                let nick = Nick::from(args.get(1)?.as_str());
+
+                if let Some((text, sent_at)) = self.sent_to_users.remove(&nick) {
+                    if sent_at.elapsed() < UNDELIVERED_REPLY_TIMEOUT {
+                        return Some(vec![Event::Undelivered(nick, text)]);
+                    }
+                }
+            }
             Command::Numeric(ERR_NOCHANMODES, args) => {
                 let channel = args.get(1)?;
 
@@ -947,8 +1574,66 @@ impl Client {
 
                 return None;
             }
-            Command::TAGMSG(_) => {
-                return None;
+            Command::Numeric(RPL_ENDOFMOTD | ERR_NOMOTD, _) => {
+                // A cloak for logging in during registration would have been applied by now
+                self.awaiting_login_cloak = false;
+
+                // For servers which didn't send RPL_MYINFO, without knowing the modes they support
+                let mut events = self.send_umodes(None);
+
+                // ISUPPORT is complete once the MOTD is, so presence tracking can start
+                if self.presence.is_none() {
+                    self.presence = presence::Backend::from_isupport(&self.isupport);
+
+                    if let Some(backend) = self.presence {
+                        let nicks = self
+                            .config
+                            .monitor
+                            .iter()
+                            .map(|nick| Nick::from(nick.as_str()))
+                            .collect::<Vec<_>>();
+
+                        for message in backend.add(&nicks) {
+                            let _ = self.handle.try_send(message);
+                        }
+                    }
+                }
+
+                events.push(Event::Single(message, self.nickname().to_owned()));
+
+                return Some(events);
+            }
+            Command::Numeric(
+                RPL_MONONLINE | RPL_MONOFFLINE | RPL_LOGON | RPL_LOGOFF | RPL_NOWON | RPL_NOWOFF,
+                _,
+            ) => {
+                let update = presence::Update::parse(&message.command)?;
+                let mut delivered = vec![];
+
+                // Messages queued for users while they were offline
+                if let presence::Update::Online(users) = &update {
+                    for user in users {
+                        let nick = user.nickname().to_owned();
+
+                        if let Some(texts) = self.queued_for_users.remove(&nick) {
+                            for text in &texts {
+                                let _ = self.handle.try_send(command!(
+                                    "PRIVMSG",
+                                    nick.to_string(),
+                                    text
+                                ));
+                            }
+
+                            delivered.push(Event::Delivered(nick, texts.len()));
+                        }
+                    }
+                }
+
+                return Some(
+                    std::iter::once(Event::Presence(update))
+                        .chain(delivered)
+                        .collect(),
+                );
             }
             _ => {}
         }
@@ -956,8 +1641,127 @@ impl Client {
         Some(vec![Event::Single(message, self.nickname().to_owned())])
     }
 
+    /// The configured SASL mechanism, unless the server lists its mechanisms without it
+    fn sasl(&self) -> Option<&config::server::Sasl> {
+        let sasl = self.config.sasl.as_ref()?;
+        let mut mechanisms = self.listed_caps.values("sasl").peekable();
+
+        // Servers needn't list their mechanisms
+        (mechanisms.peek().is_none() || mechanisms.any(|listed| listed == sasl.command()))
+            .then_some(sasl)
+    }
+
+    fn sasl_unsupported_reason(&self) -> &'static str {
+        if self.listed_caps.contains("sasl") {
+            "server does not list the configured SASL mechanism"
+        } else {
+            "server does not support SASL"
+        }
+    }
+
+    /// Reports that SASL won't be attempted, when it's configured
+    fn sasl_unavailable(&self, reason: &str, message: &message::Encoded) -> Option<Event> {
+        let sasl = self.config.sasl.as_ref()?;
+
+        log::warn!("[{}] sasl unavailable: {reason}", self.server);
+
+        Some(Event::Broadcast(Broadcast::SaslFailed {
+            mechanism: sasl.command(),
+            reason: reason.to_string(),
+            sent_time: server_time(message),
+        }))
+    }
+
+    /// Sets the user modes of the config, once per connection. Modes missing from `available`
+    /// aren't sent
+    fn send_umodes(&mut self, available: Option<&str>) -> Vec<Event> {
+        let mut events = vec![];
+
+        if std::mem::replace(&mut self.umodes_sent, true) {
+            return events;
+        }
+
+        let Some(modestring) = self.config.connect_umodes() else {
+            return events;
+        };
+
+        let (supported, unsupported) = match available {
+            Some(available) => mode::supported_user_modes(&modestring, available),
+            None => (modestring, String::new()),
+        };
+
+        if !supported.is_empty() {
+            let _ = self
+                .handle
+                .try_send(command!("MODE", self.nickname().as_ref(), &supported));
+            self.requested_umodes = Some(supported);
+        }
+
+        if !unsupported.is_empty() {
+            log::warn!("[{}] user modes {unsupported} not supported", self.server);
+
+            events.push(Event::UserModesNotSet(
+                unsupported,
+                "not supported by the server".to_string(),
+            ));
+        }
+
+        events
+    }
+
+    /// The `attempt`th variation of our nickname, shortened to fit `NICKLEN`
+    fn fallback_nick(&self, attempt: usize) -> Option<String> {
+        if attempt > MAX_NICK_FALLBACKS {
+            return None;
+        }
+
+        let suffix = match self.config.nick_in_use {
+            config::server::NickInUse::None => return None,
+            config::server::NickInUse::Underscore => "_".repeat(attempt),
+            config::server::NickInUse::Suffix => attempt.to_string(),
+        };
+
+        let max_len = match self.isupport.get(&isupport::Kind::NICKLEN) {
+            Some(isupport::Parameter::NICKLEN(len)) => usize::from(*len),
+            _ => DEFAULT_NICKLEN,
+        };
+
+        let nick = self
+            .config
+            .nickname
+            .chars()
+            .take(max_len.saturating_sub(suffix.len()))
+            .collect::<String>();
+
+        (!nick.is_empty()).then(|| format!("{nick}{suffix}"))
+    }
+
+    /// JOIN messages for configured `channels`, with their keys
+    fn joins(&self, channels: &[String]) -> Vec<proto::Message> {
+        match self.config.channel_order {
+            config::server::ChannelOrder::Alphabetical => {
+                group_joins(channels, &self.config.channel_keys).collect()
+            }
+            config::server::ChannelOrder::Config => {
+                ordered_joins(channels, &self.config.channel_keys)
+            }
+        }
+    }
+
     fn sync(&mut self) {
         self.channels = self.chanmap.keys().cloned().collect();
+
+        if let config::server::ChannelOrder::Config = self.config.channel_order {
+            // Stable, so channels which aren't configured stay sorted after the others
+            self.channels.sort_by_key(|channel| {
+                self.config
+                    .channels
+                    .iter()
+                    .position(|configured| configured.eq_ignore_ascii_case(channel))
+                    .unwrap_or(usize::MAX)
+            });
+        }
+
         self.users = self
             .chanmap
             .iter()
@@ -978,6 +1782,112 @@ impl Client {
         self.chanmap.get(channel).map(|channel| &channel.topic)
     }
 
+    fn modes<'a>(&'a self, channel: &str) -> Option<&'a str> {
+        self.chanmap.get(channel)?.modes.as_deref()
+    }
+
+    fn request_who(&mut self, channel: &str) {
+        if let Some(state) = self.chanmap.get_mut(channel) {
+            if self.isupport.contains_key(&isupport::Kind::WHOX) {
+                let _ = self.handle.try_send(command!(
+                    "WHO",
+                    channel,
+                    "tcnfa",
+                    isupport::WHO_POLL_TOKEN.to_owned()
+                ));
+                state.last_who = Some(WhoStatus::Requested(
+                    Instant::now(),
+                    Some(isupport::WHO_POLL_TOKEN),
+                ));
+            } else {
+                let _ = self.handle.try_send(command!("WHO", channel));
+                state.last_who = Some(WhoStatus::Requested(Instant::now(), None));
+            }
+            log::debug!("[{}] {channel} - WHO requested", self.server);
+        }
+    }
+
+    /// Tells `nick` we've read their messages, up to `id` when known
+    fn send_read_receipt(&mut self, nick: &Nick, id: Option<String>) {
+        self.unread_from_users.remove(nick);
+
+        // Client-only tags need `message-tags`
+        if !self.supports_message_tags {
+            return;
+        }
+
+        let mut message = command!("TAGMSG", nick.as_ref());
+        message.tags = vec![proto::Tag {
+            key: READ_RECEIPT_TAG.to_string(),
+            value: id,
+        }];
+
+        if let Err(e) = self.handle.try_send(message) {
+            log::warn!("Error sending read receipt: {e}");
+        }
+    }
+
+    /// Rebuilds the members of `channel` from a fresh NAMES and WHO
+    fn resync(&mut self, channel: &str) {
+        let Some(state) = self.chanmap.get_mut(channel) else {
+            return;
+        };
+
+        state.users.clear();
+        // Replies aren't shown, as when joining
+        state.names_init = false;
+
+        let _ = self.handle.try_send(command!("NAMES", channel));
+        self.request_who(channel);
+
+        log::debug!("[{}] {channel} - resync requested", self.server);
+    }
+
+    /// Key to join `channel` with, either configured or from its `+k` mode
+    fn channel_key(&self, channel: &str) -> Option<String> {
+        if let Some(key) = self.config.channel_keys.get(channel) {
+            return Some(key.clone());
+        }
+
+        let mut modes = self.modes(channel)?.split_whitespace();
+        let modestring = modes.next()?;
+        let args = modes.map(String::from).collect::<Vec<_>>();
+
+        mode::parse::<mode::Channel>(modestring, &args)
+            .into_iter()
+            .find_map(|mode| match mode {
+                mode::Mode::Add(mode::Channel::Key, key) => key,
+                _ => None,
+            })
+    }
+
+    fn bans<'a>(&'a self, channel: &str) -> Option<&'a [String]> {
+        match &self.chanmap.get(channel)?.bans {
+            BanList::Received(bans) => Some(bans),
+            _ => None,
+        }
+    }
+
+    /// Requests the ban list of a joined channel once, if configured to
+    fn request_ban_list(&mut self, channel: &str) {
+        if !self.config.request_ban_lists {
+            return;
+        }
+
+        if let Some(state) = self.chanmap.get_mut(channel) {
+            if let BanList::NotRequested = state.bans {
+                let _ = self.handle.try_send(command!("MODE", channel, "+b"));
+                state.bans = BanList::Requested(vec![]);
+            }
+        }
+    }
+
+    fn update_user_account(&mut self, nick: NickRef, account: Account) {
+        for channel in self.chanmap.values_mut() {
+            channel.update_user_account(nick.as_ref(), account.clone());
+        }
+    }
+
     fn resolve_user_attributes<'a>(&'a self, channel: &str, user: &User) -> Option<&'a User> {
         self.chanmap
             .get(channel)
@@ -1003,8 +1913,65 @@ impl Client {
             .collect()
     }
 
+    pub fn capabilities(&self) -> &capability::Listed {
+        &self.listed_caps
+    }
+
+    fn multiline_limits(&self) -> Option<capability::MultilineLimits> {
+        self.listed_caps
+            .multiline_limits()
+            .filter(|_| self.supports_multiline)
+    }
+
+    /// Sends `lines` to `target` as `draft/multiline` batches, within the limits advertised by
+    /// the server
+    fn send_multiline(&mut self, buffer: &Buffer, target: &str, lines: &[String]) {
+        let Some(limits) = self.multiline_limits() else {
+            return;
+        };
+
+        for batch in limits.batches(lines) {
+            let reference = generate_label();
+
+            // The label goes on the opening BATCH, so our echoed lines are known as sent
+            self.send(
+                buffer,
+                command!(
+                    "BATCH",
+                    format!("+{reference}"),
+                    capability::MULTILINE,
+                    target
+                )
+                .into(),
+            );
+
+            for line in batch {
+                let mut message = command!("PRIVMSG", target, line.as_str());
+
+                message.tags.push(proto::Tag {
+                    key: "batch".to_string(),
+                    value: Some(reference.clone()),
+                });
+
+                let _ = self.handle.try_send(message);
+            }
+
+            let _ = self
+                .handle
+                .try_send(command!("BATCH", format!("-{reference}")));
+        }
+    }
+
+    /// The last full channel list, unless it has expired
+    pub fn channel_list(&self) -> Option<&channel_list::Cached> {
+        let ttl = Duration::from_secs(self.config.channel_list_ttl);
+
+        self.channel_list
+            .as_ref()
+            .filter(|cached| !cached.is_expired(ttl, Utc::now()))
+    }
+
     pub fn nickname(&self) -> NickRef {
-        // TODO: Fallback nicks
         NickRef::from(
             self.resolved_nick
                 .as_deref()
@@ -1022,6 +1989,9 @@ impl Client {
             HighlightBlackout::Receiving => {}
         }
 
+        self.sent_to_users
+            .retain(|_, (_, sent_at)| now.duration_since(*sent_at) < UNDELIVERED_REPLY_TIMEOUT);
+
         for (channel, state) in self.chanmap.iter_mut() {
             enum Request {
                 Poll,
@@ -1044,7 +2014,7 @@ impl Client {
                     let _ = self.handle.try_send(command!(
                         "WHO",
                         channel,
-                        "tcnf",
+                        "tcnfa",
                         isupport::WHO_POLL_TOKEN.to_owned()
                     ));
                     state.last_who = Some(WhoStatus::Requested(
@@ -1084,30 +2054,76 @@ impl HighlightBlackout {
 }
 
 #[derive(Debug, Default)]
-pub struct Map(BTreeMap<Server, State>);
+struct Staged {
+    channels: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Map {
+    clients: BTreeMap<Server, State>,
+    /// Changes requested while a server is disconnected
+    staged: BTreeMap<Server, Staged>,
+    staged_nicks: server::StagedNicks,
+    /// Users whose messages are dropped, kept across reconnects until restart
+    ignored: BTreeMap<Server, Vec<Nick>>,
+}
 
 impl Map {
     pub fn disconnected(&mut self, server: Server) {
-        self.0.insert(server, State::Disconnected);
+        self.clients.insert(server, State::Disconnected);
+    }
+
+    pub fn connection_failed(&mut self, server: &Server) {
+        if let Some(state) = self.clients.get_mut(server) {
+            *state = State::Failed;
+        }
     }
 
-    pub fn ready(&mut self, server: Server, client: Client) {
-        self.0.insert(server, State::Ready(client));
+    pub fn ready(&mut self, server: Server, mut client: Client) {
+        if let Some(staged) = self.staged.remove(&server) {
+            client.apply_staged(staged);
+        }
+
+        self.clients.insert(server, State::Ready(client));
+    }
+
+    /// Nickname to use once `server` reconnects
+    pub fn stage_nick(&mut self, server: &Server, nick: String) {
+        self.staged_nicks.stage(server, nick);
+    }
+
+    /// Shared with the streams, so they register with a staged nickname
+    pub fn staged_nicks(&self) -> server::StagedNicks {
+        self.staged_nicks.clone()
+    }
+
+    /// Channels to join once `server` reconnects
+    pub fn queue_joins(&mut self, server: &Server, channels: Vec<String>) {
+        let queued = &mut self.staged.entry(server.clone()).or_default().channels;
+
+        for channel in channels {
+            if !queued.contains(&channel) {
+                queued.push(channel);
+            }
+        }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.clients.is_empty()
     }
 
     pub fn remove(&mut self, server: &Server) -> Option<Client> {
-        self.0.remove(server).and_then(|state| match state {
-            State::Disconnected => None,
+        self.staged.remove(server);
+        self.staged_nicks.take(server);
+
+        self.clients.remove(server).and_then(|state| match state {
+            State::Disconnected | State::Failed => None,
             State::Ready(client) => Some(client),
         })
     }
 
     pub fn client(&self, server: &Server) -> Option<&Client> {
-        if let Some(State::Ready(client)) = self.0.get(server) {
+        if let Some(State::Ready(client)) = self.clients.get(server) {
             Some(client)
         } else {
             None
@@ -1115,7 +2131,7 @@ impl Map {
     }
 
     pub fn client_mut(&mut self, server: &Server) -> Option<&mut Client> {
-        if let Some(State::Ready(client)) = self.0.get_mut(server) {
+        if let Some(State::Ready(client)) = self.clients.get_mut(server) {
             Some(client)
         } else {
             None
@@ -1127,13 +2143,26 @@ impl Map {
     }
 
     pub fn receive(&mut self, server: &Server, message: message::Encoded) -> Vec<Event> {
+        let is_ignored = matches!(
+            message.command,
+            Command::PRIVMSG(..) | Command::NOTICE(..) | Command::TAGMSG(_)
+        ) && message.user().is_some_and(|user| {
+            self.get_ignored(server)
+                .iter()
+                .any(|nick| user.nickname() == *nick)
+        });
+
+        if is_ignored {
+            return vec![];
+        }
+
         self.client_mut(server)
             .map(|client| client.receive(message))
             .unwrap_or_default()
     }
 
     pub fn sync(&mut self, server: &Server) {
-        if let Some(State::Ready(client)) = self.0.get_mut(server) {
+        if let Some(State::Ready(client)) = self.clients.get_mut(server) {
             client.sync();
         }
     }
@@ -1150,6 +2179,35 @@ impl Map {
         }
     }
 
+    /// Joins `channel` again, with the key it was left with
+    pub fn rejoin(&mut self, server: &Server, channel: &str, key: Option<String>) {
+        if let Some(client) = self.client_mut(server) {
+            let channels = [channel.to_string()];
+            let keys = key
+                .map(|key| HashMap::from([(channel.to_string(), key)]))
+                .unwrap_or_default();
+
+            for message in group_joins(&channels, &keys) {
+                if let Err(e) = client.handle.try_send(message) {
+                    log::warn!("Error sending join: {e}");
+                }
+            }
+        }
+    }
+
+    /// Starts tracking whether `nick` is online, returning whether the server supports it
+    pub fn monitor(&mut self, server: &Server, nick: &Nick) -> bool {
+        self.client_mut(server)
+            .is_some_and(|client| client.monitor(nick))
+    }
+
+    /// Queues `text` to be sent to `nick` once they're back online
+    pub fn queue_for_online(&mut self, server: &Server, nick: Nick, text: String) {
+        if let Some(client) = self.client_mut(server) {
+            client.queued_for_users.entry(nick).or_default().push(text);
+        }
+    }
+
     pub fn quit(&mut self, server: &Server, reason: Option<String>) {
         if let Some(client) = self.client_mut(server) {
             client.quit(reason);
@@ -1166,6 +2224,24 @@ impl Map {
             .and_then(|client| client.resolve_user_attributes(channel, user))
     }
 
+    /// Ignores `nick`, or stops ignoring them
+    pub fn toggle_ignore(&mut self, server: &Server, nick: Nick) {
+        let ignored = self.ignored.entry(server.clone()).or_default();
+
+        if let Some(index) = ignored.iter().position(|ignored| *ignored == nick) {
+            ignored.remove(index);
+        } else {
+            ignored.push(nick);
+        }
+    }
+
+    pub fn get_ignored<'a>(&'a self, server: &Server) -> &'a [Nick] {
+        self.ignored
+            .get(server)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
     pub fn get_channel_users<'a>(&'a self, server: &Server, channel: &str) -> &'a [User] {
         self.client(server)
             .map(|client| client.users(channel))
@@ -1184,12 +2260,98 @@ impl Map {
             .unwrap_or_default()
     }
 
+    pub fn get_channel_modes<'a>(&'a self, server: &Server, channel: &str) -> Option<&'a str> {
+        self.client(server).and_then(|client| client.modes(channel))
+    }
+
+    /// Clears the members of `channel` and requests them again
+    pub fn resync(&mut self, server: &Server, channel: &str) {
+        if let Some(client) = self.client_mut(server) {
+            client.resync(channel);
+            client.sync();
+        }
+    }
+
+    pub fn send_read_receipt(&mut self, server: &Server, nick: &Nick, id: Option<String>) {
+        if let Some(client) = self.client_mut(server) {
+            client.send_read_receipt(nick, id);
+        }
+    }
+
+    /// Remembers a message of `nick` to send a read receipt for once their query is seen
+    pub fn mark_unread(&mut self, server: &Server, nick: &Nick, id: Option<String>) {
+        if let Some(client) = self.client_mut(server) {
+            client.unread_from_users.insert(nick.clone(), id);
+        }
+    }
+
+    /// Sends the read receipt held back for `nick`, if any
+    pub fn send_pending_read_receipt(&mut self, server: &Server, nick: &Nick) {
+        if let Some(client) = self.client_mut(server) {
+            if let Some(id) = client.unread_from_users.remove(nick) {
+                client.send_read_receipt(nick, id);
+            }
+        }
+    }
+
+    pub fn get_read_receipt(&self, server: &Server, nick: &Nick) -> Option<DateTime<Utc>> {
+        self.client(server)
+            .and_then(|client| client.read_receipts.get(nick).copied())
+    }
+
+    /// Whether tags of messages received from `server` should be kept for inspection
+    pub fn shows_tags(&self, server: &Server) -> bool {
+        self.client(server)
+            .is_some_and(|client| client.config.show_tags)
+    }
+
+    pub fn get_channel_key(&self, server: &Server, channel: &str) -> Option<String> {
+        self.client(server)
+            .and_then(|client| client.channel_key(channel))
+    }
+
+    pub fn get_channel_bans<'a>(&'a self, server: &Server, channel: &str) -> Option<&'a [String]> {
+        self.client(server).and_then(|client| client.bans(channel))
+    }
+
+    pub fn request_ban_list(&mut self, server: &Server, channel: &str) {
+        if let Some(client) = self.client_mut(server) {
+            client.request_ban_list(channel);
+        }
+    }
+
     pub fn get_channels<'a>(&'a self, server: &Server) -> &'a [String] {
         self.client(server)
             .map(|client| client.channels())
             .unwrap_or_default()
     }
 
+    pub fn supports_search(&self, server: &Server) -> bool {
+        self.client(server)
+            .map(|client| client.supports_search)
+            .unwrap_or_default()
+    }
+
+    pub fn supports_multiline(&self, server: &Server) -> bool {
+        self.client(server)
+            .and_then(Client::multiline_limits)
+            .is_some()
+    }
+
+    pub fn send_multiline(&mut self, buffer: &Buffer, target: &str, lines: &[String]) {
+        if let Some(client) = self.client_mut(buffer.server()) {
+            client.send_multiline(buffer, target, lines);
+        }
+    }
+
+    pub fn get_capabilities(&self, server: &Server) -> Option<&capability::Listed> {
+        self.client(server).map(Client::capabilities)
+    }
+
+    pub fn get_channel_list(&self, server: &Server) -> Option<&channel_list::Cached> {
+        self.client(server).and_then(Client::channel_list)
+    }
+
     pub fn get_isupport(&self, server: &Server) -> HashMap<isupport::Kind, isupport::Parameter> {
         self.client(server)
             .map(|client| client.isupport.clone())
@@ -1201,7 +2363,7 @@ impl Map {
     }
 
     pub fn connected_servers(&self) -> impl Iterator<Item = &Server> {
-        self.0.iter().filter_map(|(server, state)| {
+        self.clients.iter().filter_map(|(server, state)| {
             if let State::Ready(_) = state {
                 Some(server)
             } else {
@@ -1211,21 +2373,22 @@ impl Map {
     }
 
     pub fn iter(&self) -> std::collections::btree_map::Iter<Server, State> {
-        self.0.iter()
+        self.clients.iter()
     }
 
     pub fn status(&self, server: &Server) -> Status {
-        self.0
+        self.clients
             .get(server)
             .map(|s| match s {
                 State::Disconnected => Status::Disconnected,
+                State::Failed => Status::Failed,
                 State::Ready(_) => Status::Connected,
             })
             .unwrap_or(Status::Unavailable)
     }
 
     pub fn tick(&mut self, now: Instant) {
-        self.0.values_mut().for_each(|client| {
+        self.clients.values_mut().for_each(|client| {
             if let State::Ready(client) = client {
                 client.tick(now);
             }
@@ -1264,6 +2427,8 @@ impl Context {
 pub struct Batch {
     context: Option<Context>,
     events: Vec<Event>,
+    is_search: bool,
+    search_results: Vec<message::Encoded>,
 }
 
 impl Batch {
@@ -1271,6 +2436,8 @@ impl Batch {
         Self {
             context,
             events: vec![],
+            is_search: false,
+            search_results: vec![],
         }
     }
 }
@@ -1284,6 +2451,10 @@ fn remove_tag(key: &str, tags: &mut Vec<irc::proto::Tag>) -> Option<String> {
         .value
 }
 
+fn is_search(command: &Command) -> bool {
+    matches!(command, Command::Unknown(command, _) if command == "SEARCH")
+}
+
 fn start_reroute(command: &Command) -> bool {
     use Command::*;
 
@@ -1323,6 +2494,8 @@ pub struct Channel {
     pub last_who: Option<WhoStatus>,
     pub topic: Topic,
     pub names_init: bool,
+    pub modes: Option<String>,
+    pub bans: BanList,
 }
 
 impl Channel {
@@ -1343,6 +2516,26 @@ impl Channel {
             }
         }
     }
+
+    pub fn update_user_account(&mut self, user: &str, account: Account) {
+        let user = User::from(Nick::from(user));
+
+        if let Some(mut user) = self.users.take(&user) {
+            user.update_account(account);
+            self.users.insert(user);
+        }
+    }
+}
+
+/// Ban list requested on our behalf, which is collected instead of shown
+#[derive(Debug, Default)]
+pub enum BanList {
+    #[default]
+    NotRequested,
+    Requested(Vec<String>),
+    Received(Vec<String>),
+    /// The server didn't let us see the list, so it isn't requested again
+    Refused,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -1408,3 +2601,51 @@ fn group_joins<'a>(
 
     joins_without_keys.chain(joins_with_keys)
 }
+
+/// Group channels into JOIN messages, joining them in the order given
+fn ordered_joins(channels: &[String], keys: &HashMap<String, String>) -> Vec<proto::Message> {
+    const MAX_LEN: usize = proto::format::BYTE_LIMIT - b"JOIN \r\n".len();
+
+    let mut groups: Vec<Vec<(&String, Option<&String>)>> = vec![];
+    let mut len = 0;
+
+    for channel in channels {
+        let key = keys.get(channel);
+        let channel_len = channel.len() + key.map_or(1, |key| key.len() + 2);
+
+        // Keys apply to the leading channels of a JOIN, so a keyed channel
+        // can't follow one without a key in the same message
+        let starts_group = match groups.last() {
+            Some(group) => {
+                len + channel_len > MAX_LEN
+                    || (key.is_some() && group.iter().any(|(_, key)| key.is_none()))
+            }
+            None => true,
+        };
+
+        if starts_group {
+            groups.push(vec![]);
+            len = 0;
+        }
+
+        len += channel_len;
+
+        if let Some(group) = groups.last_mut() {
+            group.push((channel, key));
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|group| {
+            let channels = group.iter().map(|(channel, _)| channel).join(",");
+            let keys = group.iter().filter_map(|(_, key)| *key).join(",");
+
+            if keys.is_empty() {
+                command!("JOIN", channels)
+            } else {
+                command!("JOIN", channels, keys)
+            }
+        })
+        .collect()
+}
@@ -1,9 +1,12 @@
+use std::collections::HashMap;
 use std::str::FromStr;
 
 use irc::proto;
 use itertools::Itertools;
 
-use crate::Buffer;
+use crate::buffer::AppearancePart;
+use crate::theme::hex_to_color;
+use crate::{isupport, Buffer};
 
 #[derive(Debug, Clone, Copy)]
 pub enum Kind {
@@ -19,6 +22,12 @@ pub enum Kind {
     Kick,
     Mode,
     Raw,
+    Search,
+    ToChannel,
+    Help,
+    Resync,
+    Appearance,
+    Note,
 }
 
 impl FromStr for Kind {
@@ -38,6 +47,12 @@ impl FromStr for Kind {
             "kick" => Ok(Kind::Kick),
             "mode" => Ok(Kind::Mode),
             "raw" => Ok(Kind::Raw),
+            "search" => Ok(Kind::Search),
+            "tochannel" => Ok(Kind::ToChannel),
+            "help" => Ok(Kind::Help),
+            "resync" => Ok(Kind::Resync),
+            "appearance" => Ok(Kind::Appearance),
+            "note" => Ok(Kind::Note),
             _ => Err(()),
         }
     }
@@ -57,10 +72,23 @@ pub enum Command {
     Kick(String, String, Option<String>),
     Mode(String, Option<String>, Vec<String>),
     Raw(String, Vec<String>),
+    Search(Option<String>, String),
+    ToChannel(String, usize),
+    Help(Option<String>),
+    Resync(String),
+    Appearance(AppearancePart, Option<String>),
+    Note(String, Option<String>),
     Unknown(String, Vec<String>),
 }
 
-pub fn parse(s: &str, buffer: Option<&Buffer>) -> Result<Command, Error> {
+/// Number of query messages copied by `/tochannel` when no count is given
+const TO_CHANNEL_DEFAULT_COUNT: usize = 10;
+
+pub fn parse(
+    s: &str,
+    buffer: Option<&Buffer>,
+    isupport: &HashMap<isupport::Kind, isupport::Parameter>,
+) -> Result<Command, Error> {
     let (head, rest) = s.split_once('/').ok_or(Error::MissingSlash)?;
     // Don't allow leading whitespace before slash
     if !head.is_empty() {
@@ -85,7 +113,13 @@ pub fn parse(s: &str, buffer: Option<&Buffer>) -> Result<Command, Error> {
                 Command::Join(chanlist, chankeys)
             }),
             Kind::Motd => validated::<0, 1, false>(args, |_, [target]| Command::Motd(target)),
-            Kind::Nick => validated::<1, 0, false>(args, |[nick], _| Command::Nick(nick)),
+            Kind::Nick => {
+                if !args.is_empty() {
+                    validate_nick(&args.join(" "), isupport)?;
+                }
+
+                validated::<1, 0, false>(args, |[nick], _| Command::Nick(nick))
+            }
             Kind::Quit => validated::<0, 1, true>(args, |_, [comment]| Command::Quit(comment)),
             Kind::Msg => {
                 validated::<2, 0, true>(args, |[target, msg], []| Command::Msg(target, msg))
@@ -128,6 +162,66 @@ pub fn parse(s: &str, buffer: Option<&Buffer>) -> Result<Command, Error> {
                     args.iter().map(|s| s.to_string()).collect(),
                 ))
             }
+            Kind::Search => {
+                // Searching from the server buffer covers all buffers
+                let target = buffer.and_then(|b| b.target());
+
+                validated::<1, 0, true>(args, |[terms], _| Command::Search(target, terms))
+            }
+            Kind::ToChannel => {
+                // Only a query has a conversation to move
+                let Some(Buffer::Query(..)) = buffer else {
+                    return Ok(unknown());
+                };
+
+                let count = match args.get(1) {
+                    Some(count) => count
+                        .parse()
+                        .ok()
+                        .filter(|count| *count > 0)
+                        .ok_or_else(|| Error::InvalidCount(count.to_string()))?,
+                    None => TO_CHANNEL_DEFAULT_COUNT,
+                };
+
+                validated::<1, 1, false>(args, |[channel], _| Command::ToChannel(channel, count))
+            }
+            Kind::Help => validated::<0, 1, true>(args, |_, [topic]| Command::Help(topic)),
+            Kind::Resync => {
+                // Defaults to the channel it's sent from
+                let channel = buffer.and_then(|buffer| match buffer {
+                    Buffer::Channel(_, channel) => Some(channel.clone()),
+                    _ => None,
+                });
+
+                match channel {
+                    Some(channel) if args.is_empty() => Ok(Command::Resync(channel)),
+                    _ => validated::<1, 0, false>(args, |[channel], _| Command::Resync(channel)),
+                }
+            }
+            Kind::Appearance => match args.as_slice() {
+                [part, rest @ ..] if rest.len() <= 1 => {
+                    let part = part
+                        .parse()
+                        .map_err(|_| Error::InvalidAppearancePart(part.to_string()))?;
+
+                    // Leaving out the color resets it to the theme's
+                    let hex = match rest.first() {
+                        Some(hex) if hex_to_color(hex).is_none() => {
+                            return Err(Error::InvalidColor(hex.to_string()))
+                        }
+                        hex => hex.map(|hex| hex.to_string()),
+                    };
+
+                    Ok(Command::Appearance(part, hex))
+                }
+                _ => Err(Error::IncorrectArgCount {
+                    min: 1,
+                    max: 2,
+                    actual: args.len(),
+                }),
+            },
+            // Leaving out the note removes it
+            Kind::Note => validated::<1, 1, true>(args, |[nick], [note]| Command::Note(nick, note)),
         },
         Err(_) => Ok(unknown()),
     }
@@ -172,6 +266,47 @@ fn validated<const EXACT: usize, const OPT: usize, const TEXT: bool>(
     }
 }
 
+/// Catches nicknames the server would obviously reject with ERR_ERRONEUSNICKNAME.
+/// The server remains the final arbiter of what is allowed.
+fn validate_nick(
+    nick: &str,
+    isupport: &HashMap<isupport::Kind, isupport::Parameter>,
+) -> Result<(), NickError> {
+    const INVALID_CHARACTERS: [char; 6] = [',', '*', '?', '!', '@', '.'];
+
+    let channel_prefixes = match isupport.get(&isupport::Kind::CHANTYPES) {
+        Some(isupport::Parameter::CHANTYPES(Some(types))) => types.chars().collect(),
+        Some(isupport::Parameter::CHANTYPES(None)) => vec![],
+        _ => proto::CHANNEL_PREFIXES.to_vec(),
+    };
+
+    if nick.chars().any(char::is_whitespace) {
+        return Err(NickError::Whitespace);
+    }
+
+    if let Some(first) = nick.chars().next() {
+        if first.is_ascii_digit() || ['-', '$', ':'].contains(&first) {
+            return Err(NickError::InvalidStart(first));
+        }
+
+        if channel_prefixes.contains(&first) {
+            return Err(NickError::InvalidStart(first));
+        }
+    }
+
+    if let Some(invalid) = nick.chars().find(|c| INVALID_CHARACTERS.contains(c)) {
+        return Err(NickError::InvalidCharacter(invalid));
+    }
+
+    if let Some(isupport::Parameter::NICKLEN(max_len)) = isupport.get(&isupport::Kind::NICKLEN) {
+        if nick.chars().count() > *max_len as usize {
+            return Err(NickError::TooLong(*max_len));
+        }
+    }
+
+    Ok(())
+}
+
 impl TryFrom<Command> for proto::Command {
     type Error = ();
 
@@ -191,6 +326,30 @@ impl TryFrom<Command> for proto::Command {
             Command::Kick(channel, user, comment) => proto::Command::KICK(channel, user, comment),
             Command::Mode(channel, mode, users) => proto::Command::MODE(channel, mode, users),
             Command::Raw(command, args) => proto::Command::Unknown(command, args),
+            Command::Search(target, terms) => {
+                let attributes = target
+                    .map(|target| proto::Tag {
+                        key: "in".to_string(),
+                        value: Some(target),
+                    })
+                    .into_iter()
+                    .chain(Some(proto::Tag {
+                        key: "text".to_string(),
+                        value: Some(terms),
+                    }))
+                    .collect();
+
+                proto::Command::Unknown("SEARCH".to_string(), vec![proto::format::tags(attributes)])
+            }
+            // Sent as individual messages once the query history is read
+            Command::ToChannel(..) => return Err(()),
+            Command::Help(topic) => proto::Command::HELP(topic),
+            // Handled by the client, which requests the members again
+            Command::Resync(_) => return Err(()),
+            // Changes the buffer's settings
+            Command::Appearance(..) => return Err(()),
+            // Kept with the dashboard
+            Command::Note(..) => return Err(()),
             Command::Unknown(command, args) => proto::Command::new(&command, args),
         })
     }
@@ -208,6 +367,26 @@ pub enum Error {
     MissingSlash,
     #[error("missing command")]
     MissingCommand,
+    #[error("invalid message count '{0}'")]
+    InvalidCount(String),
+    #[error("unknown part '{0}', expected background, accent or nickname")]
+    InvalidAppearancePart(String),
+    #[error("invalid color '{0}', expected a hex color like #1e1e2e")]
+    InvalidColor(String),
+    #[error(transparent)]
+    InvalidNick(#[from] NickError),
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum NickError {
+    #[error("nickname cannot contain spaces")]
+    Whitespace,
+    #[error("nickname cannot start with '{0}'")]
+    InvalidStart(char),
+    #[error("nickname cannot contain '{0}'")]
+    InvalidCharacter(char),
+    #[error("nickname exceeds maximum length of {0} characters")]
+    TooLong(u16),
 }
 
 fn fmt_incorrect_arg_count(min: usize, max: usize, actual: usize) -> String {
@@ -219,3 +398,157 @@ fn fmt_incorrect_arg_count(min: usize, max: usize, actual: usize) -> String {
         format!("expected {min} to {max} arguments, recevied {actual}")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nick_validation() {
+        let isupport = HashMap::from([(isupport::Kind::NICKLEN, isupport::Parameter::NICKLEN(9))]);
+
+        let tests = [
+            ("halloy", Ok(())),
+            ("[away]`_", Ok(())),
+            ("h4lloy-", Ok(())),
+            ("hall oy", Err(NickError::Whitespace)),
+            ("1halloy", Err(NickError::InvalidStart('1'))),
+            ("-halloy", Err(NickError::InvalidStart('-'))),
+            ("#halloy", Err(NickError::InvalidStart('#'))),
+            ("hal@loy", Err(NickError::InvalidCharacter('@'))),
+            ("hal,loy", Err(NickError::InvalidCharacter(','))),
+            ("halloy1234", Err(NickError::TooLong(9))),
+        ];
+
+        for (nick, expected) in tests {
+            assert_eq!(validate_nick(nick, &isupport), expected, "{nick}");
+        }
+    }
+
+    #[test]
+    fn nick_validation_without_isupport() {
+        let isupport = HashMap::new();
+
+        assert_eq!(validate_nick("a_very_long_nickname", &isupport), Ok(()));
+        assert_eq!(
+            validate_nick("&halloy", &isupport),
+            Err(NickError::InvalidStart('&'))
+        );
+    }
+
+    #[test]
+    fn nick_validation_chantypes() {
+        let isupport = HashMap::from([(
+            isupport::Kind::CHANTYPES,
+            isupport::Parameter::CHANTYPES(Some("#".to_string())),
+        )]);
+
+        assert_eq!(validate_nick("&halloy", &isupport), Ok(()));
+        assert_eq!(
+            validate_nick("#halloy", &isupport),
+            Err(NickError::InvalidStart('#'))
+        );
+    }
+
+    #[test]
+    fn nick_command() {
+        let isupport = HashMap::new();
+
+        assert!(matches!(
+            parse("/nick halloy", None, &isupport),
+            Ok(Command::Nick(nick)) if nick == "halloy"
+        ));
+        assert!(matches!(
+            parse("/nick hal loy", None, &isupport),
+            Err(Error::InvalidNick(NickError::Whitespace))
+        ));
+        assert!(matches!(
+            parse("/nick", None, &isupport),
+            Err(Error::IncorrectArgCount { .. })
+        ));
+    }
+
+    #[test]
+    fn to_channel() {
+        let isupport = HashMap::new();
+        let query = Buffer::Query("server".into(), "halloy".to_string().into());
+        let channel = Buffer::Channel("server".into(), "#halloy".to_string());
+
+        assert!(matches!(
+            parse("/tochannel #halloy", Some(&query), &isupport),
+            Ok(Command::ToChannel(target, TO_CHANNEL_DEFAULT_COUNT)) if target == "#halloy"
+        ));
+        assert!(matches!(
+            parse("/tochannel #halloy 25", Some(&query), &isupport),
+            Ok(Command::ToChannel(_, 25))
+        ));
+        assert!(matches!(
+            parse("/tochannel #halloy 0", Some(&query), &isupport),
+            Err(Error::InvalidCount(_))
+        ));
+        assert!(matches!(
+            parse("/tochannel #halloy", Some(&channel), &isupport),
+            Ok(Command::Unknown(..))
+        ));
+    }
+
+    #[test]
+    fn resync() {
+        let isupport = HashMap::new();
+        let query = Buffer::Query("server".into(), "halloy".to_string().into());
+        let channel = Buffer::Channel("server".into(), "#halloy".to_string());
+
+        assert!(matches!(
+            parse("/resync", Some(&channel), &isupport),
+            Ok(Command::Resync(target)) if target == "#halloy"
+        ));
+        assert!(matches!(
+            parse("/resync #other", Some(&query), &isupport),
+            Ok(Command::Resync(target)) if target == "#other"
+        ));
+        assert!(matches!(
+            parse("/resync", Some(&query), &isupport),
+            Err(Error::IncorrectArgCount { .. })
+        ));
+    }
+
+    #[test]
+    fn appearance() {
+        let isupport = HashMap::new();
+
+        assert!(matches!(
+            parse("/appearance background #1e1e2e", None, &isupport),
+            Ok(Command::Appearance(AppearancePart::Background, Some(hex))) if hex == "#1e1e2e"
+        ));
+        assert!(matches!(
+            parse("/appearance nick", None, &isupport),
+            Ok(Command::Appearance(AppearancePart::Nickname, None))
+        ));
+        assert!(matches!(
+            parse("/appearance border #1e1e2e", None, &isupport),
+            Err(Error::InvalidAppearancePart(_))
+        ));
+        assert!(matches!(
+            parse("/appearance accent blue", None, &isupport),
+            Err(Error::InvalidColor(_))
+        ));
+    }
+
+    #[test]
+    fn note() {
+        let isupport = HashMap::new();
+
+        assert!(matches!(
+            parse("/note casper halloy dev", None, &isupport),
+            Ok(Command::Note(nick, Some(note))) if nick == "casper" && note == "halloy dev"
+        ));
+        assert!(matches!(
+            parse("/note casper", None, &isupport),
+            Ok(Command::Note(_, None))
+        ));
+        assert!(matches!(
+            parse("/note", None, &isupport),
+            Err(Error::IncorrectArgCount { .. })
+        ));
+    }
+}
@@ -13,11 +13,13 @@ pub use self::file_transfer::FileTransfer;
 pub use self::keys::Keyboard;
 pub use self::notification::{Notification, Notifications};
 pub use self::proxy::Proxy;
+pub use self::query::Query;
 pub use self::server::Server;
 pub use self::sidebar::Sidebar;
 use crate::environment::config_dir;
+use crate::rules::Rule;
 use crate::server::Map as ServerMap;
-use crate::theme::Palette;
+use crate::theme::{Palette, SidebarPalette};
 use crate::{environment, Theme};
 
 pub mod buffer;
@@ -25,12 +27,15 @@ pub mod channel;
 pub mod file_transfer;
 mod keys;
 pub mod notification;
+pub mod profile;
 pub mod proxy;
+pub mod query;
 pub mod server;
 pub mod sidebar;
 
 const CONFIG_TEMPLATE: &str = include_str!("../../config.toml");
 const DEFAULT_THEME_FILE_NAME: &str = "ferra.toml";
+const RULES_FILE_NAME: &str = "rules.toml";
 
 #[derive(Debug, Clone, Default)]
 pub struct Config {
@@ -45,6 +50,8 @@ pub struct Config {
     pub notifications: Notifications,
     pub file_transfer: FileTransfer,
     pub tooltips: bool,
+    pub confirm_join_threshold: usize,
+    pub rules: Vec<Rule>,
 }
 
 #[derive(Debug, Clone, Copy, Deserialize)]
@@ -139,6 +146,8 @@ impl Config {
             pub file_transfer: FileTransfer,
             #[serde(default = "default_tooltip")]
             pub tooltips: bool,
+            #[serde(default = "default_confirm_join_threshold")]
+            pub confirm_join_threshold: usize,
         }
 
         let path = Self::path();
@@ -156,11 +165,13 @@ impl Config {
             notifications,
             file_transfer,
             tooltips,
+            confirm_join_threshold,
         } = toml::from_str(content.as_ref()).map_err(|e| Error::Parse(e.to_string()))?;
 
         servers.read_password_files()?;
 
         let themes = Self::load_themes(&theme).unwrap_or_default();
+        let rules = Self::load_rules()?;
 
         Ok(Config {
             themes,
@@ -174,9 +185,32 @@ impl Config {
             notifications,
             file_transfer,
             tooltips,
+            confirm_join_threshold,
+            rules,
         })
     }
 
+    /// Rules are optional, in `rules.toml` next to the config
+    fn load_rules() -> Result<Vec<Rule>, Error> {
+        #[derive(Deserialize)]
+        pub struct Rules {
+            #[serde(default)]
+            pub rule: Vec<Rule>,
+        }
+
+        let path = Self::config_dir().join(RULES_FILE_NAME);
+
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| Error::Read(e.to_string()))?;
+        let Rules { rule } = toml::from_str(content.as_ref())
+            .map_err(|e| Error::Parse(format!("{RULES_FILE_NAME}: {e}")))?;
+
+        Ok(rule)
+    }
+
     fn load_themes(default_key: &str) -> Result<Themes, Error> {
         #[derive(Deserialize)]
         pub struct Data {
@@ -184,15 +218,20 @@ impl Config {
             pub name: String,
             #[serde(default)]
             pub palette: Palette,
+            #[serde(default)]
+            pub sidebar: SidebarPalette,
         }
 
         let read_entry = |entry: fs::DirEntry| {
             let content = fs::read_to_string(entry.path())?;
 
-            let Data { name, palette } =
-                toml::from_str(content.as_ref()).map_err(|e| Error::Parse(e.to_string()))?;
+            let Data {
+                name,
+                palette,
+                sidebar,
+            } = toml::from_str(content.as_ref()).map_err(|e| Error::Parse(e.to_string()))?;
 
-            Ok::<Theme, Error>(Theme::new(name, &palette))
+            Ok::<Theme, Error>(Theme::new(name, &palette, &sidebar))
         };
 
         let mut all = vec![];
@@ -231,6 +270,11 @@ impl Config {
         Ok(Themes { default, all })
     }
 
+    /// Whether joining this many channels at once should be confirmed first
+    pub fn confirm_join(&self, channels: usize) -> bool {
+        self.confirm_join_threshold > 0 && channels > self.confirm_join_threshold
+    }
+
     pub fn create_initial_config() {
         // Checks if a config file is there
         let config_file = Self::path();
@@ -283,6 +327,10 @@ fn default_tooltip() -> bool {
     true
 }
 
+fn default_confirm_join_threshold() -> usize {
+    3
+}
+
 #[derive(Debug, Error, Clone)]
 pub enum Error {
     #[error("config could not be read: {0}")]
@@ -1,9 +1,13 @@
+use std::borrow::Cow;
+
 use chrono::{DateTime, Local, Utc};
+use irc::proto::command::Numeric;
 use serde::Deserialize;
 
-use super::Channel;
+use super::{Channel, Query};
 use crate::{
-    buffer::{Color, Nickname, TextInput, Timestamp},
+    buffer::{Alignment, Color, Nickname, TextInput, Timestamp},
+    emoji,
     message::source,
 };
 
@@ -18,12 +22,32 @@ pub struct Buffer {
     #[serde(default)]
     pub channel: Channel,
     #[serde(default)]
+    pub query: Query,
+    #[serde(default)]
     pub server_messages: ServerMessages,
     #[serde(default)]
     pub internal_messages: InternalMessages,
+    #[serde(default)]
+    pub compact_server_buffer: CompactServerBuffer,
+    #[serde(default)]
+    pub focus_follows_mention: FocusFollowsMention,
+    #[serde(default)]
+    pub duplicate_messages: DuplicateMessages,
+    #[serde(default)]
+    pub emojis: Emojis,
+    #[serde(default)]
+    pub copy: CopyMessages,
+    #[serde(default)]
+    pub disconnected: Disconnected,
+    #[serde(default)]
+    pub leave: Leave,
+    #[serde(default)]
+    pub own_messages: OwnMessages,
+    #[serde(default)]
+    pub pastebin: Pastebin,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ServerMessages {
     #[serde(default)]
     pub topic: ServerMessage,
@@ -33,6 +57,8 @@ pub struct ServerMessages {
     pub part: ServerMessage,
     #[serde(default)]
     pub quit: ServerMessage,
+    #[serde(default = "default_change_host")]
+    pub change_host: ServerMessage,
 }
 
 impl ServerMessages {
@@ -42,6 +68,20 @@ impl ServerMessages {
             source::server::Kind::Part => Some(&self.part),
             source::server::Kind::Quit => Some(&self.quit),
             source::server::Kind::Join => Some(&self.join),
+            source::server::Kind::ChangeHost => Some(&self.change_host),
+            source::server::Kind::Numeric(_) => None,
+        }
+    }
+}
+
+impl Default for ServerMessages {
+    fn default() -> Self {
+        Self {
+            topic: ServerMessage::default(),
+            join: ServerMessage::default(),
+            part: ServerMessage::default(),
+            quit: ServerMessage::default(),
+            change_host: default_change_host(),
         }
     }
 }
@@ -106,6 +146,270 @@ impl Default for InternalMessage {
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompactServerBuffer {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_hidden_numerics")]
+    pub hidden_numerics: Vec<u16>,
+}
+
+impl Default for CompactServerBuffer {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hidden_numerics: default_hidden_numerics(),
+        }
+    }
+}
+
+impl CompactServerBuffer {
+    pub fn hides(&self, numeric: u16) -> bool {
+        self.hidden_numerics.contains(&numeric)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FocusFollowsMention {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Minimum seconds between two automatic focus changes
+    #[serde(default = "default_focus_cooldown")]
+    pub cooldown: u64,
+    /// Seconds without interacting with a buffer before focus may move
+    #[serde(default = "default_focus_idle")]
+    pub idle: u64,
+}
+
+impl Default for FocusFollowsMention {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cooldown: default_focus_cooldown(),
+            idle: default_focus_idle(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DuplicateMessages {
+    #[serde(default)]
+    pub collapse: bool,
+    /// Maximum seconds between two identical messages for them to be collapsed
+    #[serde(default = "default_duplicate_window")]
+    pub window: i64,
+}
+
+impl Default for DuplicateMessages {
+    fn default() -> Self {
+        Self {
+            collapse: false,
+            window: default_duplicate_window(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct Emojis {
+    #[serde(default)]
+    pub send: SendEmojis,
+    /// Show emojis in received messages as their `:shortcode:`
+    #[serde(default)]
+    pub show_shortcodes: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SendEmojis {
+    /// Replace `:shortcode:`s with their emoji
+    Unicode,
+    /// Replace emojis with their `:shortcode:`
+    Shortcode,
+    #[default]
+    AsIs,
+}
+
+impl SendEmojis {
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            SendEmojis::Unicode => emoji::replace_shortcodes(text),
+            SendEmojis::Shortcode => emoji::to_shortcodes(text),
+            SendEmojis::AsIs => Cow::Borrowed(text),
+        }
+    }
+}
+
+impl Emojis {
+    /// Text of a received message as it should be shown
+    pub fn display<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.show_shortcodes {
+            emoji::to_shortcodes(text)
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct CopyMessages {
+    /// Format of text copied with the copy shortcut
+    #[serde(default)]
+    pub format: CopyFormat,
+    /// Format of text copied with the copy shortcut while holding shift
+    #[serde(default = "default_shift_copy_format")]
+    pub shift_format: CopyFormat,
+}
+
+impl Default for CopyMessages {
+    fn default() -> Self {
+        Self {
+            format: CopyFormat::default(),
+            shift_format: default_shift_copy_format(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CopyFormat {
+    /// Exactly the selected text
+    #[default]
+    Selection,
+    /// Only the text of the selected messages
+    Text,
+    /// Text of the selected messages, with nicknames
+    Nicknames,
+    /// Text of the selected messages, with timestamps and nicknames
+    Full,
+    /// Like `Full`, as a markdown code block
+    Markdown,
+}
+
+fn default_shift_copy_format() -> CopyFormat {
+    CopyFormat::Full
+}
+
+/// Commands entered while a server is disconnected, which are applied once it reconnects
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Disconnected {
+    /// Use a nickname set with `/nick` on reconnect
+    #[serde(default = "default_bool_true")]
+    pub stage_nick: bool,
+    /// Join channels requested with `/join` on reconnect
+    #[serde(default)]
+    pub queue_joins: bool,
+}
+
+impl Default for Disconnected {
+    fn default() -> Self {
+        Self {
+            stage_nick: true,
+            queue_joins: false,
+        }
+    }
+}
+
+/// What happens when leaving a channel or closing a query
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Leave {
+    #[serde(default)]
+    pub mode: LeaveMode,
+    /// Time (seconds) a left buffer can be restored with the undo shortcut
+    #[serde(default = "default_undo_window")]
+    pub undo_window: u64,
+}
+
+impl Default for Leave {
+    fn default() -> Self {
+        Self {
+            mode: LeaveMode::default(),
+            undo_window: default_undo_window(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LeaveMode {
+    /// Leave immediately
+    #[default]
+    None,
+    /// Ask for confirmation before leaving
+    Confirm,
+    /// Leave immediately, but allow undoing it for a short while
+    Undo,
+}
+
+fn default_undo_window() -> u64 {
+    10
+}
+
+/// How our own messages are set apart from others
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct OwnMessages {
+    #[serde(default)]
+    pub style: OwnMessageStyle,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OwnMessageStyle {
+    /// Shown like any other message
+    #[default]
+    None,
+    /// Shown on a tinted background
+    Background,
+    /// Text shown in the accent color
+    Accent,
+}
+
+/// Where large pastes are uploaded instead of being sent line by line
+#[derive(Debug, Clone, Deserialize)]
+pub struct Pastebin {
+    /// Endpoint the paste is posted to, which responds with its link
+    pub url: Option<String>,
+    /// Sent as the `Authorization` header of the upload
+    pub auth: Option<String>,
+    /// Upload without asking first
+    #[serde(default)]
+    pub auto: bool,
+    /// Pastes with more lines than this are uploaded
+    #[serde(default = "default_pastebin_lines")]
+    pub lines: usize,
+    /// Pastes longer than this many bytes are uploaded
+    #[serde(default = "default_pastebin_bytes")]
+    pub bytes: usize,
+}
+
+impl Default for Pastebin {
+    fn default() -> Self {
+        Self {
+            url: None,
+            auth: None,
+            auto: false,
+            lines: default_pastebin_lines(),
+            bytes: default_pastebin_bytes(),
+        }
+    }
+}
+
+impl Pastebin {
+    /// Whether `text` should be uploaded rather than sent, leaving commands alone
+    pub fn exceeds(&self, text: &str) -> bool {
+        self.url.is_some()
+            && !text.starts_with('/')
+            && (text.lines().count() > self.lines || text.len() > self.bytes)
+    }
+}
+
+fn default_pastebin_lines() -> usize {
+    3
+}
+
+fn default_pastebin_bytes() -> usize {
+    400
+}
+
 #[derive(Debug, Copy, Clone, Default, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum UsernameFormat {
@@ -124,8 +428,18 @@ impl Default for Buffer {
             },
             text_input: Default::default(),
             channel: Channel::default(),
+            query: Query::default(),
             server_messages: Default::default(),
             internal_messages: Default::default(),
+            compact_server_buffer: Default::default(),
+            focus_follows_mention: Default::default(),
+            duplicate_messages: Default::default(),
+            emojis: Default::default(),
+            copy: Default::default(),
+            disconnected: Default::default(),
+            leave: Default::default(),
+            own_messages: Default::default(),
+            pastebin: Default::default(),
         }
     }
 }
@@ -136,17 +450,64 @@ impl Buffer {
             return None;
         }
 
-        Some(format!(
-            "{} ",
-            self.timestamp.brackets.format(
-                date_time
-                    .with_timezone(&Local)
-                    .format(&self.timestamp.format)
-            )
-        ))
+        let formatted = date_time
+            .with_timezone(&Local)
+            .format(&self.timestamp.format)
+            .to_string();
+
+        // Padded to the widest time, so timestamps line up when their width varies
+        let width = self.timestamp.width;
+        let aligned = match self.timestamp.alignment {
+            Some(Alignment::Left) => format!("{formatted:<width$}"),
+            Some(Alignment::Right) => format!("{formatted:>width$}"),
+            None => formatted,
+        };
+
+        Some(format!("{} ", self.timestamp.brackets.format(aligned)))
     }
 }
 
 fn default_bool_true() -> bool {
     true
 }
+
+/// Routine connection numerics which are hidden in a compact server buffer
+fn default_hidden_numerics() -> Vec<u16> {
+    use Numeric::*;
+
+    [
+        RPL_YOURHOST,
+        RPL_CREATED,
+        RPL_MYINFO,
+        RPL_LUSERCLIENT,
+        RPL_LUSEROP,
+        RPL_LUSERUNKNOWN,
+        RPL_LUSERCHANNELS,
+        RPL_LUSERME,
+        RPL_LOCALUSERS,
+        RPL_GLOBALUSERS,
+    ]
+    .into_iter()
+    .map(|numeric| numeric as u16)
+    .collect()
+}
+
+fn default_focus_cooldown() -> u64 {
+    30
+}
+
+fn default_focus_idle() -> u64 {
+    10
+}
+
+fn default_duplicate_window() -> i64 {
+    60
+}
+
+/// Host changes are hidden by default, since cloaking on login is common
+fn default_change_host() -> ServerMessage {
+    ServerMessage {
+        enabled: false,
+        ..Default::default()
+    }
+}
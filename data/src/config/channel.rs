@@ -1,7 +1,13 @@
-use serde::Deserialize;
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
 
 use crate::buffer::Color;
 use crate::channel::Position;
+use crate::math;
+use crate::message::{self, Message};
+use crate::user::{Account, NickRef};
 
 #[derive(Debug, Clone, Default, Deserialize)]
 pub struct Channel {
@@ -9,6 +15,18 @@ pub struct Channel {
     pub nicklist: Nicklist,
     #[serde(default)]
     pub topic: Topic,
+    #[serde(default)]
+    pub threads: Threads,
+    #[serde(default)]
+    pub cannot_send: CannotSend,
+    #[serde(default)]
+    pub relays: Vec<Relay>,
+    #[serde(default)]
+    pub math: Math,
+    #[serde(default)]
+    pub bot_commands: BotCommands,
+    #[serde(default)]
+    pub accounts: Accounts,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -37,6 +55,8 @@ pub struct Topic {
     pub enabled: bool,
     #[serde(default = "default_topic_banner_max_lines")]
     pub max_lines: u16,
+    #[serde(default)]
+    pub expandable: bool,
 }
 
 impl Default for Topic {
@@ -44,10 +64,246 @@ impl Default for Topic {
         Self {
             enabled: false,
             max_lines: default_topic_banner_max_lines(),
+            expandable: false,
+        }
+    }
+}
+
+/// Inline `$...$` math rendered as unicode
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Math {
+    /// Channels math is rendered in
+    #[serde(default)]
+    pub channels: Vec<String>,
+}
+
+impl Math {
+    /// Text of a message in `channel` as it should be shown
+    pub fn display<'a>(&self, channel: &str, text: Cow<'a, str>) -> Cow<'a, str> {
+        let enabled = self
+            .channels
+            .iter()
+            .any(|included| included.eq_ignore_ascii_case(channel));
+
+        if !enabled {
+            return text;
+        }
+
+        match math::render(&text) {
+            Cow::Borrowed(_) => text,
+            Cow::Owned(rendered) => Cow::Owned(rendered),
+        }
+    }
+}
+
+/// Commands sent to bots and the bots' replies, which can be collapsed so the conversation
+/// stands out
+#[derive(Debug, Clone, Deserialize)]
+pub struct BotCommands {
+    /// Whether bot commands start out collapsed in channels
+    #[serde(default)]
+    pub collapsed: bool,
+    /// Prefixes of bot commands, e.g. `!` in `!weather`
+    #[serde(default = "default_bot_command_prefixes")]
+    pub prefixes: Vec<String>,
+    /// Nicknames of bots which don't mark their messages with the `bot` tag
+    #[serde(default)]
+    pub nicks: Vec<String>,
+    /// Hide collapsed bot commands, rather than dim them
+    #[serde(default)]
+    pub hide: bool,
+}
+
+impl Default for BotCommands {
+    fn default() -> Self {
+        Self {
+            collapsed: false,
+            prefixes: default_bot_command_prefixes(),
+            nicks: vec![],
+            hide: false,
+        }
+    }
+}
+
+impl BotCommands {
+    /// Whether `message` is a command to a bot, or sent by one
+    pub fn matches(&self, message: &Message) -> bool {
+        let message::Source::User(user) = message.target.source() else {
+            return false;
+        };
+
+        let is_bot = message.bot
+            || self
+                .nicks
+                .iter()
+                .any(|nick| user.nickname().as_ref().eq_ignore_ascii_case(nick));
+
+        let is_command = self.prefixes.iter().any(|prefix| {
+            !prefix.is_empty()
+                && message
+                    .text
+                    .strip_prefix(prefix.as_str())
+                    .and_then(|command| command.chars().next())
+                    .is_some_and(char::is_alphanumeric)
+        });
+
+        is_bot || is_command
+    }
+}
+
+/// Marks message authors by the services account they're logged into, where the account
+/// is known from WHOIS, WHOX or the `account` tag
+#[derive(Debug, Clone, Deserialize)]
+pub struct Accounts {
+    /// Accounts whose messages are marked with `badge`
+    #[serde(default)]
+    pub trusted: Vec<String>,
+    #[serde(default = "default_account_badge")]
+    pub badge: String,
+    /// Channels where messages from users who aren't logged in are marked with `warning`
+    #[serde(default)]
+    pub required_in: Vec<String>,
+    #[serde(default = "default_account_warning")]
+    pub warning: String,
+}
+
+impl Default for Accounts {
+    fn default() -> Self {
+        Self {
+            trusted: vec![],
+            badge: default_account_badge(),
+            required_in: vec![],
+            warning: default_account_warning(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountMarker {
+    Trusted,
+    Warning,
+}
+
+impl Accounts {
+    /// Marker of a message from `nick`, who was logged into `account` when they sent it
+    pub fn marker(
+        &self,
+        nick: NickRef,
+        account: Option<&Account>,
+        channel: &str,
+    ) -> Option<AccountMarker> {
+        let is_trusted = |name: &str| {
+            self.trusted
+                .iter()
+                .any(|trusted| trusted.eq_ignore_ascii_case(name))
+        };
+
+        match account? {
+            Account::LoggedIn(account) if is_trusted(account) => Some(AccountMarker::Trusted),
+            // The nickname of a trusted account, used from another account
+            _ if is_trusted(nick.as_ref()) => Some(AccountMarker::Warning),
+            Account::LoggedOut
+                if self
+                    .required_in
+                    .iter()
+                    .any(|required| required.eq_ignore_ascii_case(channel)) =>
+            {
+                Some(AccountMarker::Warning)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn marker_text(&self, marker: AccountMarker) -> &str {
+        match marker {
+            AccountMarker::Trusted => &self.badge,
+            AccountMarker::Warning => &self.warning,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct Threads {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct CannotSend {
+    #[serde(default = "default_bool_true")]
+    pub restore_input: bool,
+    #[serde(default = "default_bool_true")]
+    pub explain: bool,
+}
+
+impl Default for CannotSend {
+    fn default() -> Self {
+        Self {
+            restore_input: true,
+            explain: true,
+        }
+    }
+}
+
+/// A bot relaying messages from a bridged network, with their author in the text
+#[derive(Debug, Clone, Deserialize)]
+pub struct Relay {
+    /// Nickname of the relaying bot
+    pub nick: String,
+    /// Channels the bot relays messages in, or every channel when empty
+    #[serde(default)]
+    pub channels: Vec<String>,
+    /// Pattern of relayed messages, with `nick` and `text` capture groups
+    #[serde(
+        default = "default_relay_pattern",
+        deserialize_with = "deserialize_regex"
+    )]
+    pub pattern: Regex,
+}
+
+impl Relay {
+    /// Author and text of a message sent by `nick` in `channel`, if relayed by this bot
+    pub fn author<'a>(
+        &self,
+        nick: &str,
+        channel: &str,
+        text: &'a str,
+    ) -> Option<(&'a str, &'a str)> {
+        let relays_in_channel = self.channels.is_empty()
+            || self
+                .channels
+                .iter()
+                .any(|relayed| relayed.eq_ignore_ascii_case(channel));
+
+        if !self.nick.eq_ignore_ascii_case(nick) || !relays_in_channel {
+            return None;
+        }
+
+        let captures = self.pattern.captures(text)?;
+
+        Some((
+            captures.name("nick")?.as_str(),
+            captures.name("text")?.as_str(),
+        ))
+    }
+}
+
+fn default_relay_pattern() -> Regex {
+    Regex::new(r"^[<\[](?P<nick>[^>\]\s]+)[>\]] (?P<text>.*)$").unwrap()
+}
+
+fn deserialize_regex<'de, D>(deserializer: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let pattern: String = Deserialize::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
+
+fn default_bot_command_prefixes() -> Vec<String> {
+    vec!["!".to_string()]
+}
+
 fn default_topic_banner_max_lines() -> u16 {
     2
 }
@@ -55,3 +311,11 @@ fn default_topic_banner_max_lines() -> u16 {
 fn default_bool_true() -> bool {
     true
 }
+
+fn default_account_badge() -> String {
+    "✓".to_string()
+}
+
+fn default_account_warning() -> String {
+    "!".to_string()
+}
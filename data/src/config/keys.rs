@@ -24,6 +24,8 @@ pub struct Keyboard {
     pub cycle_previous_buffer: KeyBind,
     #[serde(default = "KeyBind::leave_buffer")]
     pub leave_buffer: KeyBind,
+    #[serde(default = "KeyBind::undo_leave_buffer")]
+    pub undo_leave_buffer: KeyBind,
     #[serde(default = "KeyBind::toggle_nick_list")]
     pub toggle_nick_list: KeyBind,
     #[serde(default = "KeyBind::toggle_sidebar")]
@@ -47,6 +49,7 @@ impl Default for Keyboard {
             cycle_next_buffer: KeyBind::cycle_next_buffer(),
             cycle_previous_buffer: KeyBind::cycle_previous_buffer(),
             leave_buffer: KeyBind::leave_buffer(),
+            undo_leave_buffer: KeyBind::undo_leave_buffer(),
             toggle_nick_list: KeyBind::toggle_nick_list(),
             toggle_sidebar: KeyBind::toggle_sidebar(),
             command_bar: KeyBind::command_bar(),
@@ -70,6 +73,7 @@ impl Keyboard {
             shortcut(self.cycle_next_buffer.clone(), CycleNextBuffer),
             shortcut(self.cycle_previous_buffer.clone(), CyclePreviousBuffer),
             shortcut(self.leave_buffer.clone(), LeaveBuffer),
+            shortcut(self.undo_leave_buffer.clone(), UndoLeaveBuffer),
             shortcut(self.toggle_nick_list.clone(), ToggleNicklist),
             shortcut(self.toggle_sidebar.clone(), ToggleSidebar),
             shortcut(self.command_bar.clone(), CommandBar),
@@ -35,6 +35,112 @@ pub struct Notifications {
     pub highlight: Notification,
     #[serde(default)]
     pub file_transfer_request: Notification,
+    #[serde(default)]
+    pub monitored_online: Notification,
+    #[serde(default)]
+    pub highlight_flood: HighlightFlood,
+    #[serde(default)]
+    pub activity_resumed: ActivityResumed,
+    #[serde(default)]
+    pub unstable_connection: UnstableConnection,
+}
+
+/// Highlights are combined into a digest while more than `threshold` arrive within `window`
+#[derive(Debug, Clone, Deserialize)]
+pub struct HighlightFlood {
+    #[serde(default = "default_highlight_flood_threshold")]
+    pub threshold: usize,
+    /// Seconds
+    #[serde(default = "default_highlight_flood_window")]
+    pub window: u64,
+}
+
+impl Default for HighlightFlood {
+    fn default() -> Self {
+        Self {
+            threshold: default_highlight_flood_threshold(),
+            window: default_highlight_flood_window(),
+        }
+    }
+}
+
+/// A connection is unstable once it drops `threshold` times within `window`. Its disconnects
+/// are then notified once, and its reconnect only after it stays up for `stable`
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnstableConnection {
+    #[serde(default = "default_unstable_connection_threshold")]
+    pub threshold: usize,
+    /// Seconds
+    #[serde(default = "default_unstable_connection_window")]
+    pub window: u64,
+    /// Seconds
+    #[serde(default = "default_unstable_connection_stable")]
+    pub stable: u64,
+}
+
+impl Default for UnstableConnection {
+    fn default() -> Self {
+        Self {
+            threshold: default_unstable_connection_threshold(),
+            window: default_unstable_connection_window(),
+            stable: default_unstable_connection_stable(),
+        }
+    }
+}
+
+/// Notifies once when someone speaks in a channel which has been quiet for `quiet` seconds
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActivityResumed {
+    #[serde(flatten)]
+    pub notification: Notification,
+    /// Channels to notify for
+    #[serde(default)]
+    pub channels: Vec<String>,
+    /// Seconds
+    #[serde(default = "default_activity_quiet")]
+    pub quiet: u64,
+}
+
+impl ActivityResumed {
+    pub fn includes(&self, channel: &str) -> bool {
+        self.channels
+            .iter()
+            .any(|included| included.eq_ignore_ascii_case(channel))
+    }
+}
+
+impl Default for ActivityResumed {
+    fn default() -> Self {
+        Self {
+            notification: Notification::default(),
+            channels: vec![],
+            quiet: default_activity_quiet(),
+        }
+    }
+}
+
+fn default_activity_quiet() -> u64 {
+    3600
+}
+
+fn default_highlight_flood_threshold() -> usize {
+    5
+}
+
+fn default_highlight_flood_window() -> u64 {
+    30
+}
+
+fn default_unstable_connection_threshold() -> usize {
+    3
+}
+
+fn default_unstable_connection_window() -> u64 {
+    300
+}
+
+fn default_unstable_connection_stable() -> u64 {
+    60
 }
 
 fn default_sound() -> String {
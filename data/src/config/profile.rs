@@ -0,0 +1,201 @@
+//! Server profiles, the `[servers]` section of a config on its own, to share a setup or move
+//! it to another machine
+
+use std::fs;
+
+use toml::{Table, Value};
+
+use super::{Config, Error};
+
+/// Keys of a server which hold passwords, or paths to files which do. Commands are included,
+/// as they commonly identify with NickServ, e.g. `/msg NickServ IDENTIFY <password>`
+const SECRETS: &[&str] = &[
+    "password",
+    "password_file",
+    "nick_password",
+    "nick_password_file",
+    "channel_keys",
+    "sasl",
+    "on_connect",
+    "nick_reclaim_commands",
+];
+
+/// The servers of `config` as a profile. Secrets are left out unless `include_secrets` is set
+pub fn export(config: &str, include_secrets: bool) -> Result<String, Error> {
+    let mut servers = servers(config)?;
+
+    if !include_secrets {
+        for server in servers.values_mut() {
+            if let Value::Table(server) = server {
+                for key in SECRETS {
+                    server.remove(*key);
+                }
+            }
+        }
+    }
+
+    let header = if include_secrets {
+        "# Halloy server profile. It includes passwords, keep it private.\n\n"
+    } else {
+        "# Halloy server profile. Passwords, commands and other secrets were left out.\n\n"
+    };
+
+    let profile = Table::from_iter([("servers".to_string(), Value::Table(servers))]);
+    let content = toml::to_string(&profile).map_err(|e| Error::Parse(e.to_string()))?;
+
+    Ok(format!("{header}{content}"))
+}
+
+/// The servers a profile adds to the current config
+#[derive(Debug, Clone, Default)]
+pub struct Import {
+    added: Table,
+    /// Servers which are already configured the same way
+    pub unchanged: Vec<String>,
+    /// Servers which are configured differently, and are never replaced
+    pub conflicts: Vec<String>,
+}
+
+impl Import {
+    pub fn added(&self) -> impl Iterator<Item = &str> {
+        self.added.keys().map(String::as_str)
+    }
+}
+
+/// Validates `profile` against `config`, without changing it
+pub fn prepare(config: &str, profile: &str) -> Result<Import, Error> {
+    let existing = servers(config)?;
+
+    let mut import = Import::default();
+
+    for (name, server) in servers(profile)? {
+        server
+            .clone()
+            .try_into::<super::Server>()
+            .map_err(|e| Error::Parse(format!("server \"{name}\" is invalid: {e}")))?;
+
+        match existing.get(&name) {
+            Some(current) if *current == server => import.unchanged.push(name),
+            Some(_) => import.conflicts.push(name),
+            None => {
+                import.added.insert(name, server);
+            }
+        }
+    }
+
+    Ok(import)
+}
+
+/// Appends the added servers to the config file, leaving the rest of it as written
+pub fn apply(config: &str, import: &Import) -> Result<(), Error> {
+    if import.added.is_empty() {
+        return Ok(());
+    }
+
+    let servers = Table::from_iter([("servers".to_string(), Value::Table(import.added.clone()))]);
+    let addition = toml::to_string(&servers).map_err(|e| Error::Parse(e.to_string()))?;
+
+    let mut content = config.to_string();
+
+    if !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push('\n');
+    content.push_str(&addition);
+
+    // e.g. servers written as an inline table can't be extended with new sections
+    content
+        .parse::<Table>()
+        .map_err(|e| Error::Parse(format!("servers could not be added to the config: {e}")))?;
+
+    fs::write(Config::path(), content)?;
+
+    Ok(())
+}
+
+pub fn read_config() -> Result<String, Error> {
+    fs::read_to_string(Config::path()).map_err(|e| Error::Read(e.to_string()))
+}
+
+fn servers(content: &str) -> Result<Table, Error> {
+    let mut table = content
+        .parse::<Table>()
+        .map_err(|e| Error::Parse(e.to_string()))?;
+
+    match table.remove("servers") {
+        Some(Value::Table(servers)) => Ok(servers),
+        _ => Err(Error::Parse("no [servers] section".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+theme = "ferra"
+
+[servers.libera]
+nickname = "halloy"
+server = "irc.libera.chat"
+channels = ["#halloy"]
+nick_password = "hunter2"
+on_connect = ["/msg NickServ IDENTIFY halloy hunter2"]
+
+[servers.libera.sasl.plain]
+username = "halloy"
+password = "hunter2"
+"#;
+
+    #[test]
+    fn export_without_secrets() {
+        let profile = export(CONFIG, false).unwrap();
+
+        assert!(!profile.contains("hunter2"));
+        assert!(!profile.contains("sasl"));
+        assert!(!profile.contains("theme"));
+
+        let libera = &servers(&profile).unwrap()["libera"];
+
+        assert_eq!(libera["server"].as_str(), Some("irc.libera.chat"));
+        assert_eq!(libera["channels"][0].as_str(), Some("#halloy"));
+    }
+
+    #[test]
+    fn export_with_secrets() {
+        let profile = export(CONFIG, true).unwrap();
+
+        assert_eq!(servers(&profile).unwrap(), servers(CONFIG).unwrap());
+    }
+
+    #[test]
+    fn conflicts() {
+        let profile = r#"
+[servers.libera]
+nickname = "halloy"
+server = "irc.libera.chat"
+channels = ["#rust"]
+
+[servers.oftc]
+nickname = "halloy"
+server = "irc.oftc.net"
+"#;
+
+        let import = prepare(CONFIG, profile).unwrap();
+
+        assert_eq!(import.conflicts, vec!["libera".to_string()]);
+        assert!(import.unchanged.is_empty());
+        assert_eq!(import.added().collect::<Vec<_>>(), vec!["oftc"]);
+
+        let import = prepare(CONFIG, &export(CONFIG, true).unwrap()).unwrap();
+
+        assert_eq!(import.unchanged, vec!["libera".to_string()]);
+        assert!(import.conflicts.is_empty());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(prepare(CONFIG, "[servers.oftc]\nserver = \"irc.oftc.net\"\n").is_err());
+        assert!(prepare(CONFIG, "theme = \"ferra\"\n").is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use serde::Deserialize;
+
+use crate::user::Account;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Query {
+    #[serde(default)]
+    pub open_incoming: OpenIncoming,
+    #[serde(default)]
+    pub open_from: OpenFrom,
+    /// Services accounts of users whose queries are always opened, unless `open_from` is
+    /// `nobody`. Accounts rather than nicknames, as anyone can take a nickname
+    #[serde(default)]
+    pub contacts: Vec<String>,
+    #[serde(default)]
+    pub services: Services,
+    #[serde(default)]
+    pub offline: Offline,
+    #[serde(default)]
+    pub read_receipts: ReadReceipts,
+}
+
+/// How a query started by someone else is opened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OpenIncoming {
+    /// Only listed in the sidebar
+    #[default]
+    Sidebar,
+    /// Opened in a new pane, without moving focus
+    Background,
+    /// Opened in a new pane which takes focus
+    Focus,
+}
+
+/// Who may open a query in a pane; queries from anyone else are only listed in the sidebar
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OpenFrom {
+    #[default]
+    Everyone,
+    /// Users listed in `contacts`, or who share a channel with us
+    SharedChannel,
+    /// Only users listed in `contacts`
+    Contacts,
+    Nobody,
+}
+
+impl OpenFrom {
+    pub fn allows(self, is_contact: bool, shares_channel: impl FnOnce() -> bool) -> bool {
+        match self {
+            OpenFrom::Everyone => true,
+            OpenFrom::SharedChannel => is_contact || shares_channel(),
+            OpenFrom::Contacts => is_contact,
+            OpenFrom::Nobody => false,
+        }
+    }
+}
+
+/// Queries with network services, such as NickServ, which mostly send notices in reply to
+/// our own commands
+#[derive(Debug, Clone, Deserialize)]
+pub struct Services {
+    #[serde(default = "default_service_nicks")]
+    pub nicks: Vec<String>,
+    /// Overrides `buffer.query.open_incoming` for services
+    #[serde(default)]
+    pub open_incoming: Option<OpenIncoming>,
+}
+
+impl Default for Services {
+    fn default() -> Self {
+        Self {
+            nicks: default_service_nicks(),
+            open_incoming: None,
+        }
+    }
+}
+
+impl Query {
+    /// Whether the sender of a message, logged into `account` when they sent it, is a contact
+    pub fn is_contact(&self, account: Option<&Account>) -> bool {
+        let Some(Account::LoggedIn(account)) = account else {
+            return false;
+        };
+
+        self.contacts
+            .iter()
+            .any(|contact| contact.eq_ignore_ascii_case(account))
+    }
+
+    pub fn is_service(&self, nick: &str) -> bool {
+        self.services
+            .nicks
+            .iter()
+            .any(|service| service.eq_ignore_ascii_case(nick))
+    }
+}
+
+/// What happens when a message can't be delivered because the user is offline
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct Offline {
+    /// Track the user with `MONITOR` (or `WATCH`), to be notified when they're back online
+    #[serde(default)]
+    pub monitor: bool,
+    /// Send the message once the user is back online
+    #[serde(default)]
+    pub deliver_when_online: bool,
+}
+
+/// Read receipts exchanged with `+draft/read`, where the server supports message tags
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ReadReceipts {
+    /// Tell users when we've read the messages they sent us
+    #[serde(default)]
+    pub send: bool,
+    /// Show when users have read our messages
+    #[serde(default = "default_bool_true")]
+    pub show: bool,
+}
+
+impl Default for ReadReceipts {
+    fn default() -> Self {
+        Self {
+            send: false,
+            show: true,
+        }
+    }
+}
+
+fn default_bool_true() -> bool {
+    true
+}
+
+fn default_service_nicks() -> Vec<String> {
+    [
+        "NickServ", "ChanServ", "MemoServ", "OperServ", "HostServ", "BotServ", "SaslServ", "Global",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
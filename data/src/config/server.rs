@@ -39,6 +39,9 @@ pub struct Server {
     /// A mapping of channel names to keys for join-on-connect.
     #[serde(default)]
     pub channel_keys: HashMap<String, String>,
+    /// A list of nicknames to be notified about when they come online.
+    #[serde(default)]
+    pub monitor: Vec<String>,
     /// The amount of inactivity in seconds before the client will ping the server.
     #[serde(default = "default_ping_time")]
     pub ping_time: u64,
@@ -48,6 +51,14 @@ pub struct Server {
     /// The amount of time in seconds before attempting to reconnect to the server when disconnected.
     #[serde(default = "default_reconnect_delay")]
     pub reconnect_delay: u64,
+    /// Whether the connection is checked with a ping right away when the system appears to have
+    /// resumed from sleep.
+    #[serde(default = "default_check_on_resume")]
+    pub check_on_resume: bool,
+    /// The amount of time in seconds for a client to reconnect when the ping sent on resume isn't
+    /// answered.
+    #[serde(default = "default_resume_ping_timeout")]
+    pub resume_ping_timeout: u64,
     /// Whether the client should use NickServ GHOST to reclaim its primary nickname if it is in
     /// use. This has no effect if `nick_password` is not set.
     #[serde(default)]
@@ -58,8 +69,18 @@ pub struct Server {
     /// in that order.
     #[serde(default = "default_ghost_sequence")]
     pub ghost_sequence: Vec<String>,
+    /// How a nickname is picked once `alt_nicks` are all in use.
+    #[serde(default)]
+    pub nick_in_use: NickInUse,
+    /// Commands which are executed when connected with another nickname than `nickname`, after
+    /// which `nickname` is requested again.
+    #[serde(default)]
+    pub nick_reclaim_commands: Vec<String>,
     /// User modestring to set on connect. Example: "+RB-x"
     pub umodes: Option<String>,
+    /// Set user mode +i on connect, hiding us from users who don't share a channel with us
+    #[serde(default)]
+    pub invisible: bool,
     /// Whether or not to use TLS.
     /// Clients will automatically panic if this is enabled without TLS support.
     #[serde(default = "default_use_tls")]
@@ -74,6 +95,25 @@ pub struct Server {
     /// Commands which are executed once connected.
     #[serde(default)]
     pub on_connect: Vec<String>,
+    /// Request the modes of channels when joining them.
+    #[serde(default)]
+    pub request_channel_modes: bool,
+    /// Request the ban lists of joined channels once their buffers are opened.
+    #[serde(default)]
+    pub request_ban_lists: bool,
+    /// Keep the tags of received messages so they can be inspected.
+    #[serde(default)]
+    pub show_tags: bool,
+    /// Order channels are joined in and listed in the sidebar.
+    #[serde(default)]
+    pub channel_order: ChannelOrder,
+    /// The amount of time in seconds a full channel list from `/list` is kept, to be shown
+    /// right away while a fresh list is requested.
+    #[serde(default = "default_channel_list_ttl")]
+    pub channel_list_ttl: u64,
+    /// The number of channels kept of a full channel list, the ones with the most users.
+    #[serde(default = "default_channel_list_max_entries")]
+    pub channel_list_max_entries: usize,
     /// WHO poll interval for servers without away-notify.
     #[serde(
         default = "default_who_poll_interval",
@@ -111,6 +151,19 @@ impl Server {
         }
     }
 
+    /// User modestring set once registered, including +i when invisible
+    pub fn connect_umodes(&self) -> Option<String> {
+        let umodes = self.umodes.as_deref().unwrap_or_default();
+
+        let umodes = match (self.invisible, umodes.strip_prefix('+')) {
+            (true, Some(added)) => format!("+i{added}"),
+            (true, None) => format!("+i{umodes}"),
+            (false, _) => umodes.to_string(),
+        };
+
+        (!umodes.is_empty()).then_some(umodes)
+    }
+
     pub fn connection(&self, proxy: Option<config::Proxy>) -> connection::Config {
         let security = if self.use_tls {
             connection::Security::Secured {
@@ -148,23 +201,57 @@ impl Default for Server {
             password_file: Default::default(),
             channels: Default::default(),
             channel_keys: Default::default(),
+            monitor: Default::default(),
             ping_time: default_ping_time(),
             ping_timeout: default_ping_timeout(),
             reconnect_delay: default_reconnect_delay(),
+            check_on_resume: default_check_on_resume(),
+            resume_ping_timeout: default_resume_ping_timeout(),
             should_ghost: Default::default(),
             ghost_sequence: default_ghost_sequence(),
+            nick_in_use: Default::default(),
+            nick_reclaim_commands: Default::default(),
             umodes: Default::default(),
+            invisible: Default::default(),
             use_tls: default_use_tls(),
             dangerously_accept_invalid_certs: Default::default(),
             root_cert_path: Default::default(),
             sasl: Default::default(),
             on_connect: Default::default(),
+            request_channel_modes: Default::default(),
+            request_ban_lists: Default::default(),
+            show_tags: Default::default(),
+            channel_order: Default::default(),
+            channel_list_ttl: default_channel_list_ttl(),
+            channel_list_max_entries: default_channel_list_max_entries(),
             who_poll_interval: default_who_poll_interval(),
             who_retry_interval: default_who_retry_interval(),
         }
     }
 }
 
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChannelOrder {
+    /// Sorted by name
+    #[default]
+    Alphabetical,
+    /// In the order of `channels`, followed by other channels sorted by name
+    Config,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NickInUse {
+    /// Stop trying, leaving registration to the server
+    #[default]
+    None,
+    /// Append underscores, e.g. `nick_`, `nick__`
+    Underscore,
+    /// Append an incrementing number, e.g. `nick1`, `nick2`
+    Suffix,
+}
+
 #[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum IdentifySyntax {
@@ -266,10 +353,26 @@ fn default_reconnect_delay() -> u64 {
     10
 }
 
+fn default_check_on_resume() -> bool {
+    true
+}
+
+fn default_resume_ping_timeout() -> u64 {
+    10
+}
+
 fn default_ghost_sequence() -> Vec<String> {
     vec!["GHOST".into()]
 }
 
+fn default_channel_list_ttl() -> u64 {
+    86400
+}
+
+fn default_channel_list_max_entries() -> usize {
+    5000
+}
+
 fn default_who_poll_interval() -> Duration {
     Duration::from_secs(180)
 }
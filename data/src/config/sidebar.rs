@@ -1,8 +1,11 @@
+use std::collections::BTreeMap;
+
 use serde::Deserialize;
 
 use crate::dashboard::DefaultAction;
+use crate::Server;
 
-#[derive(Debug, Copy, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Sidebar {
     #[serde(default)]
     pub default_action: DefaultAction,
@@ -12,6 +15,17 @@ pub struct Sidebar {
     pub buttons: Buttons,
     #[serde(default = "default_bool_true")]
     pub show_unread_indicators: bool,
+    /// Servers grouped under a network label, keyed by label
+    #[serde(default)]
+    pub networks: BTreeMap<String, Vec<Server>>,
+}
+
+impl Sidebar {
+    pub fn network(&self, server: &Server) -> Option<&str> {
+        self.networks
+            .iter()
+            .find_map(|(network, servers)| servers.contains(server).then_some(network.as_str()))
+    }
 }
 
 impl Default for Sidebar {
@@ -21,6 +35,7 @@ impl Default for Sidebar {
             width: default_sidebar_width(),
             buttons: Default::default(),
             show_unread_indicators: default_bool_true(),
+            networks: BTreeMap::default(),
         }
     }
 }
@@ -3,12 +3,19 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+use crate::input::Draft;
+use crate::notes::Notes;
 use crate::pane::Pane;
 use crate::{compression, environment};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Dashboard {
     pub pane: Pane,
+    /// Unsent input, saved when `buffer.text_input.persist_drafts` is enabled
+    #[serde(default)]
+    pub drafts: Vec<Draft>,
+    #[serde(default)]
+    pub notes: Notes,
 }
 
 #[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
@@ -0,0 +1,157 @@
+use std::borrow::Cow;
+
+/// Longest emoji, in chars, tried when matching emojis in text
+const MAX_EMOJI_CHARS: usize = 10;
+
+/// Replaces known `:shortcode:`s with their emoji, leaving code spans and URLs untouched
+pub fn replace_shortcodes(text: &str) -> Cow<'_, str> {
+    transform(text, |plain, output| {
+        let mut rest = plain;
+
+        while let Some(start) = rest.find(':') {
+            let after = &rest[start + 1..];
+
+            let emoji = after.find(':').and_then(|end| {
+                let shortcode = &after[..end];
+
+                is_shortcode(shortcode)
+                    .then(|| emojis::get_by_shortcode(shortcode))
+                    .flatten()
+                    .map(|emoji| (emoji, end))
+            });
+
+            match emoji {
+                Some((emoji, end)) => {
+                    output.push_str(&rest[..start]);
+                    output.push_str(emoji.as_str());
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    output.push_str(&rest[..start + 1]);
+                    rest = after;
+                }
+            }
+        }
+
+        output.push_str(rest);
+    })
+}
+
+/// Replaces emojis with their `:shortcode:`, leaving code spans and URLs untouched
+pub fn to_shortcodes(text: &str) -> Cow<'_, str> {
+    transform(text, |plain, output| {
+        let mut rest = plain;
+
+        while let Some(first) = rest.chars().next() {
+            // Prefer the longest match, so sequences aren't split into their parts
+            let emoji = rest
+                .char_indices()
+                .skip(1)
+                .map(|(index, _)| index)
+                .chain(Some(rest.len()))
+                .take(MAX_EMOJI_CHARS)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+                .find_map(|end| {
+                    let emoji = emojis::get(&rest[..end])?;
+
+                    Some((emoji.shortcode()?, end))
+                });
+
+            match emoji {
+                Some((shortcode, end)) => {
+                    output.push(':');
+                    output.push_str(shortcode);
+                    output.push(':');
+                    rest = &rest[end..];
+                }
+                None => {
+                    output.push(first);
+                    rest = &rest[first.len_utf8()..];
+                }
+            }
+        }
+    })
+}
+
+/// Applies `f` to the parts of `text` outside of code spans and URLs
+fn transform(text: &str, f: impl Fn(&str, &mut String)) -> Cow<'_, str> {
+    let mut output = String::with_capacity(text.len());
+    let mut in_code = false;
+
+    for (index, span) in text.split('`').enumerate() {
+        if index > 0 {
+            output.push('`');
+        }
+
+        if in_code {
+            output.push_str(span);
+        } else {
+            for word in span.split_inclusive(char::is_whitespace) {
+                let (word, whitespace) =
+                    word.split_at(word.trim_end_matches(char::is_whitespace).len());
+
+                if is_url(word) {
+                    output.push_str(word);
+                } else {
+                    f(word, &mut output);
+                }
+
+                output.push_str(whitespace);
+            }
+        }
+
+        in_code = !in_code;
+    }
+
+    if output == text {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(output)
+    }
+}
+
+fn is_shortcode(shortcode: &str) -> bool {
+    !shortcode.is_empty()
+        && shortcode
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-'))
+}
+
+fn is_url(word: &str) -> bool {
+    word.contains("://") || word.starts_with("www.")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shortcodes() {
+        let tests = [
+            (":thumbsup: nice", "👍 nice"),
+            ("it's 12:30:45 now", "it's 12:30:45 now"),
+            (":unknown_code: :+1::wave:", ":unknown_code: 👍👋"),
+            ("run `echo :wave:` :wave:", "run `echo :wave:` 👋"),
+            (
+                "see https://example.com/:wave:/",
+                "see https://example.com/:wave:/",
+            ),
+            (
+                ":wave:\twww.example.com/:wave:",
+                "👋\twww.example.com/:wave:",
+            ),
+        ];
+
+        for (text, expected) in tests {
+            assert_eq!(replace_shortcodes(text), expected);
+        }
+    }
+
+    #[test]
+    fn unicode() {
+        assert_eq!(to_shortcodes("nice 👍"), "nice :+1:");
+        assert_eq!(to_shortcodes("`👍`"), "`👍`");
+    }
+}
@@ -1,7 +1,9 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 use std::{fmt, io};
 
+use chrono::{DateTime, Utc};
 use futures::future::BoxFuture;
 use futures::{Future, FutureExt};
 use tokio::fs;
@@ -21,6 +23,10 @@ const MAX_MESSAGES: usize = 10_000;
 const TRUNC_COUNT: usize = 500;
 /// Duration to wait after receiving last message before flushing
 const FLUSH_AFTER_LAST_RECEIVED: Duration = Duration::from_secs(5);
+/// # latest messages considered for channel [`Stats`]
+const STATS_MESSAGES: usize = 1_000;
+/// # most active users listed in channel [`Stats`]
+const STATS_TOP_USERS: usize = 3;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Kind {
@@ -122,6 +128,7 @@ pub enum History {
         messages: Vec<Message>,
         last_received_at: Option<Instant>,
         unread_message_count: usize,
+        has_unread_highlight: bool,
         opened_at: Posix,
     },
     Full {
@@ -141,6 +148,7 @@ impl History {
             messages: vec![],
             last_received_at: None,
             unread_message_count: 0,
+            has_unread_highlight: false,
             opened_at,
         }
     }
@@ -171,6 +179,16 @@ impl History {
         }
     }
 
+    fn mark_highlight(&mut self) {
+        if let History::Partial {
+            has_unread_highlight,
+            ..
+        } = self
+        {
+            *has_unread_highlight = true;
+        }
+    }
+
     fn flush(&mut self, now: Instant) -> Option<BoxFuture<'static, Result<(), Error>>> {
         match self {
             History::Partial {
@@ -268,8 +286,133 @@ impl History {
 #[derive(Debug)]
 pub struct View<'a> {
     pub total: usize,
-    pub old_messages: Vec<&'a Message>,
-    pub new_messages: Vec<&'a Message>,
+    pub old_messages: Vec<Entry<'a>>,
+    pub new_messages: Vec<Entry<'a>>,
+}
+
+/// A message in a view, standing in for `repeats` consecutive identical messages
+#[derive(Debug, Clone, Copy)]
+pub struct Entry<'a> {
+    pub message: &'a Message,
+    pub repeats: usize,
+}
+
+/// Replies grouped under the message their chain starts from
+#[derive(Debug, Clone, Copy)]
+pub struct Thread<'a> {
+    /// `msgid` of the message the thread starts from
+    pub id: &'a str,
+    /// The starting message, or the earliest reply when it isn't loaded
+    pub first: &'a Message,
+    pub replies: usize,
+    pub last_activity: DateTime<Utc>,
+}
+
+/// Threads among `messages` which have replies, most recently active first
+pub fn threads(messages: &[Message]) -> Vec<Thread<'_>> {
+    let by_id = index_by_id(messages);
+    let mut positions = HashMap::<&str, usize>::new();
+    let mut threads: Vec<Thread> = vec![];
+
+    for message in messages {
+        let Some(id) = thread_root(message, &by_id) else {
+            continue;
+        };
+        let reply = usize::from(message.reply_to.is_some());
+
+        match positions.get(id) {
+            Some(&position) => {
+                let thread = &mut threads[position];
+                thread.replies += reply;
+                thread.last_activity = thread.last_activity.max(message.server_time);
+            }
+            None => {
+                positions.insert(id, threads.len());
+                threads.push(Thread {
+                    id,
+                    first: by_id.get(id).copied().unwrap_or(message),
+                    replies: reply,
+                    last_activity: message.server_time,
+                });
+            }
+        }
+    }
+
+    threads.retain(|thread| thread.replies > 0);
+    threads.sort_by(|a, b| b.last_activity.cmp(&a.last_activity));
+    threads
+}
+
+/// Activity among the latest messages of a channel
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub last_hour: usize,
+    pub last_day: usize,
+    /// Most active users with their message counts, most active first
+    pub top_users: Vec<(Nick, usize)>,
+}
+
+/// [`Stats`] of the last [`STATS_MESSAGES`] user messages among `messages`
+pub fn stats(messages: &[Message], now: DateTime<Utc>) -> Stats {
+    let mut stats = Stats::default();
+    let mut counts = HashMap::<Nick, usize>::new();
+
+    let messages = messages
+        .iter()
+        .rev()
+        .filter_map(|message| match message.target.source() {
+            message::Source::User(user) => Some((user, message.server_time)),
+            _ => None,
+        })
+        .take(STATS_MESSAGES);
+
+    for (user, server_time) in messages {
+        let age = now - server_time;
+
+        if age <= chrono::Duration::hours(1) {
+            stats.last_hour += 1;
+        }
+        if age <= chrono::Duration::days(1) {
+            stats.last_day += 1;
+        }
+
+        *counts.entry(user.nickname().to_owned()).or_default() += 1;
+    }
+
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by(|(a_nick, a), (b_nick, b)| b.cmp(a).then_with(|| a_nick.cmp(b_nick)));
+    counts.truncate(STATS_TOP_USERS);
+
+    stats.top_users = counts;
+    stats
+}
+
+/// `msgid` of the message the reply chain of `message` starts from
+pub fn thread_root<'a>(
+    message: &'a Message,
+    by_id: &HashMap<&'a str, &'a Message>,
+) -> Option<&'a str> {
+    let mut current = message;
+
+    // Bounded, in case reply tags form a cycle
+    for _ in 0..=by_id.len() {
+        match current.reply_to.as_deref() {
+            None => return current.id.as_deref(),
+            Some(parent) => match by_id.get(parent) {
+                Some(message) => current = message,
+                None => return Some(parent),
+            },
+        }
+    }
+
+    None
+}
+
+pub fn index_by_id(messages: &[Message]) -> HashMap<&str, &Message> {
+    messages
+        .iter()
+        .filter_map(|message| Some((message.id.as_deref()?, message)))
+        .collect()
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -8,11 +8,14 @@ use tokio::time::Instant;
 
 use crate::history::{self, History};
 use crate::message::{self, Limit};
+use crate::notes::Notes;
 use crate::time::Posix;
 use crate::user::Nick;
 use crate::{config, input};
 use crate::{server, Buffer, Config, Input, Server, User};
 
+const SEARCH_RESULT_LIMIT: usize = 50;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Resource {
     pub server: server::Server,
@@ -34,6 +37,7 @@ pub enum Message {
 pub struct Manager {
     resources: HashSet<Resource>,
     data: Data,
+    notes: Notes,
 }
 
 impl Manager {
@@ -180,6 +184,26 @@ impl Manager {
         self.data.input.store_draft(draft);
     }
 
+    pub fn clear_draft(&mut self, buffer: &Buffer) {
+        self.data.input.clear_draft(buffer);
+    }
+
+    pub fn notes(&self) -> &Notes {
+        &self.notes
+    }
+
+    pub fn restore_notes(&mut self, notes: Notes) {
+        self.notes = notes;
+    }
+
+    pub fn record_note(&mut self, server: &Server, nick: Nick, note: Option<String>) {
+        self.notes.set(server, nick, note);
+    }
+
+    pub fn persistable_drafts(&self) -> Vec<input::Draft> {
+        self.data.input.persistable_drafts()
+    }
+
     pub fn record_message(&mut self, server: &Server, message: crate::Message) {
         self.data.add_message(
             server.clone(),
@@ -188,6 +212,14 @@ impl Manager {
         );
     }
 
+    /// Records a message which highlighted us, flagging its buffer until it's opened.
+    pub fn record_highlight(&mut self, server: &Server, message: crate::Message) {
+        let kind = history::Kind::from(message.target.clone());
+
+        self.data.add_message(server.clone(), kind.clone(), message);
+        self.data.mark_highlight(server, &kind);
+    }
+
     pub fn get_channel_messages(
         &self,
         server: &Server,
@@ -200,9 +232,54 @@ impl Manager {
             &history::Kind::Channel(channel.to_string()),
             limit,
             buffer_config,
+            None,
         )
     }
 
+    /// Messages of a channel which belong to the thread starting from `thread`
+    pub fn get_thread_messages(
+        &self,
+        server: &Server,
+        channel: &str,
+        thread: &str,
+        limit: Option<Limit>,
+        buffer_config: &config::Buffer,
+    ) -> Option<history::View<'_>> {
+        self.data.history_view(
+            server,
+            &history::Kind::Channel(channel.to_string()),
+            limit,
+            buffer_config,
+            Some(thread),
+        )
+    }
+
+    pub fn get_channel_threads(&self, server: &Server, channel: &str) -> Vec<history::Thread<'_>> {
+        self.data
+            .map
+            .get(server)
+            .and_then(|map| map.get(&history::Kind::Channel(channel.to_string())))
+            .map(|history| match history {
+                History::Partial { messages, .. } | History::Full { messages, .. } => {
+                    history::threads(messages)
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn get_channel_stats(&self, server: &Server, channel: &str) -> history::Stats {
+        self.data
+            .map
+            .get(server)
+            .and_then(|map| map.get(&history::Kind::Channel(channel.to_string())))
+            .map(|history| match history {
+                History::Partial { messages, .. } | History::Full { messages, .. } => {
+                    history::stats(messages, Utc::now())
+                }
+            })
+            .unwrap_or_default()
+    }
+
     pub fn get_server_messages(
         &self,
         server: &Server,
@@ -210,7 +287,7 @@ impl Manager {
         buffer_config: &config::Buffer,
     ) -> Option<history::View<'_>> {
         self.data
-            .history_view(server, &history::Kind::Server, limit, buffer_config)
+            .history_view(server, &history::Kind::Server, limit, buffer_config, None)
     }
 
     pub fn get_query_messages(
@@ -225,6 +302,7 @@ impl Manager {
             &history::Kind::Query(nick.clone()),
             limit,
             buffer_config,
+            None,
         )
     }
 
@@ -245,6 +323,110 @@ impl Manager {
         queries
     }
 
+    /// Searches messages loaded for the buffer, or every buffer of a server buffer
+    pub fn search(&self, buffer: &Buffer, terms: &str) -> Vec<crate::Message> {
+        let Some(map) = self.data.map.get(buffer.server()) else {
+            return vec![];
+        };
+
+        let kind = match buffer {
+            Buffer::Server(_) => None,
+            Buffer::Channel(_, channel) => Some(history::Kind::Channel(channel.clone())),
+            Buffer::Query(_, nick) => Some(history::Kind::Query(nick.clone())),
+        };
+        let terms = terms.to_lowercase();
+
+        map.iter()
+            .filter(|(history_kind, _)| kind.as_ref().map_or(true, |kind| kind == *history_kind))
+            .flat_map(|(_, history)| match history {
+                History::Partial { messages, .. } | History::Full { messages, .. } => messages,
+            })
+            .filter(|message| {
+                matches!(
+                    message.target.source(),
+                    message::Source::User(_) | message::Source::Action
+                ) && message.text.to_lowercase().contains(&terms)
+            })
+            .sorted_by_key(|message| message.server_time)
+            .rev()
+            .take(SEARCH_RESULT_LIMIT)
+            .rev()
+            .cloned()
+            .collect()
+    }
+
+    /// When the message from a user sent at `server_time` was received in `buffer`, if it's
+    /// still in its history
+    pub fn received_at(&self, buffer: &Buffer, server_time: DateTime<Utc>) -> Option<Posix> {
+        let kind = match buffer {
+            Buffer::Server(_) => history::Kind::Server,
+            Buffer::Channel(_, channel) => history::Kind::Channel(channel.clone()),
+            Buffer::Query(_, nick) => history::Kind::Query(nick.clone()),
+        };
+
+        let messages = match self.data.map.get(buffer.server())?.get(&kind)? {
+            History::Partial { messages, .. } | History::Full { messages, .. } => messages,
+        };
+
+        messages
+            .iter()
+            .find(|message| {
+                message.server_time == server_time
+                    && matches!(
+                        message.target.source(),
+                        message::Source::User(_) | message::Source::Action
+                    )
+            })
+            .map(|message| message.received_at)
+    }
+
+    /// The last `count` messages of a query, formatted to be quoted in another buffer
+    pub fn quote_query(&self, server: &Server, nick: &Nick, count: usize) -> Vec<String> {
+        let Some(history) = self
+            .data
+            .map
+            .get(server)
+            .and_then(|map| map.get(&history::Kind::Query(nick.clone())))
+        else {
+            return vec![];
+        };
+
+        let messages = match history {
+            History::Partial { messages, .. } | History::Full { messages, .. } => messages,
+        };
+
+        let mut lines = messages
+            .iter()
+            .filter_map(|message| match message.target.source() {
+                message::Source::User(user) => {
+                    Some(format!("<{}> {}", user.nickname(), message.text))
+                }
+                message::Source::Action => Some(message.text.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        lines.split_off(lines.len().saturating_sub(count))
+    }
+
+    pub fn server_has_unread(&self, server: &Server) -> bool {
+        self.data
+            .map
+            .get(server)
+            .map(|map| {
+                map.values().any(|history| {
+                    matches!(
+                        history,
+                        History::Partial {
+                            unread_message_count,
+                            ..
+                        } if *unread_message_count > 0
+                    )
+                })
+            })
+            .unwrap_or_default()
+    }
+
     pub fn has_unread(&self, server: &Server, kind: &history::Kind) -> bool {
         self.data
             .map
@@ -262,6 +444,23 @@ impl Manager {
             .unwrap_or_default()
     }
 
+    pub fn has_unread_highlight(&self, server: &Server, kind: &history::Kind) -> bool {
+        self.data
+            .map
+            .get(server)
+            .and_then(|map| map.get(kind))
+            .map(|history| {
+                matches!(
+                    history,
+                    History::Partial {
+                        has_unread_highlight: true,
+                        ..
+                    }
+                )
+            })
+            .unwrap_or_default()
+    }
+
     pub fn broadcast(
         &mut self,
         server: &Server,
@@ -302,6 +501,12 @@ impl Manager {
                 message::broadcast::disconnected(channels, queries, error, sent_time)
             }
             Broadcast::Reconnected => message::broadcast::reconnected(channels, queries, sent_time),
+            Broadcast::SaslAuthenticated { mechanism, account } => {
+                message::broadcast::sasl_authenticated(mechanism, account.as_deref(), sent_time)
+            }
+            Broadcast::SaslFailed { mechanism, reason } => {
+                message::broadcast::sasl_failed(mechanism, &reason, sent_time)
+            }
             Broadcast::Quit {
                 user,
                 comment,
@@ -352,6 +557,41 @@ impl Manager {
                 channel,
                 user_channels,
             } => message::broadcast::invite(inviter, channel, user_channels, sent_time),
+            Broadcast::ChangeHost {
+                old_user,
+                new_username,
+                new_hostname,
+                ourself,
+                logged_in,
+                user_channels,
+            } => {
+                if ourself {
+                    // If ourself, broadcast to all query channels (since we are in all of them)
+                    message::broadcast::change_host(
+                        user_channels,
+                        queries,
+                        &old_user,
+                        &new_username,
+                        &new_hostname,
+                        ourself,
+                        logged_in,
+                        sent_time,
+                    )
+                } else {
+                    // Otherwise just the query channel of the user w/ host change
+                    let user_query = queries.find(|nick| old_user.nickname() == *nick);
+                    message::broadcast::change_host(
+                        user_channels,
+                        user_query,
+                        &old_user,
+                        &new_username,
+                        &new_hostname,
+                        ourself,
+                        logged_in,
+                        sent_time,
+                    )
+                }
+            }
         };
 
         messages.into_iter().for_each(|message| {
@@ -449,6 +689,7 @@ impl Data {
         kind: &history::Kind,
         limit: Option<Limit>,
         buffer_config: &config::Buffer,
+        thread: Option<&str>,
     ) -> Option<history::View> {
         let History::Full {
             messages,
@@ -460,9 +701,17 @@ impl Data {
         };
 
         let mut most_recent_messages = HashMap::<Nick, DateTime<Utc>>::new();
+        let by_id = thread
+            .map(|_| history::index_by_id(messages))
+            .unwrap_or_default();
 
         let filtered = messages
             .iter()
+            .filter(|message| {
+                thread.map_or(true, |thread| {
+                    history::thread_root(message, &by_id) == Some(thread)
+                })
+            })
             .filter(|message| match message.target.source() {
                 message::Source::Server(Some(source)) => {
                     if let Some(server_message) = buffer_config.server_messages.get(source) {
@@ -525,10 +774,12 @@ impl Data {
 
         let (old, new) = limited.split_at(split_at);
 
+        let duplicates = &buffer_config.duplicate_messages;
+
         Some(history::View {
             total,
-            old_messages: old.to_vec(),
-            new_messages: new.to_vec(),
+            old_messages: collapse_duplicates(old, duplicates),
+            new_messages: collapse_duplicates(new, duplicates),
         })
     }
 
@@ -546,6 +797,12 @@ impl Data {
             .add_message(message)
     }
 
+    fn mark_highlight(&mut self, server: &server::Server, kind: &history::Kind) {
+        if let Some(history) = self.map.get_mut(server).and_then(|map| map.get_mut(kind)) {
+            history.mark_highlight();
+        }
+    }
+
     fn untrack(
         &mut self,
         server: &server::Server,
@@ -574,6 +831,45 @@ impl Data {
     }
 }
 
+/// Folds runs of identical messages from the same user into their latest message
+fn collapse_duplicates<'a>(
+    messages: &[&'a crate::Message],
+    config: &config::buffer::DuplicateMessages,
+) -> Vec<history::Entry<'a>> {
+    let is_repeat = |previous: &crate::Message, message: &crate::Message| {
+        let (message::Source::User(previous_user), message::Source::User(user)) =
+            (previous.target.source(), message.target.source())
+        else {
+            return false;
+        };
+
+        previous_user.nickname() == user.nickname()
+            && previous.text == message.text
+            && message
+                .server_time
+                .signed_duration_since(previous.server_time)
+                .num_seconds()
+                <= config.window
+    };
+
+    let mut entries: Vec<history::Entry> = Vec::with_capacity(messages.len());
+
+    for message in messages {
+        match entries.last_mut() {
+            Some(entry) if config.collapse && is_repeat(entry.message, message) => {
+                entry.message = message;
+                entry.repeats += 1;
+            }
+            _ => entries.push(history::Entry {
+                message,
+                repeats: 1,
+            }),
+        }
+    }
+
+    entries
+}
+
 fn smart_filter_message(
     message: &crate::Message,
     seconds: &i64,
@@ -612,6 +908,14 @@ pub enum Broadcast {
         error: Option<String>,
     },
     Reconnected,
+    SaslAuthenticated {
+        mechanism: &'static str,
+        account: Option<String>,
+    },
+    SaslFailed {
+        mechanism: &'static str,
+        reason: String,
+    },
     Quit {
         user: User,
         comment: Option<String>,
@@ -628,4 +932,12 @@ pub enum Broadcast {
         channel: String,
         user_channels: Vec<String>,
     },
+    ChangeHost {
+        old_user: User,
+        new_username: String,
+        new_hostname: String,
+        ourself: bool,
+        logged_in: bool,
+        user_channels: Vec<String>,
+    },
 }
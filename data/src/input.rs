@@ -1,16 +1,20 @@
 use std::collections::HashMap;
 
-use chrono::Utc;
 use irc::proto;
 use irc::proto::format;
+use serde::{Deserialize, Serialize};
 
-use crate::time::Posix;
-use crate::{command, message, Buffer, Command, Message, Server, User};
+use crate::buffer::AppearancePart;
+use crate::{command, config, isupport, message, Buffer, Command, Message, Server, User};
 
 const INPUT_HISTORY_LENGTH: usize = 100;
 
-pub fn parse(buffer: Buffer, input: &str) -> Result<Input, Error> {
-    let content = match command::parse(input, Some(&buffer)) {
+pub fn parse(
+    buffer: Buffer,
+    input: &str,
+    isupport: &HashMap<isupport::Kind, isupport::Parameter>,
+) -> Result<Input, Error> {
+    let content = match command::parse(input, Some(&buffer), isupport) {
         Ok(command) => Content::Command(command),
         Err(command::Error::MissingSlash) => Content::Text(input.to_string()),
         Err(error) => return Err(Error::Command(error)),
@@ -28,6 +32,7 @@ pub fn parse(buffer: Buffer, input: &str) -> Result<Input, Error> {
         buffer,
         content,
         raw: Some(input.to_string()),
+        reply_to: None,
     })
 }
 
@@ -36,6 +41,7 @@ pub struct Input {
     buffer: Buffer,
     content: Content,
     raw: Option<String>,
+    reply_to: Option<String>,
 }
 
 impl Input {
@@ -44,9 +50,37 @@ impl Input {
             buffer,
             content: Content::Command(command),
             raw: None,
+            reply_to: None,
+        }
+    }
+
+    /// Sends a plain message as a reply to the message with the `msgid` given
+    pub fn in_reply_to(self, id: String) -> Self {
+        match self.content {
+            Content::Text(_) => Self {
+                reply_to: Some(id),
+                ..self
+            },
+            Content::Command(_) => self,
         }
     }
 
+    /// Applies the configured emoji replacement to the text being sent
+    pub fn with_emojis(self, emojis: config::buffer::SendEmojis) -> Self {
+        let content = match self.content {
+            Content::Text(text) => Content::Text(emojis.apply(&text).into_owned()),
+            Content::Command(Command::Msg(target, text)) => {
+                Content::Command(Command::Msg(target, emojis.apply(&text).into_owned()))
+            }
+            Content::Command(Command::Me(target, action)) => {
+                Content::Command(Command::Me(target, emojis.apply(&action).into_owned()))
+            }
+            content => content,
+        };
+
+        Self { content, ..self }
+    }
+
     pub fn buffer(&self) -> &Buffer {
         &self.buffer
     }
@@ -76,25 +110,105 @@ impl Input {
 
         match command {
             Command::Msg(target, text) => Some(Message {
-                received_at: Posix::now(),
-                server_time: Utc::now(),
-                direction: message::Direction::Sent,
-                target: to_target(target, message::Source::User(user))?,
-                text,
+                reply_to: self.reply_to.clone(),
+                ..Message::new(
+                    message::Direction::Sent,
+                    to_target(target, message::Source::User(user))?,
+                    text,
+                )
             }),
             Command::Me(target, action) => Some(Message {
-                received_at: Posix::now(),
-                server_time: Utc::now(),
-                direction: message::Direction::Sent,
-                target: to_target(target, message::Source::Action)?,
-                text: message::action_text(user.nickname(), &action),
+                reply_to: self.reply_to.clone(),
+                ..Message::new(
+                    message::Direction::Sent,
+                    to_target(target, message::Source::Action)?,
+                    message::action_text(user.nickname(), &action),
+                )
             }),
             _ => None,
         }
     }
 
     pub fn encoded(&self) -> Option<message::Encoded> {
-        self.content.proto(&self.buffer).map(message::Encoded::from)
+        let mut message = self.content.proto(&self.buffer)?;
+
+        if let Some(id) = &self.reply_to {
+            message.tags.push(proto::Tag {
+                key: "+draft/reply".to_string(),
+                value: Some(id.clone()),
+            });
+        }
+
+        Some(message::Encoded::from(message))
+    }
+
+    /// Channels of a join without keys, which can be confirmed or trimmed before sending
+    pub fn join_channels(&self) -> Option<Vec<String>> {
+        match &self.content {
+            Content::Command(Command::Join(chanlist, None)) => Some(
+                chanlist
+                    .split(',')
+                    .filter(|channel| !channel.is_empty())
+                    .map(String::from)
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    pub fn nick(&self) -> Option<&str> {
+        match &self.content {
+            Content::Command(Command::Nick(nick)) => Some(nick),
+            _ => None,
+        }
+    }
+
+    pub fn search_terms(&self) -> Option<&str> {
+        match &self.content {
+            Content::Command(Command::Search(_, terms)) => Some(terms),
+            _ => None,
+        }
+    }
+
+    /// Target channel and message count of a `/tochannel` command
+    pub fn to_channel(&self) -> Option<(&str, usize)> {
+        match &self.content {
+            Content::Command(Command::ToChannel(channel, count)) => Some((channel, *count)),
+            _ => None,
+        }
+    }
+
+    /// Whether this is an unfiltered `/list`, whose cached list can be shown while it's requested
+    pub fn is_full_list(&self) -> bool {
+        matches!(
+            &self.content,
+            Content::Command(Command::Unknown(command, args))
+                if command.eq_ignore_ascii_case("list") && args.is_empty()
+        )
+    }
+
+    /// Channel to request the members of again with `/resync`
+    pub fn resync(&self) -> Option<&str> {
+        match &self.content {
+            Content::Command(Command::Resync(channel)) => Some(channel),
+            _ => None,
+        }
+    }
+
+    /// Color of the buffer to change with `/appearance`
+    pub fn appearance(&self) -> Option<(AppearancePart, Option<&str>)> {
+        match &self.content {
+            Content::Command(Command::Appearance(part, hex)) => Some((*part, hex.as_deref())),
+            _ => None,
+        }
+    }
+
+    /// User and note given to `/note`, which removes the note without one
+    pub fn note(&self) -> Option<(&str, Option<&str>)> {
+        match &self.content {
+            Content::Command(Command::Note(nick, note)) => Some((nick, note.as_deref())),
+            _ => None,
+        }
     }
 
     pub fn raw(&self) -> Option<&str> {
@@ -126,7 +240,7 @@ impl Content {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Draft {
     pub buffer: Buffer,
     pub text: String,
@@ -160,6 +274,47 @@ impl Storage {
     pub fn store_draft(&mut self, draft: Draft) {
         self.draft.insert(draft.buffer, draft.text);
     }
+
+    pub fn clear_draft(&mut self, buffer: &Buffer) {
+        self.draft.remove(buffer);
+    }
+
+    /// Drafts which can be saved across restarts, leaving out any which may hold a secret
+    pub fn persistable_drafts(&self) -> Vec<Draft> {
+        self.draft
+            .iter()
+            .filter(|(_, text)| !text.trim().is_empty() && !may_contain_secret(text))
+            .map(|(buffer, text)| Draft {
+                buffer: buffer.clone(),
+                text: text.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Whether a draft mentions a password or a command which takes one
+fn may_contain_secret(text: &str) -> bool {
+    const WORDS: &[&str] = &[
+        "pass",
+        "passwd",
+        "password",
+        "identify",
+        "id",
+        "auth",
+        "login",
+        "register",
+        "ghost",
+        "recover",
+        "oper",
+        "authenticate",
+        "nickserv",
+        "ns",
+        "token",
+        "secret",
+    ];
+
+    text.split(|c: char| !c.is_alphanumeric())
+        .any(|word| WORDS.contains(&word.to_lowercase().as_str()))
 }
 
 /// Cached values for a buffers input
@@ -183,3 +338,28 @@ pub enum Error {
 fn exceeds_byte_limit(message: proto::Message) -> bool {
     format::message(message).len() > format::BYTE_LIMIT
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn secret_drafts() {
+        let tests = [
+            ("/msg NickServ IDENTIFY hunter2", true),
+            ("/msg NickServ ID hunter2", true),
+            ("/msg Q@CServe.quakenet.org AUTH halloy hunter2", true),
+            ("/msg X@channels.undernet.org LOGIN halloy hunter2", true),
+            ("/ns ghost halloy hunter2", true),
+            ("/oper admin hunter2", true),
+            ("my password is hunter2", true),
+            ("/quote PASS hunter2", true),
+            ("see you tomorrow", false),
+            ("passing through", false),
+        ];
+
+        for (text, expected) in tests {
+            assert_eq!(may_contain_secret(text), expected, "{text}");
+        }
+    }
+}
@@ -9,18 +9,21 @@ pub enum Kind {
     AWAYLEN,
     CHANLIMIT,
     CHANNELLEN,
+    CHANTYPES,
     CNOTICE,
     CPRIVMSG,
     ELIST,
     KEYLEN,
     KICKLEN,
     KNOCK,
+    MONITOR,
     NICKLEN,
     SAFELIST,
     STATUSMSG,
     TARGMAX,
     TOPICLEN,
     USERIP,
+    WATCH,
     WHOX,
 }
 
@@ -483,18 +486,21 @@ impl Operation {
                 "AWAYLEN" => Some(Kind::AWAYLEN),
                 "CHANLIMIT" => Some(Kind::CHANLIMIT),
                 "CHANNELLEN" => Some(Kind::CHANNELLEN),
+                "CHANTYPES" => Some(Kind::CHANTYPES),
                 "CNOTICE" => Some(Kind::CNOTICE),
                 "CPRIVMSG" => Some(Kind::CPRIVMSG),
                 "ELIST" => Some(Kind::ELIST),
                 "KEYLEN" => Some(Kind::KEYLEN),
                 "KICKLEN" => Some(Kind::KICKLEN),
                 "KNOCK" => Some(Kind::KNOCK),
+                "MONITOR" => Some(Kind::MONITOR),
                 "NICKLEN" => Some(Kind::NICKLEN),
                 "SAFELIST" => Some(Kind::SAFELIST),
                 "STATUSMSG" => Some(Kind::STATUSMSG),
                 "TARGMAX" => Some(Kind::TARGMAX),
                 "TOPICLEN" => Some(Kind::TOPICLEN),
                 "USERIP" => Some(Kind::USERIP),
+                "WATCH" => Some(Kind::WATCH),
                 "WHOX" => Some(Kind::WHOX),
                 _ => None,
             },
@@ -577,18 +583,21 @@ impl Parameter {
             Parameter::AWAYLEN(_) => Some(Kind::AWAYLEN),
             Parameter::CHANLIMIT(_) => Some(Kind::CHANLIMIT),
             Parameter::CHANNELLEN(_) => Some(Kind::CHANNELLEN),
+            Parameter::CHANTYPES(_) => Some(Kind::CHANTYPES),
             Parameter::CNOTICE => Some(Kind::CNOTICE),
             Parameter::CPRIVMSG => Some(Kind::CPRIVMSG),
             Parameter::ELIST(_) => Some(Kind::ELIST),
             Parameter::KEYLEN(_) => Some(Kind::KEYLEN),
             Parameter::KICKLEN(_) => Some(Kind::KICKLEN),
             Parameter::KNOCK => Some(Kind::KNOCK),
+            Parameter::MONITOR(_) => Some(Kind::MONITOR),
             Parameter::NICKLEN(_) => Some(Kind::NICKLEN),
             Parameter::SAFELIST => Some(Kind::SAFELIST),
             Parameter::STATUSMSG(_) => Some(Kind::STATUSMSG),
             Parameter::TARGMAX(_) => Some(Kind::TARGMAX),
             Parameter::TOPICLEN(_) => Some(Kind::TOPICLEN),
             Parameter::USERIP => Some(Kind::USERIP),
+            Parameter::WATCH(_) => Some(Kind::WATCH),
             Parameter::WHOX => Some(Kind::WHOX),
             _ => None,
         }
@@ -16,22 +16,30 @@ pub use self::user::User;
 pub use self::version::Version;
 
 pub mod buffer;
+pub mod capability;
 pub mod channel;
+pub mod channel_list;
 pub mod client;
 pub mod command;
 mod compression;
 pub mod config;
 pub mod dashboard;
 pub mod dcc;
+pub mod emoji;
 pub mod environment;
 pub mod file_transfer;
 pub mod history;
 pub mod input;
 pub mod isupport;
 pub mod log;
+pub mod math;
 pub mod message;
 pub mod mode;
+pub mod notes;
 pub mod pane;
+pub mod pastebin;
+pub mod presence;
+pub mod rules;
 pub mod server;
 pub mod shortcut;
 pub mod stream;
@@ -0,0 +1,400 @@
+use std::borrow::Cow;
+
+/// Renders inline `$...$` math spans of `text` as unicode, leaving spans which can't be
+/// rendered as they were written
+pub fn render(text: &str) -> Cow<'_, str> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('$') {
+        let after = &rest[start + 1..];
+
+        let rendered = closing(after).and_then(|end| Some((render_math(&after[..end])?, end)));
+
+        match rendered {
+            Some((rendered, end)) => {
+                output.push_str(&rest[..start]);
+                output.push_str(&rendered);
+                rest = &after[end + 1..];
+            }
+            None => {
+                output.push_str(&rest[..start + 1]);
+                rest = after;
+            }
+        }
+    }
+
+    output.push_str(rest);
+
+    if output == text {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(output)
+    }
+}
+
+/// Position of the `$` closing a span opened right before `after`. Spans can't start or end
+/// with whitespace, and can't be followed by a digit, so prices like `$5 or $10` aren't math.
+/// Display math (`$$...$$`) isn't rendered
+fn closing(after: &str) -> Option<usize> {
+    let first = after.chars().next()?;
+
+    if first.is_whitespace() || first == '$' {
+        return None;
+    }
+
+    let end = after.find('$')?;
+    let before_end = after[..end].chars().last()?;
+    let after_end = after[end + 1..].chars().next();
+
+    (!before_end.is_whitespace() && !after_end.is_some_and(|c| c.is_ascii_digit() || c == '$'))
+        .then_some(end)
+}
+
+fn render_math(source: &str) -> Option<String> {
+    let mut parser = Parser {
+        chars: source.chars().collect(),
+        position: 0,
+    };
+
+    let rendered = parser.sequence()?;
+
+    (parser.position == parser.chars.len()).then_some(rendered)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    /// Renders until the end of the source or of the enclosing group
+    fn sequence(&mut self) -> Option<String> {
+        let mut output = String::new();
+
+        while let Some(&c) = self.chars.get(self.position) {
+            match c {
+                '}' => break,
+                '^' => {
+                    self.position += 1;
+                    output.push_str(
+                        &self
+                            .argument()?
+                            .chars()
+                            .map(superscript)
+                            .collect::<Option<String>>()?,
+                    );
+                }
+                '_' => {
+                    self.position += 1;
+                    output.push_str(
+                        &self
+                            .argument()?
+                            .chars()
+                            .map(subscript)
+                            .collect::<Option<String>>()?,
+                    );
+                }
+                '{' => output.push_str(&self.group()?),
+                '\\' => output.push_str(&self.command()?),
+                _ => {
+                    self.position += 1;
+                    output.push(c);
+                }
+            }
+        }
+
+        Some(output)
+    }
+
+    /// A `{...}` group, a command or a single character
+    fn argument(&mut self) -> Option<String> {
+        match *self.chars.get(self.position)? {
+            '{' => self.group(),
+            '\\' => self.command(),
+            '}' | '^' | '_' => None,
+            c => {
+                self.position += 1;
+                Some(c.to_string())
+            }
+        }
+    }
+
+    fn group(&mut self) -> Option<String> {
+        self.position += 1;
+
+        let rendered = self.sequence()?;
+
+        if self.chars.get(self.position) != Some(&'}') {
+            return None;
+        }
+        self.position += 1;
+
+        Some(rendered)
+    }
+
+    fn command(&mut self) -> Option<String> {
+        self.position += 1;
+
+        let start = self.position;
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|c| c.is_ascii_alphabetic())
+        {
+            self.position += 1;
+        }
+
+        // Escaped symbols and spacing, like `\{` and `\,`
+        if start == self.position {
+            let c = *self.chars.get(self.position)?;
+            self.position += 1;
+
+            return match c {
+                '{' | '}' | '$' | '%' | '&' | '#' | '_' => Some(c.to_string()),
+                ',' | ':' | ';' | ' ' => Some(" ".to_string()),
+                '!' => Some(String::new()),
+                _ => None,
+            };
+        }
+
+        let name = self.chars[start..self.position].iter().collect::<String>();
+
+        match name.as_str() {
+            "frac" => {
+                let numerator = self.argument()?;
+                let denominator = self.argument()?;
+
+                Some(format!(
+                    "{}/{}",
+                    parenthesize(&numerator),
+                    parenthesize(&denominator)
+                ))
+            }
+            "sqrt" => Some(format!("√{}", parenthesize(&self.argument()?))),
+            "sin" | "cos" | "tan" | "log" | "ln" | "exp" | "lim" | "max" | "min" | "det" => {
+                Some(name)
+            }
+            "quad" | "qquad" => Some(" ".to_string()),
+            _ => symbol(&name).map(String::from),
+        }
+    }
+}
+
+/// Wraps rendered arguments longer than a character, so `\frac{a+b}{2}` reads `(a+b)/2`
+fn parenthesize(rendered: &str) -> Cow<'_, str> {
+    if rendered.chars().count() > 1 {
+        Cow::Owned(format!("({rendered})"))
+    } else {
+        Cow::Borrowed(rendered)
+    }
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" | "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" => "θ",
+        "vartheta" => "ϑ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "rho" => "ρ",
+        "sigma" => "σ",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" | "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "times" => "×",
+        "cdot" => "⋅",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "propto" => "∝",
+        "infty" => "∞",
+        "sum" => "∑",
+        "prod" => "∏",
+        "int" => "∫",
+        "oint" => "∮",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "forall" => "∀",
+        "exists" => "∃",
+        "in" => "∈",
+        "notin" => "∉",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "cup" => "∪",
+        "cap" => "∩",
+        "emptyset" => "∅",
+        "to" | "rightarrow" => "→",
+        "leftarrow" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "iff" | "Leftrightarrow" => "⇔",
+        "mapsto" => "↦",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "neg" | "lnot" => "¬",
+        "circ" => "∘",
+        "cdots" => "⋯",
+        "ldots" | "dots" => "…",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "aleph" => "ℵ",
+        _ => return None,
+    })
+}
+
+fn superscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'a' => 'ᵃ',
+        'b' => 'ᵇ',
+        'c' => 'ᶜ',
+        'd' => 'ᵈ',
+        'e' => 'ᵉ',
+        'f' => 'ᶠ',
+        'g' => 'ᵍ',
+        'h' => 'ʰ',
+        'i' => 'ⁱ',
+        'j' => 'ʲ',
+        'k' => 'ᵏ',
+        'l' => 'ˡ',
+        'm' => 'ᵐ',
+        'n' => 'ⁿ',
+        'o' => 'ᵒ',
+        'p' => 'ᵖ',
+        'r' => 'ʳ',
+        's' => 'ˢ',
+        't' => 'ᵗ',
+        'u' => 'ᵘ',
+        'v' => 'ᵛ',
+        'w' => 'ʷ',
+        'x' => 'ˣ',
+        'y' => 'ʸ',
+        'z' => 'ᶻ',
+        'T' => 'ᵀ',
+        _ => return None,
+    })
+}
+
+fn subscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rendered() {
+        let tests = [
+            ("so $x^2 + y_1 = z^{n+1}$ then", "so x² + y₁ = zⁿ⁺¹ then"),
+            (r"$\alpha \leq \beta$", "α ≤ β"),
+            (r"$\frac{a+b}{2}$ and $\sqrt{2}$", "(a+b)/2 and √2"),
+            (r"$\{x \in A\}$", "{x ∈ A}"),
+        ];
+
+        for (text, expected) in tests {
+            assert_eq!(render(text), expected);
+        }
+    }
+
+    #[test]
+    fn left_as_is() {
+        let tests = [
+            "it costs $5 or $10",
+            "a $ b $ c",
+            "$$x$$",
+            r"$\unknown$",
+            r"$x^{\alpha}$",
+            "$x^{2$",
+            "no math here",
+        ];
+
+        for text in tests {
+            assert!(matches!(render(text), Cow::Borrowed(_)), "{text}");
+        }
+    }
+}
@@ -1,12 +1,14 @@
-use chrono::{DateTime, Utc};
+use std::fmt;
+
+use chrono::{DateTime, Local, Utc};
 use irc::proto;
 use irc::proto::Command;
 use serde::{Deserialize, Serialize};
 
 pub use self::source::Source;
 use crate::time::{self, Posix};
-use crate::user::{Nick, NickRef};
-use crate::{Config, User};
+use crate::user::{Account, Nick, NickRef};
+use crate::{channel_list, config, Buffer, Config, User};
 
 pub type Channel = String;
 
@@ -83,9 +85,85 @@ pub struct Message {
     pub direction: Direction,
     pub target: Target,
     pub text: String,
+    /// Server assigned `msgid`
+    #[serde(default)]
+    pub id: Option<String>,
+    /// `msgid` of the message this replies to, from the `+draft/reply` tag
+    #[serde(default)]
+    pub reply_to: Option<String>,
+    /// Tags the message was received with, when kept for inspection
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<Tag>,
+    /// Sent by a bot, marked with the `bot` tag
+    #[serde(default)]
+    pub bot: bool,
+    /// Services account of the author when the message was received, where known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<Account>,
+}
+
+/// A message tag, e.g. `msgid=abc` or a valueless `+draft/typing`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tag {
+    pub key: String,
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}={value}", self.key),
+            None => write!(f, "{}", self.key),
+        }
+    }
+}
+
+/// Tags of `message`, to be kept with [`Message::with_tags`]
+pub fn tags(message: &Encoded) -> Vec<Tag> {
+    message
+        .tags
+        .iter()
+        .map(|tag| Tag {
+            key: tag.key.clone(),
+            value: tag.value.clone(),
+        })
+        .collect()
 }
 
 impl Message {
+    /// A message at the current time without any tags, which others are built from
+    pub fn new(direction: Direction, target: Target, text: String) -> Message {
+        Message {
+            received_at: Posix::now(),
+            server_time: Utc::now(),
+            direction,
+            target,
+            text,
+            id: None,
+            reply_to: None,
+            tags: vec![],
+            bot: false,
+            account: None,
+        }
+    }
+
+    /// Whether we sent the message, including echoes and messages from other
+    /// clients using our nickname
+    pub fn is_own(&self, our_nick: Option<NickRef>) -> bool {
+        if matches!(self.direction, Direction::Sent) {
+            return true;
+        }
+
+        match (self.target.source(), our_nick) {
+            (Source::User(user), Some(our_nick)) => user
+                .nickname()
+                .as_ref()
+                .eq_ignore_ascii_case(our_nick.as_ref()),
+            _ => false,
+        }
+    }
+
     pub fn triggers_unread(&self) -> bool {
         matches!(self.direction, Direction::Received)
             && matches!(self.target.source(), Source::User(_) | Source::Action)
@@ -98,47 +176,324 @@ impl Message {
         resolve_attributes: impl Fn(&User, &str) -> Option<User>,
     ) -> Option<Message> {
         let server_time = server_time(&encoded);
+        let id = tag_value(&encoded, "msgid");
+        let reply_to = tag_value(&encoded, "+draft/reply");
+        let account_tag = tag_value(&encoded, "account");
+        // Sent with `RELAYMSG`, so already from the author rather than the relay bot
+        let is_relaymsg = tag_value(&encoded, "draft/relaymsg").is_some();
+        let bot = encoded
+            .tags
+            .iter()
+            .any(|tag| tag.key == "bot" || tag.key == "draft/bot");
         let text = text(&encoded, &our_nick, config, &resolve_attributes)?;
         let target = target(encoded, &our_nick, &resolve_attributes)?;
 
+        let (target, text) = if is_relaymsg {
+            (target, text)
+        } else {
+            relay_author(target, text, &config.buffer.channel.relays)
+        };
+
+        // The `account` tag is the most recent, and covers users we share no channel with
+        let account = match (account_tag, target.source()) {
+            (Some(account), _) => Some(Account::parse(&account)),
+            (None, Source::User(user)) => user.account().cloned(),
+            (None, _) => None,
+        };
+
         Some(Message {
-            received_at: Posix::now(),
             server_time,
-            direction: Direction::Received,
-            target,
-            text,
+            id,
+            reply_to,
+            bot,
+            account,
+            ..Message::new(Direction::Received, target, text)
         })
     }
 
     pub fn file_transfer_request_received(from: &Nick, filename: &str) -> Message {
-        Message {
-            received_at: Posix::now(),
-            server_time: Utc::now(),
-            direction: Direction::Received,
-            target: Target::Query {
+        Message::new(
+            Direction::Received,
+            Target::Query {
                 nick: from.clone(),
                 source: Source::Action,
             },
-            text: format!(" ∙ {from} wants to send you \"{filename}\""),
-        }
+            format!(" ∙ {from} wants to send you \"{filename}\""),
+        )
     }
 
     pub fn file_transfer_request_sent(to: &Nick, filename: &str) -> Message {
-        Message {
-            received_at: Posix::now(),
-            server_time: Utc::now(),
-            direction: Direction::Sent,
-            target: Target::Query {
+        Message::new(
+            Direction::Sent,
+            Target::Query {
                 nick: to.clone(),
                 source: Source::Action,
             },
-            text: format!(" ∙ offering to send {to} \"{filename}\""),
-        }
+            format!(" ∙ offering to send {to} \"{filename}\""),
+        )
+    }
+
+    /// Lists search hits in the buffer the search was made from. Each keeps the time of the
+    /// message it was found in, and links to the buffer of that message
+    pub fn search_results(buffer: Buffer, results: Vec<Message>) -> Vec<Message> {
+        let server = buffer.server().clone();
+
+        let summary = match results.len() {
+            0 => " ∙ Search: no matches found".to_string(),
+            1 => " ∙ Search: 1 match".to_string(),
+            n => format!(" ∙ Search: {n} matches"),
+        };
+
+        let summary = Message::new(
+            Direction::Received,
+            buffer.clone().server_message_target(None),
+            summary,
+        );
+
+        let hits = results.into_iter().map(|result| {
+            let (location, found_in) = match &result.target {
+                Target::Server { .. } => (String::new(), Buffer::Server(server.clone())),
+                Target::Channel { channel, .. } => (
+                    format!("{channel} "),
+                    Buffer::Channel(server.clone(), channel.clone()),
+                ),
+                Target::Query { nick, .. } => (
+                    format!("{nick} "),
+                    Buffer::Query(server.clone(), nick.clone()),
+                ),
+            };
+            let author = match result.target.source() {
+                Source::User(user) => format!("<{}> ", user.nickname()),
+                _ => String::new(),
+            };
+
+            let source = Source::Internal(source::Internal::SearchResult(found_in));
+            let target = match &buffer {
+                Buffer::Server(_) => Target::Server { source },
+                Buffer::Channel(_, channel) => Target::Channel {
+                    channel: channel.clone(),
+                    source,
+                },
+                Buffer::Query(_, nick) => Target::Query {
+                    nick: nick.clone(),
+                    source,
+                },
+            };
+
+            Message {
+                server_time: result.server_time,
+                ..Message::new(
+                    Direction::Received,
+                    target,
+                    format!(
+                        "   {} {location}{author}{}",
+                        result.server_time.with_timezone(&Local).format("%Y-%m-%d"),
+                        result.text.trim_start()
+                    ),
+                )
+            }
+        });
+
+        std::iter::once(summary).chain(hits).collect()
+    }
+
+    /// Lists a help response from the server as status lines, headed by its topic
+    pub fn help(topic: &str, lines: Vec<String>) -> Vec<Message> {
+        let header = match topic {
+            "" | "*" | "index" => " ∙ Help".to_string(),
+            topic => format!(" ∙ Help: {topic}"),
+        };
+
+        std::iter::once(header)
+            .chain(lines.into_iter().map(|line| format!("   {line}")))
+            .map(|text| {
+                Message::new(
+                    Direction::Received,
+                    Target::Server {
+                        source: Source::Server(None),
+                    },
+                    text,
+                )
+            })
+            .collect()
+    }
+
+    /// Lists the servers of a `LINKS` reply as a tree of the network, in the server buffer
+    pub fn links(links: Vec<Link>) -> Vec<Message> {
+        let header = match links.len() {
+            0 => " ∙ Links: no servers shown".to_string(),
+            1 => " ∙ Links: 1 server".to_string(),
+            n => format!(" ∙ Links: {n} servers"),
+        };
+
+        std::iter::once(header)
+            .chain(
+                link_tree(&links)
+                    .into_iter()
+                    .map(|line| format!("   {line}")),
+            )
+            .map(|text| {
+                Message::new(
+                    Direction::Received,
+                    Target::Server {
+                        source: Source::Server(None),
+                    },
+                    text,
+                )
+            })
+            .collect()
+    }
+
+    /// Shows the cached channel list of a server, in place of asking it again
+    pub fn channel_list(cached: &channel_list::Cached) -> Vec<Message> {
+        let fetched_at = cached
+            .fetched_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M");
+        let header = format!(
+            " ∙ Channels listed at {fetched_at}, {} of {} shown. Use /list * for a fresh list",
+            cached.entries.len(),
+            cached.total
+        );
+
+        std::iter::once(header)
+            .chain(
+                cached
+                    .entries
+                    .iter()
+                    .map(|entry| format!("   {} ({}) {}", entry.channel, entry.users, entry.topic)),
+            )
+            .map(|text| {
+                Message::new(
+                    Direction::Received,
+                    Target::Server {
+                        source: Source::Server(None),
+                    },
+                    text,
+                )
+            })
+            .collect()
+    }
+
+    /// Summarizes a `LIST` reply in the server buffer, with a histogram of channel sizes
+    pub fn list_stats(stats: channel_list::Stats) -> Vec<Message> {
+        const BAR_WIDTH: usize = 20;
+
+        let header = format!(
+            " ∙ List: {} channels, {} users",
+            stats.channels, stats.users
+        );
+        let largest = stats.sizes.into_iter().max().unwrap_or_default().max(1);
+
+        std::iter::once(header)
+            .chain(stats.histogram().map(|(label, count)| {
+                let bar = "█".repeat((count * BAR_WIDTH).div_ceil(largest));
+
+                format!("   {label:>7} users  {bar} {count}")
+            }))
+            .map(|text| {
+                Message::new(
+                    Direction::Received,
+                    Target::Server {
+                        source: Source::Server(None),
+                    },
+                    text,
+                )
+            })
+            .collect()
+    }
+
+    /// Reports user modes set on connect which didn't take effect
+    pub fn user_modes_not_set(modes: &str, reason: &str) -> Message {
+        Message::new(
+            Direction::Received,
+            Target::Server {
+                source: Source::Internal(source::Internal::Status(source::Status::Error)),
+            },
+            format!(" ∙ User modes {modes} were not set: {reason}"),
+        )
+    }
+
+    /// Notes in a query that a message wasn't delivered, as the user is offline
+    pub fn undelivered(nick: Nick, queued: bool) -> Message {
+        let (status, text) = if queued {
+            (
+                source::Status::Success,
+                format!(
+                    " ∙ {nick} is offline — message will be delivered when they're back online"
+                ),
+            )
+        } else {
+            (
+                source::Status::Error,
+                format!(" ∙ {nick} is offline — message not delivered"),
+            )
+        };
+
+        Message::new(
+            Direction::Received,
+            Target::Query {
+                nick,
+                source: Source::Internal(source::Internal::Status(status)),
+            },
+            text,
+        )
+    }
+
+    /// Notes in a query that messages queued while the user was offline were sent
+    pub fn delivered(nick: Nick, count: usize) -> Message {
+        let text = match count {
+            1 => format!(" ∙ {nick} is back online — delivered 1 queued message"),
+            n => format!(" ∙ {nick} is back online — delivered {n} queued messages"),
+        };
+
+        Message::new(
+            Direction::Received,
+            Target::Query {
+                nick,
+                source: Source::Internal(source::Internal::Status(source::Status::Success)),
+            },
+            text,
+        )
+    }
+
+    /// Confirms a command which will be applied once the server reconnects
+    pub fn staged(buffer: Buffer, text: String) -> Message {
+        let source = Source::Internal(source::Internal::Status(source::Status::Success));
+
+        let target = match buffer {
+            Buffer::Server(_) => Target::Server { source },
+            Buffer::Channel(_, channel) => Target::Channel { channel, source },
+            Buffer::Query(_, nick) => Target::Query { nick, source },
+        };
+
+        Message::new(Direction::Received, target, format!(" ∙ {text}"))
+    }
+
+    /// Notes that a paste couldn't be uploaded, and was left in the input
+    pub fn paste_failed(buffer: Buffer, reason: &str) -> Message {
+        let source = Source::Internal(source::Internal::Status(source::Status::Error));
+
+        let target = match buffer {
+            Buffer::Server(_) => Target::Server { source },
+            Buffer::Channel(_, channel) => Target::Channel { channel, source },
+            Buffer::Query(_, nick) => Target::Query { nick, source },
+        };
+
+        Message::new(
+            Direction::Received,
+            target,
+            format!(" ∙ Paste wasn't uploaded ({reason}), send it again to send it as lines"),
+        )
     }
 
     pub fn with_target(self, target: Target) -> Self {
         Self { target, ..self }
     }
+
+    pub fn with_tags(self, tags: Vec<Tag>) -> Self {
+        Self { tags, ..self }
+    }
 }
 
 fn target(
@@ -191,6 +546,13 @@ fn target(
                 source: source::Source::Server(None),
             })
         }
+        Command::Numeric(ERR_CANNOTSENDTOCHAN, params) => {
+            let channel = params.get(1)?.clone();
+            Some(Target::Channel {
+                channel,
+                source: Source::Internal(source::Internal::Status(source::Status::Error)),
+            })
+        }
         Command::Numeric(RPL_AWAY, params) => {
             let user = params.get(1)?;
             let target = User::try_from(user.as_str()).ok()?;
@@ -299,6 +661,7 @@ fn target(
         | Command::CAP(_, _, _, _)
         | Command::AUTHENTICATE(_)
         | Command::BATCH(_, _)
+        | Command::CHGHOST(_, _)
         | Command::CNOTICE(_, _, _)
         | Command::CPRIVMSG(_, _, _)
         | Command::KNOCK(_, _)
@@ -306,13 +669,138 @@ fn target(
         | Command::USERIP(_)
         | Command::HELP(_)
         | Command::MODE(_, _, _)
-        | Command::Numeric(_, _)
         | Command::Unknown(_, _) => Some(Target::Server {
             source: Source::Server(None),
         }),
+        Command::Numeric(numeric, _) => Some(Target::Server {
+            source: Source::Server(Some(source::Server::new(
+                source::server::Kind::Numeric(numeric as u16),
+                None,
+            ))),
+        }),
+    }
+}
+
+/// Attributes a message from a relay bot to the author named in its text
+fn relay_author(
+    target: Target,
+    text: String,
+    relays: &[config::channel::Relay],
+) -> (Target, String) {
+    if let Target::Channel {
+        channel,
+        source: Source::User(user),
+    } = &target
+    {
+        if let Some((nick, relayed)) = relays
+            .iter()
+            .find_map(|relay| relay.author(user.nickname().as_ref(), channel, &text))
+        {
+            let target = Target::Channel {
+                channel: channel.clone(),
+                source: Source::User(User::from(Nick::from(nick))),
+            };
+
+            return (target, relayed.to_string());
+        }
+    }
+
+    (target, text)
+}
+
+fn tag_value(message: &Encoded, key: &str) -> Option<String> {
+    message
+        .tags
+        .iter()
+        .find(|tag| tag.key == key)
+        .and_then(|tag| tag.value.clone())
+}
+
+/// A server of the network, as listed by `LINKS`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub server: String,
+    /// Server this one is connected through
+    pub hub: String,
+    pub hops: u32,
+    pub info: String,
+}
+
+impl Link {
+    fn is_root(&self, links: &[Link]) -> bool {
+        self.hub == self.server
+            || self.hops == 0
+            || !links.iter().any(|link| link.server == self.hub)
     }
 }
 
+/// Draws links as a tree below the servers they are connected through. Servers may
+/// restrict what they show, so links whose hub isn't listed are drawn as roots.
+fn link_tree(links: &[Link]) -> Vec<String> {
+    fn describe(link: &Link) -> String {
+        if link.info.is_empty() {
+            link.server.clone()
+        } else {
+            format!("{} ({})", link.server, link.info)
+        }
+    }
+
+    fn branches<'a>(
+        links: &'a [Link],
+        hub: &str,
+        prefix: &str,
+        shown: &mut Vec<&'a str>,
+        lines: &mut Vec<String>,
+    ) {
+        let children = links
+            .iter()
+            .filter(|link| link.hub == hub && !shown.contains(&link.server.as_str()))
+            .collect::<Vec<_>>();
+
+        shown.extend(children.iter().map(|link| link.server.as_str()));
+
+        for (index, link) in children.iter().enumerate() {
+            let (branch, indent) = if index == children.len() - 1 {
+                ("└─", "   ")
+            } else {
+                ("├─", "│  ")
+            };
+
+            lines.push(format!("{prefix}{branch} {}", describe(link)));
+
+            branches(
+                links,
+                &link.server,
+                &format!("{prefix}{indent}"),
+                shown,
+                lines,
+            );
+        }
+    }
+
+    let mut shown = vec![];
+    let mut lines = vec![];
+
+    // Servers in a loop of hubs are never reached from a root, so are listed last
+    let roots = links
+        .iter()
+        .filter(|link| link.is_root(links))
+        .chain(links.iter());
+
+    for root in roots {
+        if shown.contains(&root.server.as_str()) {
+            continue;
+        }
+
+        shown.push(&root.server);
+        lines.push(describe(root));
+
+        branches(links, &root.server, "", &mut shown, &mut lines);
+    }
+
+    lines
+}
+
 pub fn server_time(message: &Encoded) -> DateTime<Utc> {
     message
         .tags
@@ -347,10 +835,7 @@ fn text(
                 .unwrap_or(raw_user)
                 .formatted(config.buffer.server_messages.part.username_format);
 
-            let text = text
-                .as_ref()
-                .map(|text| format!(" ({text})"))
-                .unwrap_or_default();
+            let text = reason(text.as_ref());
 
             Some(format!("⟵ {user} has left the channel{text}"))
         }
@@ -369,10 +854,7 @@ fn text(
             let raw_user = message.user()?;
             let user = resolve_attributes(&raw_user, channel).unwrap_or(raw_user);
 
-            let comment = comment
-                .as_ref()
-                .map(|comment| format!(" ({comment})"))
-                .unwrap_or_default();
+            let comment = reason(comment.as_ref());
             let target = if victim == our_nick.as_ref() {
                 "you have".to_string()
             } else {
@@ -512,6 +994,30 @@ fn text(
 
             Some(format!(" ∙ {user} is away{away_message}"))
         }
+        Command::Numeric(ERR_HELPNOTFOUND, params) => {
+            let topic = params.get(1)?;
+
+            Some(format!(" ∙ No help available on \"{topic}\""))
+        }
+        Command::Numeric(ERR_CANNOTSENDTOCHAN, params) => {
+            let channel = params.get(1)?;
+            let reason = params.get(2).map(String::as_str).unwrap_or_default();
+
+            let guidance = config
+                .buffer
+                .channel
+                .cannot_send
+                .explain
+                .then(|| cannot_send_guidance(reason))
+                .flatten();
+
+            Some(match guidance {
+                Some(guidance) => {
+                    format!(" ∙ Message not sent to {channel}: {guidance} ({reason})")
+                }
+                None => format!(" ∙ Message not sent to {channel}: {reason}"),
+            })
+        }
         Command::Numeric(_, responses) | Command::Unknown(_, responses) => Some(
             responses
                 .iter()
@@ -524,6 +1030,26 @@ fn text(
     }
 }
 
+/// Explains the channel restriction behind `ERR_CANNOTSENDTOCHAN`, which servers
+/// only describe in the free-form reason
+fn cannot_send_guidance(reason: &str) -> Option<&'static str> {
+    let words = reason.to_lowercase();
+
+    if reason.contains("+m") || words.contains("moderated") {
+        Some("the channel is moderated (+m), you need voice (+v) to speak")
+    } else if reason.contains("+b") || words.contains("banned") {
+        Some("you are banned (+b) from speaking in the channel")
+    } else if reason.contains("+n") || words.contains("external") {
+        Some("the channel only accepts messages from members (+n), join it first")
+    } else if reason.contains("+M") || reason.contains("+R") || words.contains("regist") {
+        Some("the channel only accepts registered users, identify with services first")
+    } else if reason.contains("+c") || words.contains("color") || words.contains("colour") {
+        Some("the channel blocks colors and formatting (+c)")
+    } else {
+        None
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Limit {
     Top(usize),
@@ -548,6 +1074,14 @@ fn is_action(text: &str) -> bool {
     text.starts_with("\u{1}ACTION ") && text.ends_with('\u{1}')
 }
 
+/// A part, kick or quit reason as shown after its message, kept as is like the text of
+/// other messages so it's rendered the same way
+pub(crate) fn reason(reason: Option<&String>) -> String {
+    reason
+        .map(|reason| format!(" ({reason})"))
+        .unwrap_or_default()
+}
+
 pub fn parse_action(nick: NickRef, text: &str) -> Option<String> {
     let action = text.strip_prefix("\u{1}ACTION ")?.strip_suffix('\u{1}')?;
     Some(action_text(nick, action))
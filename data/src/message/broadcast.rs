@@ -1,8 +1,7 @@
 //! Generate messages that can be broadcast into every buffer
 use chrono::{DateTime, Utc};
 
-use super::{source, Direction, Message, Source, Target};
-use crate::time::Posix;
+use super::{reason, source, Direction, Message, Source, Target};
 use crate::user::Nick;
 use crate::{Config, User};
 
@@ -21,11 +20,8 @@ fn expand(
 ) -> Vec<Message> {
     let message = |target, text| -> Message {
         Message {
-            received_at: Posix::now(),
             server_time: sent_time,
-            direction: Direction::Received,
-            target,
-            text,
+            ..Message::new(Direction::Received, target, text)
         }
     };
 
@@ -143,10 +139,7 @@ pub fn quit(
     config: &Config,
     sent_time: DateTime<Utc>,
 ) -> Vec<Message> {
-    let comment = comment
-        .as_ref()
-        .map(|comment| format!(" ({comment})"))
-        .unwrap_or_default();
+    let comment = reason(comment.as_ref());
     let text = format!(
         "⟵ {} has quit{comment}",
         user.formatted(config.buffer.server_messages.quit.username_format)
@@ -199,3 +192,67 @@ pub fn invite(
 
     expand(channels, [], false, Cause::Server(None), text, sent_time)
 }
+
+pub fn change_host(
+    channels: impl IntoIterator<Item = String>,
+    queries: impl IntoIterator<Item = Nick>,
+    old_user: &User,
+    new_username: &str,
+    new_hostname: &str,
+    ourself: bool,
+    logged_in: bool,
+    sent_time: DateTime<Utc>,
+) -> Vec<Message> {
+    let old_nick = old_user.nickname();
+
+    let text = match (ourself, logged_in) {
+        (true, true) => format!(" ∙ You're now logged in and cloaked as {new_hostname}"),
+        (true, false) => format!(" ∙ You've changed host to {new_username}@{new_hostname}"),
+        (false, _) => format!(" ∙ {old_nick} changed host to {new_username}@{new_hostname}"),
+    };
+
+    expand(
+        channels,
+        queries,
+        false,
+        Cause::Server(Some(source::Server::new(
+            source::server::Kind::ChangeHost,
+            Some(old_nick.to_owned()),
+        ))),
+        text,
+        sent_time,
+    )
+}
+
+pub fn sasl_authenticated(
+    mechanism: &str,
+    account: Option<&str>,
+    sent_time: DateTime<Utc>,
+) -> Vec<Message> {
+    let account = account
+        .map(|account| format!(" as {account}"))
+        .unwrap_or_default();
+    let text = format!(" ∙ SASL {mechanism}: authenticated{account}");
+
+    expand(
+        [],
+        [],
+        true,
+        Cause::Status(source::Status::Success),
+        text,
+        sent_time,
+    )
+}
+
+pub fn sasl_failed(mechanism: &str, reason: &str, sent_time: DateTime<Utc>) -> Vec<Message> {
+    let text = format!(" ∙ SASL {mechanism}: authentication failed ({reason})");
+
+    expand(
+        [],
+        [],
+        true,
+        Cause::Status(source::Status::Error),
+        text,
+        sent_time,
+    )
+}
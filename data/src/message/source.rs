@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::User;
+use crate::{Buffer, User};
 
 pub use self::server::Server;
 
@@ -12,9 +12,11 @@ pub enum Source {
     Internal(Internal),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Internal {
     Status(Status),
+    /// A search hit, linking to the buffer it was found in
+    SearchResult(Buffer),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -64,6 +66,9 @@ pub mod server {
         Part,
         Quit,
         ReplyTopic,
+        ChangeHost,
+        /// A numeric reply shown in the server buffer
+        Numeric(u16),
     }
 
     #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
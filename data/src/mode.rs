@@ -165,6 +165,53 @@ where
     parsed
 }
 
+/// Splits a user modestring into the modes listed in `available` and those which aren't,
+/// e.g. `+iRx` with `iow` available is `("+i", "+Rx")`
+pub fn supported_user_modes(modestring: &str, available: &str) -> (String, String) {
+    let (supported, unsupported): (Vec<_>, Vec<_>) =
+        user_modes(modestring).partition(|(_, mode)| available.contains(*mode));
+
+    (user_modestring(supported), user_modestring(unsupported))
+}
+
+/// Modes added by the `requested` user modestring missing from those the server `applied`.
+/// Removing a mode which wasn't set isn't echoed, so removals aren't compared.
+pub fn unapplied_user_modes(requested: &str, applied: &str) -> String {
+    let applied = user_modes(applied).collect::<Vec<_>>();
+
+    user_modestring(user_modes(requested).filter(|mode| mode.0 && !applied.contains(mode)))
+}
+
+/// Modes of a user modestring as `(added, mode)` pairs
+fn user_modes(modestring: &str) -> impl Iterator<Item = (bool, char)> + '_ {
+    let mut added = true;
+
+    modestring.chars().filter_map(move |c| match c {
+        '+' | '-' => {
+            added = c == '+';
+            None
+        }
+        c if c.is_whitespace() => None,
+        c => Some((added, c)),
+    })
+}
+
+fn user_modestring(modes: impl IntoIterator<Item = (bool, char)>) -> String {
+    let mut modestring = String::new();
+    let mut sign = None;
+
+    for (added, mode) in modes {
+        if sign != Some(added) {
+            modestring.push(if added { '+' } else { '-' });
+            sign = Some(added);
+        }
+
+        modestring.push(mode);
+    }
+
+    modestring
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -191,4 +238,24 @@ mod test {
             assert_eq!(modes, expected);
         }
     }
+
+    #[test]
+    fn user() {
+        let tests = [
+            ("+iRx", "iow", ("+i", "+Rx")),
+            ("+i-x+w", "iwx", ("+i-x+w", "")),
+            ("+i-x+w", "iw", ("+iw", "-x")),
+            ("i", "", ("", "+i")),
+        ];
+
+        for (modestring, available, (supported, unsupported)) in tests {
+            assert_eq!(
+                supported_user_modes(modestring, available),
+                (supported.to_string(), unsupported.to_string())
+            );
+        }
+
+        assert_eq!(unapplied_user_modes("+iR-x", "+i"), "+R");
+        assert_eq!(unapplied_user_modes("+i", "+iw"), "");
+    }
 }
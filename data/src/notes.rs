@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::user::{Nick, NickRef};
+use crate::Server;
+
+/// Notes kept about users of each server, set with `/note`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Notes(BTreeMap<Server, BTreeMap<Nick, String>>);
+
+impl Notes {
+    pub fn get<'a>(&'a self, server: &Server, nick: NickRef) -> Option<&'a str> {
+        self.0
+            .get(server)?
+            .iter()
+            .find_map(|(noted, note)| (nick == *noted).then_some(note.as_str()))
+    }
+
+    /// Sets the note about `nick`, or removes it when `note` is `None`
+    pub fn set(&mut self, server: &Server, nick: Nick, note: Option<String>) {
+        match note {
+            Some(note) => {
+                self.0.entry(server.clone()).or_default().insert(nick, note);
+            }
+            None => {
+                if let Some(notes) = self.0.get_mut(server) {
+                    notes.remove(&nick);
+
+                    if notes.is_empty() {
+                        self.0.remove(server);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn set_and_clear() {
+        let server = Server::from("libera");
+        let mut notes = Notes::default();
+
+        notes.set(
+            &server,
+            Nick::from("casper"),
+            Some("halloy dev".to_string()),
+        );
+        assert_eq!(
+            notes.get(&server, NickRef::from("casper")),
+            Some("halloy dev")
+        );
+        assert_eq!(
+            notes.get(&Server::from("oftc"), NickRef::from("casper")),
+            None
+        );
+
+        notes.set(&server, Nick::from("casper"), None);
+        assert_eq!(notes.get(&server, NickRef::from("casper")), None);
+    }
+}
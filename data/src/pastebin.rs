@@ -0,0 +1,36 @@
+use crate::config;
+
+/// Uploads `text` to the configured pastebin, returning the link to the paste
+pub async fn upload(config: config::buffer::Pastebin, text: String) -> Result<String, Error> {
+    let url = config.url.ok_or(Error::NotConfigured)?;
+
+    let client = reqwest::Client::builder().user_agent("halloy").build()?;
+
+    let mut request = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(text);
+
+    if let Some(auth) = config.auth {
+        request = request.header(reqwest::header::AUTHORIZATION, auth);
+    }
+
+    let response = request.send().await?.error_for_status()?;
+    let link = response.text().await?.trim().to_string();
+
+    // Anything but a link would be sent to the channel in place of the paste
+    match url::Url::parse(&link) {
+        Ok(_) if !link.contains(char::is_whitespace) => Ok(link),
+        _ => Err(Error::InvalidResponse),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("no pastebin url configured")]
+    NotConfigured,
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("response wasn't a link")]
+    InvalidResponse,
+}
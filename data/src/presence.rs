@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use irc::proto::{self, command, Command};
+use itertools::Itertools;
+
+use crate::user::Nick;
+use crate::{isupport, User};
+
+/// Targets sent per `MONITOR` or `WATCH` line
+const TARGETS_PER_MESSAGE: usize = 20;
+
+/// Protocol used to learn when users come online or go offline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// `MONITOR`, with an optional limit on the number of targets
+    Monitor(Option<u16>),
+    /// `WATCH`, which older networks advertise instead of `MONITOR`
+    Watch(u16),
+}
+
+impl Backend {
+    /// Prefers `MONITOR`, falling back to `WATCH` when it is all the server advertises
+    pub fn from_isupport(isupport: &HashMap<isupport::Kind, isupport::Parameter>) -> Option<Self> {
+        match (
+            isupport.get(&isupport::Kind::MONITOR),
+            isupport.get(&isupport::Kind::WATCH),
+        ) {
+            (Some(isupport::Parameter::MONITOR(limit)), _) => Some(Backend::Monitor(*limit)),
+            (_, Some(isupport::Parameter::WATCH(limit))) => Some(Backend::Watch(*limit)),
+            _ => None,
+        }
+    }
+
+    fn limit(&self) -> Option<usize> {
+        match self {
+            Backend::Monitor(limit) => limit.map(usize::from),
+            Backend::Watch(limit) => Some(usize::from(*limit)),
+        }
+    }
+
+    /// Messages which start tracking `nicks`, up to the server's limit
+    pub fn add(&self, nicks: &[Nick]) -> Vec<proto::Message> {
+        self.messages(nicks, '+')
+    }
+
+    /// Messages which stop tracking `nicks`
+    pub fn remove(&self, nicks: &[Nick]) -> Vec<proto::Message> {
+        self.messages(nicks, '-')
+    }
+
+    fn messages(&self, nicks: &[Nick], symbol: char) -> Vec<proto::Message> {
+        let nicks = &nicks[..self.limit().unwrap_or(nicks.len()).min(nicks.len())];
+
+        nicks
+            .chunks(TARGETS_PER_MESSAGE)
+            .map(|chunk| match self {
+                Backend::Monitor(_) => {
+                    command!("MONITOR", symbol.to_string(), chunk.iter().join(","))
+                }
+                Backend::Watch(_) => proto::command(
+                    "WATCH",
+                    chunk.iter().map(|nick| format!("{symbol}{nick}")).collect(),
+                ),
+            })
+            .collect()
+    }
+}
+
+/// Change in presence of tracked users, regardless of the backend that reported it
+#[derive(Debug, Clone)]
+pub enum Update {
+    Online(Vec<User>),
+    Offline(Vec<Nick>),
+}
+
+impl Update {
+    /// Parses the presence numerics of both `MONITOR` and `WATCH`
+    pub fn parse(command: &Command) -> Option<Self> {
+        use proto::command::Numeric::*;
+
+        match command {
+            Command::Numeric(RPL_MONONLINE, args) => Some(Update::Online(
+                args.get(1)?
+                    .split(',')
+                    .filter_map(|target| User::try_from(target).ok())
+                    .collect(),
+            )),
+            Command::Numeric(RPL_MONOFFLINE, args) => Some(Update::Offline(
+                args.get(1)?.split(',').map(Nick::from).collect(),
+            )),
+            // <client> <nick> <user> <host> <timestamp> :<text>
+            Command::Numeric(RPL_LOGON | RPL_NOWON, args) => {
+                let nick = args.get(1)?;
+                let username = args.get(2)?;
+                let hostname = args.get(3)?;
+
+                let user = User::try_from(format!("{nick}!{username}@{hostname}")).ok()?;
+
+                Some(Update::Online(vec![user]))
+            }
+            Command::Numeric(RPL_LOGOFF | RPL_NOWOFF, args) => {
+                Some(Update::Offline(vec![Nick::from(args.get(1)?.as_str())]))
+            }
+            _ => None,
+        }
+    }
+}
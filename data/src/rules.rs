@@ -0,0 +1,337 @@
+//! Actions run when something happens on a server, configured in `rules.toml`
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use irc::proto::Command;
+use serde::Deserialize;
+
+use crate::message::{self, Message};
+use crate::user::{Nick, NickRef};
+use crate::{Buffer, Server};
+
+/// Events sent longer ago than this are played back history, e.g. from `chathistory`, rather
+/// than something which just happened
+const RECENT: chrono::Duration = chrono::Duration::seconds(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Trigger {
+    /// We are highlighted in a channel
+    Highlight,
+    /// A message in a query
+    Query,
+    /// A message in a channel
+    Message,
+    Join,
+    Part,
+}
+
+impl Trigger {
+    pub fn title(self) -> &'static str {
+        match self {
+            Trigger::Highlight => "Highlight",
+            Trigger::Query => "Query",
+            Trigger::Message => "Message",
+            Trigger::Join => "Join",
+            Trigger::Part => "Part",
+        }
+    }
+}
+
+/// Runs its actions on events of `on` which match all of `server`, `channel` and `nick`
+/// that are set, at most once every `cooldown` seconds
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    pub on: Trigger,
+    #[serde(default)]
+    pub server: Option<String>,
+    #[serde(default)]
+    pub channel: Option<String>,
+    #[serde(default)]
+    pub nick: Option<String>,
+    /// Body of a notification
+    #[serde(default)]
+    pub notify: Option<String>,
+    /// Sound of the notification
+    #[serde(default)]
+    pub sound: Option<String>,
+    /// Program and arguments to run, without a shell
+    #[serde(default)]
+    pub run: Vec<String>,
+    /// Message sent to the channel or query of the event
+    #[serde(default)]
+    pub send: Option<String>,
+    #[serde(default = "default_cooldown")]
+    pub cooldown: u64,
+}
+
+impl Rule {
+    fn matches(&self, event: &Event) -> bool {
+        let matches = |filter: &Option<String>, value: Option<&str>| {
+            filter.as_ref().map_or(true, |filter| {
+                value.is_some_and(|value| filter.eq_ignore_ascii_case(value))
+            })
+        };
+
+        self.on == event.trigger
+            && matches(&self.server, Some(event.server.as_ref()))
+            && matches(&self.channel, event.channel.as_deref())
+            && matches(&self.nick, Some(event.nick.as_ref()))
+    }
+}
+
+/// Something another user did, which rules can act on
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub trigger: Trigger,
+    pub server: Server,
+    pub channel: Option<String>,
+    pub nick: Nick,
+    pub text: String,
+    pub server_time: DateTime<Utc>,
+}
+
+impl Event {
+    /// A message from another user in a channel or query
+    pub fn message(server: &Server, message: &Message, our_nick: NickRef) -> Option<Self> {
+        let message::Source::User(user) = message.target.source() else {
+            return None;
+        };
+
+        if message.is_own(Some(our_nick)) {
+            return None;
+        }
+
+        let (trigger, channel) = match &message.target {
+            message::Target::Channel { channel, .. } => (Trigger::Message, Some(channel.clone())),
+            message::Target::Query { .. } => (Trigger::Query, None),
+            _ => return None,
+        };
+
+        Some(Self {
+            trigger,
+            server: server.clone(),
+            channel,
+            nick: user.nickname().to_owned(),
+            text: message.text.clone(),
+            server_time: message.server_time,
+        })
+    }
+
+    /// Another user joining or leaving a channel
+    pub fn membership(
+        server: &Server,
+        encoded: &message::Encoded,
+        our_nick: NickRef,
+    ) -> Option<Self> {
+        let user = encoded.user()?;
+
+        if user.nickname() == our_nick {
+            return None;
+        }
+
+        let (trigger, channel, text) = match &encoded.command {
+            Command::JOIN(channel, _) => (Trigger::Join, channel, None),
+            Command::PART(channel, reason) => (Trigger::Part, channel, reason.as_ref()),
+            _ => return None,
+        };
+
+        Some(Self {
+            trigger,
+            server: server.clone(),
+            channel: Some(channel.clone()),
+            nick: user.nickname().to_owned(),
+            text: text.cloned().unwrap_or_default(),
+            server_time: message::server_time(encoded),
+        })
+    }
+
+    pub fn highlight(
+        server: &Server,
+        channel: &str,
+        nick: NickRef,
+        text: &str,
+        server_time: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            trigger: Trigger::Highlight,
+            server: server.clone(),
+            channel: Some(channel.to_string()),
+            nick: nick.to_owned(),
+            text: text.to_string(),
+            server_time,
+        }
+    }
+
+    /// Whether the event just happened, rather than being played back
+    pub fn is_recent(&self, now: DateTime<Utc>) -> bool {
+        now - self.server_time < RECENT
+    }
+
+    /// Body of a notification for the event, when a rule doesn't set one
+    pub fn describe(&self) -> String {
+        let nick = &self.nick;
+        let channel = self.channel.as_deref().unwrap_or_default();
+
+        match self.trigger {
+            Trigger::Highlight => format!("{nick} highlighted you in {channel}"),
+            Trigger::Query => format!("{nick} sent you a message"),
+            Trigger::Message => format!("{nick} spoke in {channel}"),
+            Trigger::Join => format!("{nick} joined {channel}"),
+            Trigger::Part => format!("{nick} left {channel}"),
+        }
+    }
+
+    /// Where a reply to the event goes
+    fn buffer(&self) -> Buffer {
+        match &self.channel {
+            Some(channel) => Buffer::Channel(self.server.clone(), channel.clone()),
+            None => Buffer::Query(self.server.clone(), self.nick.clone()),
+        }
+    }
+
+    /// Replaces `{server}`, `{channel}`, `{nick}` and `{text}` in `template`
+    fn expand(&self, template: &str) -> String {
+        template
+            .replace("{server}", self.server.as_ref())
+            .replace("{channel}", self.channel.as_deref().unwrap_or_default())
+            .replace("{nick}", self.nick.as_ref())
+            .replace("{text}", &self.text)
+    }
+}
+
+/// The actions of a rule which matched an event, with its placeholders filled in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Actions {
+    pub title: &'static str,
+    pub notify: Option<String>,
+    pub sound: Option<String>,
+    pub run: Vec<String>,
+    pub send: Option<(Buffer, String)>,
+}
+
+/// When each rule last ran, to hold it back for its cooldown
+#[derive(Debug, Default)]
+pub struct Engine {
+    last_run: HashMap<usize, Instant>,
+}
+
+impl Engine {
+    pub fn actions(&mut self, rules: &[Rule], event: &Event, now: Instant) -> Vec<Actions> {
+        rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.matches(event))
+            .filter(|(index, rule)| {
+                let cooldown = Duration::from_secs(rule.cooldown);
+                let is_ready = self
+                    .last_run
+                    .get(index)
+                    .map_or(true, |last_run| now.duration_since(*last_run) >= cooldown);
+
+                if is_ready {
+                    self.last_run.insert(*index, now);
+                }
+
+                is_ready
+            })
+            .map(|(_, rule)| Actions {
+                title: event.trigger.title(),
+                notify: rule.notify.as_deref().map(|notify| event.expand(notify)),
+                sound: rule.sound.clone(),
+                run: rule.run.iter().map(|arg| event.expand(arg)).collect(),
+                send: rule
+                    .send
+                    .as_deref()
+                    .map(|send| (event.buffer(), event.expand(send))),
+            })
+            .collect()
+    }
+}
+
+fn default_cooldown() -> u64 {
+    10
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(on: Trigger, nick: Option<&str>) -> Rule {
+        Rule {
+            on,
+            server: None,
+            channel: Some("#halloy".to_string()),
+            nick: nick.map(String::from),
+            notify: Some("{nick} joined {channel}".to_string()),
+            sound: None,
+            run: vec!["echo".to_string(), "{nick}".to_string()],
+            send: Some("welcome, {nick}".to_string()),
+            cooldown: 60,
+        }
+    }
+
+    fn join(nick: &str) -> Event {
+        Event {
+            trigger: Trigger::Join,
+            server: Server::from("libera"),
+            channel: Some("#Halloy".to_string()),
+            nick: Nick::from(nick),
+            text: String::new(),
+            server_time: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn actions() {
+        let rules = [
+            rule(Trigger::Join, Some("casper")),
+            rule(Trigger::Part, None),
+        ];
+        let mut engine = Engine::default();
+        let now = Instant::now();
+
+        assert_eq!(
+            engine.actions(&rules, &join("Casper"), now),
+            vec![Actions {
+                title: "Join",
+                notify: Some("Casper joined #Halloy".to_string()),
+                sound: None,
+                run: vec!["echo".to_string(), "Casper".to_string()],
+                send: Some((
+                    Buffer::Channel(Server::from("libera"), "#Halloy".to_string()),
+                    "welcome, Casper".to_string()
+                )),
+            }]
+        );
+        assert!(engine.actions(&rules, &join("cory"), now).is_empty());
+    }
+
+    #[test]
+    fn cooldown() {
+        let rules = [rule(Trigger::Join, None)];
+        let mut engine = Engine::default();
+        let now = Instant::now();
+
+        assert_eq!(engine.actions(&rules, &join("casper"), now).len(), 1);
+        assert!(engine
+            .actions(&rules, &join("cory"), now + Duration::from_secs(30))
+            .is_empty());
+        assert_eq!(
+            engine
+                .actions(&rules, &join("cory"), now + Duration::from_secs(60))
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn playback() {
+        let event = join("casper");
+
+        assert!(event.is_recent(event.server_time + chrono::Duration::seconds(5)));
+        assert!(!event.is_recent(event.server_time + chrono::Duration::minutes(5)));
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::BTreeMap;
 use std::fmt;
 use std::fs;
+use std::sync::{Arc, Mutex};
 
 use futures::channel::mpsc::Sender;
 use irc::proto;
@@ -48,6 +49,23 @@ impl<'a> From<(&'a Server, &'a config::Server)> for Entry {
     }
 }
 
+/// Nicknames set while a server is disconnected, which its stream registers with when it
+/// reconnects
+#[derive(Debug, Clone, Default)]
+pub struct StagedNicks(Arc<Mutex<BTreeMap<Server, String>>>);
+
+impl StagedNicks {
+    pub fn stage(&self, server: &Server, nick: String) {
+        if let Ok(mut nicks) = self.0.lock() {
+            nicks.insert(server.clone(), nick);
+        }
+    }
+
+    pub fn take(&self, server: &Server) -> Option<String> {
+        self.0.lock().ok()?.remove(server)
+    }
+}
+
 #[derive(Debug, Clone, Default, Deserialize)]
 pub struct Map(BTreeMap<Server, config::Server>);
 
@@ -60,6 +78,15 @@ impl Map {
         self.0.remove(server);
     }
 
+    /// Points `server` at `host` and `port` for the rest of the session, which
+    /// restarts its connection
+    pub fn redirect(&mut self, server: &Server, host: String, port: u16) {
+        if let Some(config) = self.0.get_mut(server) {
+            config.server = host;
+            config.port = port;
+        }
+    }
+
     pub fn contains(&self, server: &Server) -> bool {
         self.0.contains_key(server)
     }
@@ -33,6 +33,7 @@ pub enum Command {
     CycleNextBuffer,
     CyclePreviousBuffer,
     LeaveBuffer,
+    UndoLeaveBuffer,
     ToggleNicklist,
     ToggleSidebar,
     CommandBar,
@@ -112,6 +113,7 @@ impl KeyBind {
     default!(cycle_next_buffer, Tab, CTRL);
     default!(cycle_previous_buffer, Tab, CTRL | SHIFT);
     default!(leave_buffer, "w", COMMAND | SHIFT);
+    default!(undo_leave_buffer, "t", COMMAND | SHIFT);
     default!(toggle_nick_list, "m", COMMAND | ALT);
     default!(toggle_sidebar, "b", COMMAND | ALT);
     default!(command_bar, "k", COMMAND);
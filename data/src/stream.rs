@@ -6,12 +6,12 @@ use futures::never::Never;
 use futures::{future, stream, FutureExt, SinkExt, StreamExt};
 use irc::proto::{self, command, Command};
 use irc::{codec, connection, Connection};
-use tokio::time::{self, Instant, Interval};
+use tokio::time::{self, Instant, Interval, MissedTickBehavior};
 
 use crate::client::Client;
 use crate::server::Server;
 use crate::time::Posix;
-use crate::{config, message, server};
+use crate::{channel_list, config, message, server};
 
 pub type Result<T = Update, E = Error> = std::result::Result<T, E>;
 
@@ -52,6 +52,10 @@ enum State {
         batch: Batch,
         ping_time: Interval,
         ping_timeout: Option<Interval>,
+        /// Tokens of our pings which haven't been answered yet
+        pending_pings: Vec<String>,
+        server_pings: ServerPings,
+        resume: Resume,
     },
     Quit,
 }
@@ -62,6 +66,8 @@ enum Input {
     Send(proto::Message),
     Ping,
     PingTimeout,
+    ResumeCheck,
+    ServerPingCheck,
 }
 
 struct Stream {
@@ -72,9 +78,10 @@ struct Stream {
 pub async fn run(
     server: server::Entry,
     proxy: Option<config::Proxy>,
+    staged_nicks: server::StagedNicks,
     mut sender: mpsc::Sender<Update>,
 ) -> Never {
-    let server::Entry { server, config } = server;
+    let server::Entry { server, mut config } = server;
 
     let reconnect_delay = Duration::from_secs(config.reconnect_delay);
 
@@ -102,6 +109,11 @@ pub async fn run(
                     }
                 }
 
+                // Kept for later reconnects too, as the nickname is now ours
+                if let Some(nick) = staged_nicks.take(&server) {
+                    config.nickname = nick;
+                }
+
                 match connect(server.clone(), config.clone(), proxy.clone()).await {
                     Ok((stream, client)) => {
                         log::info!("[{server}] connected");
@@ -122,6 +134,9 @@ pub async fn run(
                             batch: Batch::new(),
                             ping_timeout: None,
                             ping_time: ping_time_interval(config.ping_time),
+                            pending_pings: vec![],
+                            server_pings: ServerPings::new(),
+                            resume: Resume::new(),
                         };
                     }
                     Err(e) => {
@@ -150,6 +165,9 @@ pub async fn run(
                 batch,
                 ping_time,
                 ping_timeout,
+                pending_pings,
+                server_pings,
+                resume,
             } => {
                 let input = {
                     let mut select = stream::select_all([
@@ -157,8 +175,25 @@ pub async fn run(
                         (&mut stream.receiver).map(Input::Send).boxed(),
                         ping_time.tick().into_stream().map(|_| Input::Ping).boxed(),
                         batch.map(Input::Batch).boxed(),
+                        server_pings
+                            .interval
+                            .tick()
+                            .into_stream()
+                            .map(|_| Input::ServerPingCheck)
+                            .boxed(),
                     ]);
 
+                    if config.check_on_resume {
+                        select.push(
+                            resume
+                                .interval
+                                .tick()
+                                .into_stream()
+                                .map(|_| Input::ResumeCheck)
+                                .boxed(),
+                        );
+                    }
+
                     if let Some(timeout) = ping_timeout.as_mut() {
                         select.push(
                             timeout
@@ -175,12 +210,28 @@ pub async fn run(
                 match input {
                     Input::IrcMessage(Ok(Ok(message))) => match message.command {
                         proto::Command::PING(token) => {
-                            let _ = stream.connection.send(command!("PONG", token)).await;
-                        }
-                        proto::Command::PONG(_, token) => {
-                            let token = token.unwrap_or_default();
-                            log::trace!("[{server}] pong received: {token}");
+                            log::trace!("[{server}] ping received: {token}");
 
+                            server_pings.received();
+
+                            if let Err(e) = stream.connection.send(command!("PONG", token)).await {
+                                log::warn!("[{server}] failed to answer ping: {e}");
+                            }
+                        }
+                        proto::Command::PONG(source, token) => {
+                            // Servers answering `PING :token` with `PONG :token` leave out the
+                            // source instead
+                            let token = token.unwrap_or(source);
+
+                            // Some servers and bouncers answer with a token of their own, any
+                            // pong still shows the connection is alive
+                            if pending_pings.contains(&token) {
+                                log::trace!("[{server}] pong received: {token}");
+                            } else {
+                                log::debug!("[{server}] pong received for unknown ping: {token}");
+                            }
+
+                            pending_pings.clear();
                             *ping_timeout = None;
                         }
                         proto::Command::ERROR(error) => {
@@ -241,12 +292,38 @@ pub async fn run(
                         let now = Posix::now().as_nanos().to_string();
                         log::trace!("[{server}] ping sent: {now}");
 
-                        let _ = stream.connection.send(command!("PING", now)).await;
+                        let _ = stream.connection.send(command!("PING", &now)).await;
+                        pending_pings.push(now);
 
                         if ping_timeout.is_none() {
                             *ping_timeout = Some(ping_timeout_interval(config.ping_timeout));
                         }
                     }
+                    Input::ResumeCheck => {
+                        if resume.has_slept() {
+                            let now = Posix::now().as_nanos().to_string();
+                            log::info!("[{server}] resumed from sleep, ping sent: {now}");
+
+                            let _ = stream.connection.send(command!("PING", &now)).await;
+                            pending_pings.push(now);
+
+                            // Connections rarely survive sleep, so don't wait the usual timeout
+                            *ping_timeout = Some(ping_timeout_interval(config.resume_ping_timeout));
+                        }
+                    }
+                    Input::ServerPingCheck => {
+                        if server_pings.have_stopped() {
+                            let now = Posix::now().as_nanos().to_string();
+                            log::warn!("[{server}] server stopped pinging, ping sent: {now}");
+
+                            let _ = stream.connection.send(command!("PING", &now)).await;
+                            pending_pings.push(now);
+
+                            if ping_timeout.is_none() {
+                                *ping_timeout = Some(ping_timeout_interval(config.ping_timeout));
+                            }
+                        }
+                    }
                     Input::PingTimeout => {
                         log::warn!("[{server}] ping timeout");
                         let _ = sender
@@ -276,16 +353,18 @@ async fn connect(
     config: config::Server,
     proxy: Option<config::Proxy>,
 ) -> Result<(Stream, Client), connection::Error> {
-    let connection = Connection::new(config.connection(proxy), irc::Codec).await?;
+    let connection = Connection::new(config.connection(proxy), irc::Codec::default()).await?;
 
     let (sender, receiver) = mpsc::channel(100);
 
+    let channel_list = channel_list::load(&server).await.ok();
+
     Ok((
         Stream {
             connection,
             receiver,
         },
-        Client::new(server, config, sender),
+        Client::new(server, config, sender, channel_list),
     ))
 }
 
@@ -332,6 +411,118 @@ impl futures::Stream for Batch {
     }
 }
 
+/// Notices the system resuming from sleep (or otherwise stalling), when noticeably more time has
+/// passed between checks than expected
+struct Resume {
+    interval: Interval,
+    last_instant: Instant,
+    last_time: DateTime<Utc>,
+}
+
+impl Resume {
+    const INTERVAL_SECS: u64 = 5;
+    const SLEPT_SECS: u64 = 30;
+
+    fn new() -> Self {
+        let mut interval = time::interval_at(
+            Instant::now() + Duration::from_secs(Self::INTERVAL_SECS),
+            Duration::from_secs(Self::INTERVAL_SECS),
+        );
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Self {
+            interval,
+            last_instant: Instant::now(),
+            last_time: Utc::now(),
+        }
+    }
+
+    fn has_slept(&mut self) -> bool {
+        // The monotonic clock doesn't advance while suspended on every platform,
+        // but the wall clock does, so take whichever saw more time pass
+        let elapsed = self
+            .last_instant
+            .elapsed()
+            .max((Utc::now() - self.last_time).to_std().unwrap_or_default());
+
+        self.last_instant = Instant::now();
+        self.last_time = Utc::now();
+
+        elapsed > Duration::from_secs(Self::INTERVAL_SECS + Self::SLEPT_SECS)
+    }
+}
+
+/// Follows how often the server pings us, so the pings suddenly stopping, as when the
+/// connection silently died, is noticed before our own ping is due
+struct ServerPings {
+    interval: Interval,
+    last: Option<Instant>,
+    cadence: Option<Duration>,
+    count: usize,
+    stopped: bool,
+}
+
+impl ServerPings {
+    const INTERVAL_SECS: u64 = 10;
+    /// Pings received before their cadence is relied on
+    const MIN_PINGS: usize = 3;
+    /// Shortest cadence considered, so pings sent in quick succession don't make an
+    /// ordinary pause look like the server stopped
+    const MIN_CADENCE_SECS: u64 = 30;
+    /// Pings missed before the server is taken to have stopped
+    const MISSED_PINGS: u32 = 2;
+
+    fn new() -> Self {
+        let mut interval = time::interval_at(
+            Instant::now() + Duration::from_secs(Self::INTERVAL_SECS),
+            Duration::from_secs(Self::INTERVAL_SECS),
+        );
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Self {
+            interval,
+            last: None,
+            cadence: None,
+            count: 0,
+            stopped: false,
+        }
+    }
+
+    fn received(&mut self) {
+        let now = Instant::now();
+
+        if let Some(last) = self.last {
+            let elapsed = now - last;
+
+            // Smoothed, so a single late ping doesn't throw it off
+            self.cadence = Some(match self.cadence {
+                Some(cadence) => (cadence * 3 + elapsed) / 4,
+                None => elapsed,
+            });
+        }
+
+        self.last = Some(now);
+        self.count += 1;
+        self.stopped = false;
+    }
+
+    /// Whether the server stopped pinging us, only true once until it pings again
+    fn have_stopped(&mut self) -> bool {
+        let (Some(last), Some(cadence)) = (self.last, self.cadence) else {
+            return false;
+        };
+
+        if self.stopped || self.count < Self::MIN_PINGS {
+            return false;
+        }
+
+        let cadence = cadence.max(Duration::from_secs(Self::MIN_CADENCE_SECS));
+
+        self.stopped = last.elapsed() > cadence * (Self::MISSED_PINGS + 1);
+        self.stopped
+    }
+}
+
 fn ping_time_interval(secs: u64) -> Interval {
     time::interval_at(
         Instant::now() + Duration::from_secs(secs),
@@ -13,10 +13,10 @@ pub struct Theme {
 }
 
 impl Theme {
-    pub fn new(name: String, palette: &Palette) -> Self {
+    pub fn new(name: String, palette: &Palette, sidebar: &SidebarPalette) -> Self {
         Theme {
             name,
-            colors: Colors::new(palette),
+            colors: Colors::new(palette).with_sidebar(sidebar),
         }
     }
 }
@@ -40,6 +40,9 @@ pub struct Colors {
     pub error: Subpalette,
     pub info: Subpalette,
     pub success: Subpalette,
+    pub sidebar: Sidebar,
+    /// Base of nickname colors, in place of `action`
+    pub nickname: Option<Color>,
 }
 
 impl Colors {
@@ -53,6 +56,20 @@ impl Colors {
             error: Subpalette::from_color(palette.error, palette),
             info: Subpalette::from_color(palette.info, palette),
             success: Subpalette::from_color(palette.success, palette),
+            sidebar: Sidebar::new(palette),
+            nickname: None,
+        }
+    }
+
+    pub fn with_sidebar(self, sidebar: &SidebarPalette) -> Self {
+        Colors {
+            sidebar: Sidebar {
+                unread: sidebar.unread.unwrap_or(self.sidebar.unread),
+                highlight: sidebar.highlight.unwrap_or(self.sidebar.highlight),
+                error: sidebar.error.unwrap_or(self.sidebar.error),
+                connecting: sidebar.connecting.unwrap_or(self.sidebar.connecting),
+            },
+            ..self
         }
     }
 
@@ -61,6 +78,26 @@ impl Colors {
     }
 }
 
+/// Colors of the activity and connection states shown in the sidebar
+#[derive(Debug, Clone, Copy)]
+pub struct Sidebar {
+    pub unread: Color,
+    pub highlight: Color,
+    pub error: Color,
+    pub connecting: Color,
+}
+
+impl Sidebar {
+    fn new(palette: &Palette) -> Self {
+        Sidebar {
+            unread: palette.info,
+            highlight: palette.alert,
+            error: palette.error,
+            connecting: Subpalette::from_color(palette.text, palette).low_alpha,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Subpalette {
     pub base: Color,
@@ -77,7 +114,12 @@ pub struct Subpalette {
 
 impl Subpalette {
     pub fn from_color(color: Color, palette: &Palette) -> Subpalette {
-        let is_dark = is_dark(palette.background);
+        Self::on_background(color, palette.background)
+    }
+
+    /// Shades of `color`, with the transparency suited to `background`
+    pub fn on_background(color: Color, background: Color) -> Subpalette {
+        let is_dark = is_dark(background);
 
         Subpalette {
             base: color,
@@ -137,6 +179,15 @@ impl Default for Palette {
     }
 }
 
+/// Sidebar state colors set by a theme, falling back to its palette when unset
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SidebarPalette {
+    pub unread: Option<Color>,
+    pub highlight: Option<Color>,
+    pub error: Option<Color>,
+    pub connecting: Option<Color>,
+}
+
 pub fn hex_to_color(hex: &str) -> Option<Color> {
     if hex.len() == 7 {
         let hash = &hex[0..1];
@@ -230,7 +281,7 @@ pub mod palette_serde {
     use iced_core::Color;
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-    use super::{hex_to_color, Palette};
+    use super::{hex_to_color, Palette, SidebarPalette};
 
     #[derive(Debug, Clone, Default, Deserialize, Serialize)]
     struct HexPalette {
@@ -300,4 +351,36 @@ pub mod palette_serde {
             })
         }
     }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct HexSidebarPalette {
+        unread: Option<String>,
+        highlight: Option<String>,
+        error: Option<String>,
+        connecting: Option<String>,
+    }
+
+    impl<'de> Deserialize<'de> for SidebarPalette {
+        fn deserialize<D>(deserializer: D) -> Result<SidebarPalette, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let hex_palette: HexSidebarPalette = serde::Deserialize::deserialize(deserializer)?;
+
+            let color = |hex: Option<String>| {
+                hex.map(|hex| {
+                    hex_to_color(hex.as_str())
+                        .ok_or_else(|| serde::de::Error::custom("not a valid hex"))
+                })
+                .transpose()
+            };
+
+            Ok(SidebarPalette {
+                unread: color(hex_palette.unread)?,
+                highlight: color(hex_palette.highlight)?,
+                error: color(hex_palette.error)?,
+                connecting: color(hex_palette.connecting)?,
+            })
+        }
+    }
 }
@@ -21,6 +21,8 @@ pub struct User {
     hostname: Option<String>,
     access_levels: HashSet<AccessLevel>,
     away: bool,
+    /// Not known until a WHOIS, WHOX or `account` tag tells
+    account: Option<Account>,
 }
 
 impl PartialEq for User {
@@ -95,6 +97,7 @@ impl<'a> TryFrom<&'a str> for User {
             hostname,
             access_levels,
             away: false,
+            account: None,
         })
     }
 }
@@ -120,6 +123,7 @@ impl From<Nick> for User {
             hostname: None,
             access_levels: HashSet::default(),
             away: false,
+            account: None,
         }
     }
 }
@@ -127,9 +131,10 @@ impl From<Nick> for User {
 impl User {
     pub fn nick_color(&self, colors: &Colors, color: &buffer::Color) -> NickColor {
         let buffer::Color { kind, hex } = color;
-        let color = hex
-            .as_deref()
-            .and_then(hex_to_color)
+        // A buffer's own nickname color wins over the configured one
+        let color = colors
+            .nickname
+            .or_else(|| hex.as_deref().and_then(hex_to_color))
             .unwrap_or(colors.action.base);
 
         match kind {
@@ -164,6 +169,14 @@ impl User {
         Self { nickname, ..self }
     }
 
+    pub fn with_username_and_hostname(self, username: String, hostname: String) -> Self {
+        Self {
+            username: Some(username),
+            hostname: Some(hostname),
+            ..self
+        }
+    }
+
     pub fn highest_access_level(&self) -> AccessLevel {
         self.access_levels
             .iter()
@@ -193,6 +206,14 @@ impl User {
         self.away = away;
     }
 
+    pub fn account(&self) -> Option<&Account> {
+        self.account.as_ref()
+    }
+
+    pub fn update_account(&mut self, account: Account) {
+        self.account = Some(account);
+    }
+
     pub fn formatted(&self, user_format: UsernameFormat) -> String {
         let user = self.username();
         let host = self.hostname();
@@ -210,6 +231,23 @@ impl User {
     }
 }
 
+/// The services account a user is logged into
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Account {
+    LoggedIn(String),
+    LoggedOut,
+}
+
+impl Account {
+    /// From the account of a WHOX reply or `account` tag, where `0` and `*` stand for none
+    pub fn parse(account: &str) -> Self {
+        match account {
+            "" | "0" | "*" => Account::LoggedOut,
+            account => Account::LoggedIn(account.to_string()),
+        }
+    }
+}
+
 impl From<proto::User> for User {
     fn from(user: proto::User) -> Self {
         User {
@@ -218,6 +256,7 @@ impl From<proto::User> for User {
             hostname: user.hostname,
             access_levels: HashSet::default(),
             away: false,
+            account: None,
         }
     }
 }
@@ -96,6 +96,8 @@ pub enum Command {
 
     /* IRC extensions */
     BATCH(String, Vec<String>),
+    /// <new_username> <new_hostname>
+    CHGHOST(String, String),
     /// <nickname> <channel> :<message>
     CNOTICE(String, String, String),
     /// <nickname> <channel> :<message>
@@ -192,6 +194,7 @@ impl Command {
             "USERHOST" => USERHOST(params.collect()),
             "WALLOPS" if len > 0 => WALLOPS(req!()),
             "BATCH" if len > 0 => BATCH(req!(), params.collect()),
+            "CHGHOST" if len > 1 => CHGHOST(req!(), req!()),
             "CNOTICE" if len > 2 => CNOTICE(req!(), req!(), req!()),
             "CPRIVMSG" if len > 2 => CPRIVMSG(req!(), req!(), req!()),
             "KNOCK" if len > 0 => KNOCK(req!(), opt!()),
@@ -246,6 +249,7 @@ impl Command {
             Command::USERHOST(params) => params,
             Command::WALLOPS(a) => vec![a],
             Command::BATCH(a, rest) => std::iter::once(a).chain(rest).collect(),
+            Command::CHGHOST(a, b) => vec![a, b],
             Command::CNOTICE(a, b, c) => vec![a, b, c],
             Command::CPRIVMSG(a, b, c) => vec![a, b, c],
             Command::KNOCK(a, b) => std::iter::once(a).chain(b).collect(),
@@ -301,6 +305,7 @@ impl Command {
             USERHOST(_) => "USERHOST".to_string(),
             WALLOPS(_) => "WALLOPS".to_string(),
             BATCH(_, _) => "BATCH".to_string(),
+            CHGHOST(_, _) => "CHGHOST".to_string(),
             CNOTICE(_, _, _) => "CNOTICE".to_string(),
             CPRIVMSG(_, _, _) => "CPRIVMSG".to_string(),
             KNOCK(_, _) => "KNOCK".to_string(),
@@ -431,6 +436,10 @@ pub enum Numeric {
     ERR_USERSDONTMATCH = 502,
     ERR_HELPNOTFOUND = 524,
     ERR_INVALIDKEY = 525,
+    RPL_LOGON = 600,
+    RPL_LOGOFF = 601,
+    RPL_NOWON = 604,
+    RPL_NOWOFF = 605,
     RPL_STARTTLS = 670,
     RPL_WHOISSECURE = 671,
     ERR_STARTTLS = 691,
@@ -439,6 +448,8 @@ pub enum Numeric {
     RPL_HELPTXT = 705,
     RPL_ENDOFHELP = 706,
     ERR_NOPRIVS = 723,
+    RPL_MONONLINE = 730,
+    RPL_MONOFFLINE = 731,
     RPL_LOGGEDIN = 900,
     RPL_LOGGEDOUT = 901,
     ERR_NICKLOCKED = 902,
@@ -572,6 +583,10 @@ impl TryFrom<u16> for Numeric {
             502 => ERR_USERSDONTMATCH,
             524 => ERR_HELPNOTFOUND,
             525 => ERR_INVALIDKEY,
+            600 => RPL_LOGON,
+            601 => RPL_LOGOFF,
+            604 => RPL_NOWON,
+            605 => RPL_NOWOFF,
             670 => RPL_STARTTLS,
             671 => RPL_WHOISSECURE,
             691 => ERR_STARTTLS,
@@ -580,6 +595,8 @@ impl TryFrom<u16> for Numeric {
             705 => RPL_HELPTXT,
             706 => RPL_ENDOFHELP,
             723 => ERR_NOPRIVS,
+            730 => RPL_MONONLINE,
+            731 => RPL_MONOFFLINE,
             900 => RPL_LOGGEDIN,
             901 => RPL_LOGGEDOUT,
             902 => ERR_NICKLOCKED,
@@ -19,7 +19,7 @@ pub fn message(message: Message) -> String {
     }
 }
 
-fn tags(tags: Vec<Tag>) -> String {
+pub fn tags(tags: Vec<Tag>) -> String {
     tags.into_iter().map(tag).join(";")
 }
 
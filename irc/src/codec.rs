@@ -1,26 +1,89 @@
 use std::io;
 
-use bytes::BytesMut;
+use bytes::{Buf, BytesMut};
 use proto::{format, parse, Message};
 use tokio_util::codec::{Decoder, Encoder};
 
 pub type ParseResult<T = Message, E = parse::Error> = std::result::Result<T, E>;
 
-pub struct Codec;
+/// Longest line accepted, 8191 bytes of tags followed by a 512 byte message
+const MAX_LINE_BYTES: usize = 8191 + 512;
+
+#[derive(Debug, Default)]
+pub struct Codec {
+    /// Skipping the rest of an over-long line, which was truncated
+    discarding: bool,
+}
 
 impl Decoder for Codec {
     type Item = ParseResult;
     type Error = Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        let Some(pos) = src.windows(2).position(|b| b == [b'\r', b'\n']) else {
-            return Ok(None);
-        };
+        loop {
+            let Some(pos) = src.windows(2).position(|b| b == [b'\r', b'\n']) else {
+                if self.discarding {
+                    // Keep a trailing '\r', its '\n' may be yet to arrive
+                    let keep = usize::from(src.last() == Some(&b'\r'));
+                    src.advance(src.len() - keep);
+
+                    return Ok(None);
+                }
+
+                if src.len() > MAX_LINE_BYTES {
+                    log::warn!("line exceeds {MAX_LINE_BYTES} bytes, truncating it");
 
-        let bytes = Vec::from(src.split_to(pos + 2));
+                    self.discarding = true;
 
-        Ok(Some(parse::message_bytes(bytes)))
+                    return Ok(Some(parse::message_bytes(truncated(
+                        src.split_to(MAX_LINE_BYTES),
+                    ))));
+                }
+
+                return Ok(None);
+            };
+
+            let bytes = Vec::from(src.split_to(pos + 2));
+
+            // Remainder of a line already truncated
+            if self.discarding {
+                self.discarding = false;
+                continue;
+            }
+
+            return Ok(Some(parse::message_bytes(bytes)));
+        }
     }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if let Some(item) = self.decode(src)? {
+            return Ok(Some(item));
+        }
+
+        // Partial line left by a dropped connection
+        if !src.is_empty() {
+            log::warn!("dropping {} bytes of an incomplete line", src.len());
+            src.clear();
+        }
+
+        self.discarding = false;
+
+        Ok(None)
+    }
+}
+
+/// Terminates the start of an over-long line, without splitting a character
+fn truncated(bytes: BytesMut) -> Vec<u8> {
+    let mut bytes = Vec::from(bytes);
+
+    if let Err(error) = std::str::from_utf8(&bytes) {
+        if error.error_len().is_none() {
+            bytes.truncate(error.valid_up_to());
+        }
+    }
+
+    bytes.extend(b"\r\n");
+    bytes
 }
 
 impl Encoder<Message> for Codec {
@@ -40,3 +103,71 @@ pub enum Error {
     #[error(transparent)]
     Io(#[from] io::Error),
 }
+
+#[cfg(test)]
+mod test {
+    use proto::Command;
+
+    use super::*;
+
+    fn decode(codec: &mut Codec, src: &mut BytesMut) -> Option<Command> {
+        codec
+            .decode(src)
+            .expect("decode")
+            .map(|result| result.expect("parse").command)
+    }
+
+    #[test]
+    fn oversized_line() {
+        let mut codec = Codec::default();
+        let mut src = BytesMut::new();
+
+        src.extend(b"PRIVMSG #halloy :");
+        src.extend("ä".repeat(MAX_LINE_BYTES).as_bytes());
+
+        let Some(Command::PRIVMSG(target, text)) = decode(&mut codec, &mut src) else {
+            panic!("expected truncated PRIVMSG");
+        };
+        assert_eq!(target, "#halloy");
+        assert!(text.len() < MAX_LINE_BYTES && text.chars().all(|c| c == 'ä'));
+
+        // The rest of the line is skipped, even across reads
+        assert_eq!(decode(&mut codec, &mut src), None);
+        src.extend("ä".repeat(100).as_bytes());
+        src.extend(b"\r\nPING :halloy\r\n");
+
+        assert_eq!(
+            decode(&mut codec, &mut src),
+            Some(Command::PING("halloy".into()))
+        );
+        assert_eq!(decode(&mut codec, &mut src), None);
+    }
+
+    #[test]
+    fn malformed_line() {
+        let mut codec = Codec::default();
+        let mut src = BytesMut::from(&b"\xff\xfe\r\nPING :halloy\r\n"[..]);
+
+        assert!(matches!(codec.decode(&mut src), Ok(Some(Err(_)))));
+        assert_eq!(
+            decode(&mut codec, &mut src),
+            Some(Command::PING("halloy".into()))
+        );
+    }
+
+    #[test]
+    fn partial_line_on_eof() {
+        let mut codec = Codec::default();
+        let mut src = BytesMut::from(&b"PING :halloy\r\nPRIVMSG #hal"[..]);
+
+        assert!(matches!(
+            codec.decode_eof(&mut src),
+            Ok(Some(Ok(Message {
+                command: Command::PING(_),
+                ..
+            })))
+        ));
+        assert!(matches!(codec.decode_eof(&mut src), Ok(None)));
+        assert!(src.is_empty());
+    }
+}
@@ -1,10 +1,12 @@
+use chrono::{DateTime, Utc};
 pub use data::buffer::Settings;
 use data::user::Nick;
-use data::{buffer, file_transfer, history, Config};
+use data::{buffer, file_transfer, history, time, Config};
 use iced::Task;
 
 use self::channel::Channel;
 use self::file_transfers::FileTransfers;
+pub use self::input_view::record_input;
 use self::query::Query;
 use self::server::Server;
 use crate::widget::Element;
@@ -38,6 +40,10 @@ pub enum Message {
 #[derive(Debug, Clone)]
 pub enum Event {
     UserContext(user_context::Event),
+    JoinChannels(data::Server, Vec<String>),
+    Appearance(buffer::AppearancePart, Option<String>),
+    NoteChanged,
+    JumpTo(data::Buffer, DateTime<Utc>),
 }
 
 impl Buffer {
@@ -65,24 +71,49 @@ impl Buffer {
     ) -> (Task<Message>, Option<Event>) {
         match (self, message) {
             (Buffer::Channel(state), Message::Channel(message)) => {
-                let (command, event) = state.update(message, clients, history);
+                let (command, event) = state.update(message, clients, history, config);
 
                 let event = event.map(|event| match event {
                     channel::Event::UserContext(event) => Event::UserContext(event),
+                    channel::Event::JoinChannels(server, channels) => {
+                        Event::JoinChannels(server, channels)
+                    }
+                    channel::Event::Appearance(part, hex) => Event::Appearance(part, hex),
+                    channel::Event::NoteChanged => Event::NoteChanged,
+                    channel::Event::JumpTo(buffer, server_time) => {
+                        Event::JumpTo(buffer, server_time)
+                    }
                 });
 
                 (command.map(Message::Channel), event)
             }
             (Buffer::Server(state), Message::Server(message)) => {
-                let command = state.update(message, clients, history);
+                let (command, event) = state.update(message, clients, history, config);
 
-                (command.map(Message::Server), None)
+                let event = event.map(|event| match event {
+                    server::Event::JoinChannels(server, channels) => {
+                        Event::JoinChannels(server, channels)
+                    }
+                    server::Event::Appearance(part, hex) => Event::Appearance(part, hex),
+                    server::Event::NoteChanged => Event::NoteChanged,
+                    server::Event::JumpTo(buffer, server_time) => {
+                        Event::JumpTo(buffer, server_time)
+                    }
+                });
+
+                (command.map(Message::Server), event)
             }
             (Buffer::Query(state), Message::Query(message)) => {
-                let (command, event) = state.update(message, clients, history);
+                let (command, event) = state.update(message, clients, history, config);
 
                 let event = event.map(|event| match event {
                     query::Event::UserContext(event) => Event::UserContext(event),
+                    query::Event::JoinChannels(server, channels) => {
+                        Event::JoinChannels(server, channels)
+                    }
+                    query::Event::Appearance(part, hex) => Event::Appearance(part, hex),
+                    query::Event::NoteChanged => Event::NoteChanged,
+                    query::Event::JumpTo(buffer, server_time) => Event::JumpTo(buffer, server_time),
                 });
 
                 (command.map(Message::Query), event)
@@ -114,14 +145,27 @@ impl Buffer {
                 &settings.channel,
                 config,
                 is_focused,
+                settings.read_only,
             )
             .map(Message::Channel),
-            Buffer::Server(state) => {
-                server::view(state, clients, history, config, is_focused).map(Message::Server)
-            }
-            Buffer::Query(state) => {
-                query::view(state, clients, history, config, is_focused).map(Message::Query)
-            }
+            Buffer::Server(state) => server::view(
+                state,
+                clients,
+                history,
+                config,
+                is_focused,
+                settings.compact,
+            )
+            .map(Message::Server),
+            Buffer::Query(state) => query::view(
+                state,
+                clients,
+                history,
+                config,
+                is_focused,
+                settings.read_only,
+            )
+            .map(Message::Query),
             Buffer::FileTransfers(state) => {
                 file_transfers::view(state, file_transfers).map(Message::FileTransfers)
             }
@@ -188,6 +232,28 @@ impl Buffer {
         }
     }
 
+    pub fn edit_note_in_input(
+        &mut self,
+        nick: Nick,
+        history: &mut history::Manager,
+    ) -> Task<Message> {
+        if let Some(buffer) = self.data() {
+            match self {
+                Buffer::Empty | Buffer::Server(_) | Buffer::FileTransfers(_) => Task::none(),
+                Buffer::Channel(channel) => channel
+                    .input_view
+                    .edit_note(nick, buffer, history)
+                    .map(|message| Message::Channel(channel::Message::InputView(message))),
+                Buffer::Query(query) => query
+                    .input_view
+                    .edit_note(nick, buffer, history)
+                    .map(|message| Message::Query(query::Message::InputView(message))),
+            }
+        } else {
+            Task::none()
+        }
+    }
+
     pub fn scroll_to_start(&mut self) -> Task<Message> {
         match self {
             Buffer::Empty | Buffer::FileTransfers(_) => Task::none(),
@@ -206,6 +272,34 @@ impl Buffer {
         }
     }
 
+    pub fn scroll_to_message(&mut self, received_at: time::Posix) -> Task<Message> {
+        match self {
+            Buffer::Empty | Buffer::FileTransfers(_) => Task::none(),
+            Buffer::Channel(channel) => channel
+                .scroll_view
+                .scroll_to_message(received_at)
+                .map(|message| Message::Channel(channel::Message::ScrollView(message))),
+            Buffer::Server(server) => server
+                .scroll_view
+                .scroll_to_message(received_at)
+                .map(|message| Message::Server(server::Message::ScrollView(message))),
+            Buffer::Query(query) => query
+                .scroll_view
+                .scroll_to_message(received_at)
+                .map(|message| Message::Query(query::Message::ScrollView(message))),
+        }
+    }
+
+    /// Shows search results below the messages, without adding them to its history
+    pub fn show_search_results(&mut self, results: Vec<data::Message>) {
+        match self {
+            Buffer::Empty | Buffer::FileTransfers(_) => {}
+            Buffer::Channel(channel) => channel.scroll_view.show_search_results(results),
+            Buffer::Server(server) => server.scroll_view.show_search_results(results),
+            Buffer::Query(query) => query.scroll_view.show_search_results(results),
+        }
+    }
+
     pub fn scroll_to_end(&mut self) -> Task<Message> {
         match self {
             Buffer::Empty | Buffer::FileTransfers(_) => Task::none(),
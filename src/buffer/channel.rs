@@ -1,3 +1,9 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, Utc};
+use data::buffer::AppearancePart;
+use data::config::buffer::OwnMessageStyle;
+use data::config::channel::AccountMarker;
 use data::server::Server;
 use data::user::Nick;
 use data::User;
@@ -16,11 +22,17 @@ pub enum Message {
     ScrollView(scroll_view::Message),
     InputView(input_view::Message),
     UserContext(user_context::Message),
+    ToggleTopic,
+    SelectThread(Option<String>),
 }
 
 #[derive(Debug, Clone)]
 pub enum Event {
     UserContext(user_context::Event),
+    JoinChannels(Server, Vec<String>),
+    Appearance(AppearancePart, Option<String>),
+    NoteChanged,
+    JumpTo(data::Buffer, DateTime<Utc>),
 }
 
 pub fn view<'a>(
@@ -30,6 +42,7 @@ pub fn view<'a>(
     settings: &'a channel::Settings,
     config: &'a Config,
     is_focused: bool,
+    read_only: bool,
 ) -> Element<'a, Message> {
     let buffer = state.buffer();
     let input = history.input(&buffer);
@@ -41,53 +54,109 @@ pub fn view<'a>(
 
     let users = clients.get_channel_users(&state.server, &state.channel);
 
+    let kind = match state.thread.as_deref() {
+        Some(thread) if settings.threads.enabled => {
+            scroll_view::Kind::Thread(&state.server, &state.channel, thread)
+        }
+        _ => scroll_view::Kind::Channel(&state.server, &state.channel),
+    };
+
     let messages = container(
         scroll_view::view(
             &state.scroll_view,
-            scroll_view::Kind::Channel(&state.server, &state.channel),
+            kind,
             history,
             config,
-            move |message| {
+            move |message, repeats| {
+                let bot_commands = &config.buffer.channel.bot_commands;
+                let is_collapsed = settings.bot_commands.collapsed && bot_commands.matches(message);
+
+                if is_collapsed && bot_commands.hide {
+                    return None;
+                }
+
                 let timestamp =
                     config
                         .buffer
                         .format_timestamp(&message.server_time)
                         .map(|timestamp| {
-                            selectable_text(timestamp).style(theme::selectable_text::transparent)
+                            scroll_view::with_tags(
+                                selectable_text(timestamp)
+                                    .part(selectable_text::Part::Timestamp)
+                                    .style(theme::selectable_text::transparent),
+                                message,
+                            )
                         });
 
                 match message.target.source() {
                     message::Source::User(user) => {
+                        let accounts = &config.buffer.channel.accounts;
+                        let marker = accounts
+                            .marker(user.nickname(), message.account.as_ref(), &state.channel)
+                            .map(|marker| {
+                                let status = match marker {
+                                    AccountMarker::Trusted => message::source::Status::Success,
+                                    AccountMarker::Warning => message::source::Status::Error,
+                                };
+
+                                selectable_text(accounts.marker_text(marker)).style(move |theme| {
+                                    theme::selectable_text::status(
+                                        theme,
+                                        status,
+                                        &config.buffer.internal_messages,
+                                    )
+                                })
+                            });
+
                         let nick = user_context::view(
-                            selectable_text(config.buffer.nickname.brackets.format(user)).style(
-                                |theme| {
+                            selectable_text(config.buffer.nickname.brackets.format(user))
+                                .part(selectable_text::Part::Nickname)
+                                .style(|theme| {
                                     theme::selectable_text::nickname(
                                         theme,
                                         user.nick_color(
                                             theme.colors(),
                                             &config.buffer.nickname.color,
                                         ),
-                                        user.is_away(),
+                                        user.is_away() || is_collapsed,
                                     )
-                                },
-                            ),
+                                }),
                             user,
                             users.iter().find(|current_user| *current_user == user),
                             state.buffer(),
                             our_user,
+                            clients.get_ignored(&state.server),
+                            history.notes(),
                         )
                         .map(scroll_view::Message::UserContext);
 
+                        let is_own = message.is_own(our_nick);
+                        let own_style = config.buffer.own_messages.style;
+
                         let space = selectable_text(" ");
-                        let text = selectable_text(&message.text);
+                        let text = message_text(&message.text, &state.channel, config);
+                        let text = selectable_text(text);
+                        let text = if is_collapsed {
+                            text.style(theme::selectable_text::transparent)
+                        } else if is_own && own_style == OwnMessageStyle::Accent {
+                            text.style(theme::selectable_text::accent)
+                        } else {
+                            text
+                        };
+                        let repeats = (repeats > 1).then(|| {
+                            selectable_text(format!(" (x{repeats})"))
+                                .style(theme::selectable_text::transparent)
+                        });
 
                         Some(
                             container(
                                 row![]
                                     .push_maybe(timestamp)
+                                    .push_maybe(marker)
                                     .push(nick)
                                     .push(space)
-                                    .push(text),
+                                    .push(text)
+                                    .push_maybe(repeats),
                             )
                             .style(move |theme| match our_nick {
                                 Some(nick)
@@ -99,6 +168,9 @@ pub fn view<'a>(
                                 {
                                     theme::container::highlight(theme)
                                 }
+                                _ if is_own && own_style == OwnMessageStyle::Background => {
+                                    theme::container::own_message(theme)
+                                }
                                 _ => Default::default(),
                             })
                             .into(),
@@ -116,11 +188,14 @@ pub fn view<'a>(
                         Some(container(row![].push_maybe(timestamp).push(message)).into())
                     }
                     message::Source::Action => {
-                        let message =
-                            selectable_text(&message.text).style(theme::selectable_text::accent);
+                        let text = message_text(&message.text, &state.channel, config);
+                        let message = selectable_text(text).style(theme::selectable_text::accent);
 
                         Some(container(row![].push_maybe(timestamp).push(message)).into())
                     }
+                    message::Source::Internal(message::source::Internal::SearchResult(
+                        found_in,
+                    )) => Some(scroll_view::search_result(timestamp, message, found_in)),
                     message::Source::Internal(message::source::Internal::Status(status)) => {
                         let message = selectable_text(&message.text).style(move |theme| {
                             theme::selectable_text::status(
@@ -140,11 +215,19 @@ pub fn view<'a>(
     .width(Length::FillPortion(2))
     .height(Length::Fill);
 
-    let nick_list = nick_list::view(users, &buffer, our_user, config).map(Message::UserContext);
+    let nick_list = nick_list::view(
+        users,
+        &buffer,
+        our_user,
+        clients.get_ignored(&state.server),
+        history.notes(),
+        config,
+    )
+    .map(Message::UserContext);
 
     // If topic toggles from None to Some then it messes with messages' scroll state,
     // so produce a zero-height placeholder when topic is None.
-    let topic = topic(state, clients, users, our_user, settings, config)
+    let topic = topic(state, clients, history, users, our_user, settings, config)
         .unwrap_or_else(|| column![].into());
 
     let show_text_input = match config.buffer.text_input.visibility {
@@ -156,6 +239,10 @@ pub fn view<'a>(
     let is_connected_to_channel = channels.iter().any(|c| c == &state.channel);
 
     let text_input = show_text_input.then(move || {
+        if read_only {
+            return input_view::read_only();
+        }
+
         input_view::view(
             &state.input_view,
             buffer,
@@ -165,12 +252,25 @@ pub fn view<'a>(
             clients.get_isupport(&state.server),
             is_focused,
             !is_connected_to_channel,
+            config,
         )
         .map(Message::InputView)
     });
 
     let content = column![topic, messages].spacing(4);
 
+    let content = if settings.threads.enabled {
+        let threads = threads::view(
+            history.get_channel_threads(&state.server, &state.channel),
+            state.thread.as_deref(),
+            config,
+        );
+
+        row![threads, content].spacing(4)
+    } else {
+        row![content]
+    };
+
     let content = match (
         settings.nicklist.enabled,
         config.buffer.channel.nicklist.position,
@@ -204,6 +304,8 @@ pub struct Channel {
 
     pub scroll_view: scroll_view::State,
     pub input_view: input_view::State,
+    topic_expanded: bool,
+    thread: Option<String>,
 }
 
 impl Channel {
@@ -213,6 +315,8 @@ impl Channel {
             channel,
             scroll_view: scroll_view::State::new(),
             input_view: input_view::State::new(),
+            topic_expanded: false,
+            thread: None,
         }
     }
 
@@ -225,6 +329,7 @@ impl Channel {
         message: Message,
         clients: &mut data::client::Map,
         history: &mut history::Manager,
+        config: &Config,
     ) -> (Task<Message>, Option<Event>) {
         match message {
             Message::ScrollView(message) => {
@@ -232,12 +337,23 @@ impl Channel {
 
                 let event = event.map(|event| match event {
                     scroll_view::Event::UserContext(event) => Event::UserContext(event),
+                    scroll_view::Event::JumpTo(buffer, server_time) => {
+                        Event::JumpTo(buffer, server_time)
+                    }
                 });
 
                 (command.map(Message::ScrollView), event)
             }
             Message::InputView(message) => {
-                let (command, event) = self.input_view.update(message, clients, history);
+                // Messages sent while a thread is open are replies to it
+                let message = match (message, &self.thread) {
+                    (input_view::Message::Send(input), Some(thread)) => {
+                        input_view::Message::Send(input.in_reply_to(thread.clone()))
+                    }
+                    (message, _) => message,
+                };
+
+                let (command, event) = self.input_view.update(message, clients, history, config);
                 let command = command.map(Message::InputView);
 
                 match event {
@@ -249,6 +365,23 @@ impl Channel {
 
                         (command, None)
                     }
+                    Some(input_view::Event::SearchResults(results)) => {
+                        self.scroll_view.show_search_results(results);
+
+                        let command = Task::batch(vec![
+                            command,
+                            self.scroll_view.scroll_to_end().map(Message::ScrollView),
+                        ]);
+
+                        (command, None)
+                    }
+                    Some(input_view::Event::JoinChannels(server, channels)) => {
+                        (command, Some(Event::JoinChannels(server, channels)))
+                    }
+                    Some(input_view::Event::Appearance(part, hex)) => {
+                        (command, Some(Event::Appearance(part, hex)))
+                    }
+                    Some(input_view::Event::NoteChanged) => (command, Some(Event::NoteChanged)),
                     None => (command, None),
                 }
             }
@@ -256,9 +389,26 @@ impl Channel {
                 Task::none(),
                 Some(Event::UserContext(user_context::update(message))),
             ),
+            Message::ToggleTopic => {
+                self.topic_expanded = !self.topic_expanded;
+
+                (Task::none(), None)
+            }
+            Message::SelectThread(thread) => {
+                self.thread = thread;
+
+                (
+                    self.scroll_view.scroll_to_end().map(Message::ScrollView),
+                    None,
+                )
+            }
         }
     }
 
+    pub fn close_thread(&mut self) {
+        self.thread = None;
+    }
+
     pub fn focus(&self) -> Task<Message> {
         self.input_view.focus().map(Message::InputView)
     }
@@ -268,9 +418,19 @@ impl Channel {
     }
 }
 
+/// Text of a message from a user, with emojis and math shown as configured
+fn message_text<'a>(text: &'a str, channel: &str, config: &Config) -> Cow<'a, str> {
+    config
+        .buffer
+        .channel
+        .math
+        .display(channel, config.buffer.emojis.display(text))
+}
+
 fn topic<'a>(
     state: &'a Channel,
     clients: &'a data::client::Map,
+    history: &'a history::Manager,
     users: &'a [User],
     our_user: Option<&'a User>,
     settings: &'a channel::Settings,
@@ -282,22 +442,25 @@ fn topic<'a>(
 
     let topic = clients.get_channel_topic(&state.server, &state.channel)?;
 
-    Some(
-        topic::view(
-            topic.text.as_deref()?,
-            topic.who.as_deref(),
-            topic.time.as_ref(),
-            config.buffer.channel.topic.max_lines,
-            users,
-            &state.buffer(),
-            our_user,
-            config,
-        )
-        .map(Message::UserContext),
-    )
+    Some(topic::view(
+        topic.text.as_deref()?,
+        topic.who.as_deref(),
+        topic.time.as_ref(),
+        config.buffer.channel.topic.max_lines,
+        config.buffer.channel.topic.expandable,
+        state.topic_expanded,
+        users,
+        &state.buffer(),
+        our_user,
+        clients.get_ignored(&state.server),
+        history.notes(),
+        config,
+    ))
 }
 
 mod nick_list {
+    use data::notes::Notes;
+    use data::user::Nick;
     use data::{Buffer, Config, User};
     use iced::widget::{column, container, scrollable, text, Scrollable};
     use iced::Length;
@@ -311,6 +474,8 @@ mod nick_list {
         users: &'a [User],
         buffer: &Buffer,
         our_user: Option<&'a User>,
+        ignored: &'a [Nick],
+        notes: &'a Notes,
         config: &'a Config,
     ) -> Element<'a, Message> {
         let column = column(users.iter().map(|user| {
@@ -322,7 +487,15 @@ mod nick_list {
                 )
             });
 
-            user_context::view(content, user, Some(user), buffer.clone(), our_user)
+            user_context::view(
+                content,
+                user,
+                Some(user),
+                buffer.clone(),
+                our_user,
+                ignored,
+                notes,
+            )
         }))
         .padding(4)
         .spacing(1);
@@ -342,3 +515,94 @@ mod nick_list {
         .into()
     }
 }
+
+mod threads {
+    use data::history::Thread;
+    use data::{message, Config};
+    use iced::widget::{button, column, container, scrollable, text, Scrollable};
+    use iced::Length;
+
+    use super::Message;
+    use crate::theme;
+    use crate::widget::Element;
+
+    /// Characters of the starting message shown for each thread
+    const PREVIEW_LENGTH: usize = 24;
+
+    pub fn view<'a>(
+        threads: Vec<Thread<'a>>,
+        selected: Option<&str>,
+        config: &'a Config,
+    ) -> Element<'a, Message> {
+        let all = button(text("All messages"))
+            .padding([2, 4])
+            .width(Length::Fill)
+            .on_press(Message::SelectThread(None))
+            .style(move |theme, status| theme::button::tertiary(theme, status, selected.is_none()));
+
+        let threads = threads.into_iter().map(|thread| {
+            let is_selected = selected == Some(thread.id);
+
+            thread_button(thread, is_selected, config)
+        });
+
+        let column = column(std::iter::once(all.into()).chain(threads))
+            .padding(4)
+            .spacing(1);
+
+        container(
+            Scrollable::with_direction(
+                column,
+                scrollable::Direction::Vertical(
+                    scrollable::Properties::new().width(1).scroller_width(1),
+                ),
+            )
+            .style(theme::scrollable::hidden),
+        )
+        .width(160)
+        .height(Length::Fill)
+        .into()
+    }
+
+    fn thread_button<'a>(
+        thread: Thread<'a>,
+        is_selected: bool,
+        config: &'a Config,
+    ) -> Element<'a, Message> {
+        let nick = match thread.first.target.source() {
+            message::Source::User(user) => {
+                Some(text(user.nickname().to_string()).style(move |theme| {
+                    theme::text::nickname(
+                        theme,
+                        user.nick_color(theme.colors(), &config.buffer.channel.nicklist.color),
+                        false,
+                    )
+                }))
+            }
+            _ => None,
+        };
+
+        let mut preview = thread
+            .first
+            .text
+            .chars()
+            .take(PREVIEW_LENGTH)
+            .collect::<String>();
+        if thread.first.text.chars().count() > PREVIEW_LENGTH {
+            preview.push('…');
+        }
+
+        let replies = text(match thread.replies {
+            1 => "1 reply".to_string(),
+            n => format!("{n} replies"),
+        })
+        .style(theme::text::transparent);
+
+        button(column![].push_maybe(nick).push(text(preview)).push(replies))
+            .padding([2, 4])
+            .width(Length::Fill)
+            .on_press(Message::SelectThread(Some(thread.id.to_string())))
+            .style(move |theme, status| theme::button::tertiary(theme, status, is_selected))
+            .into()
+    }
+}
@@ -1,10 +1,11 @@
 use chrono::{DateTime, Utc};
+use data::notes::Notes;
 use data::user::Nick;
 use data::{Buffer, Config, User};
-use iced::widget::{column, container, horizontal_rule, row, scrollable, Scrollable};
+use iced::widget::{button, column, container, horizontal_rule, row, scrollable, text, Scrollable};
 use iced::Length;
 
-use super::user_context;
+use super::{user_context, Message};
 use crate::theme;
 use crate::widget::{double_pass, selectable_text, Element};
 
@@ -13,11 +14,15 @@ pub fn view<'a>(
     who: Option<&'a str>,
     time: Option<&'a DateTime<Utc>>,
     max_lines: u16,
+    expandable: bool,
+    expanded: bool,
     users: &'a [User],
     buffer: &Buffer,
     our_user: Option<&'a User>,
+    ignored: &'a [Nick],
+    notes: &'a Notes,
     config: &'a Config,
-) -> Element<'a, user_context::Message> {
+) -> Element<'a, Message> {
     let set_by = who.and_then(|who| {
         let nick = Nick::from(who.split('!').next()?);
 
@@ -34,7 +39,10 @@ pub fn view<'a>(
                 Some(user),
                 buffer.clone(),
                 our_user,
+                ignored,
+                notes,
             )
+            .map(Message::UserContext)
         } else {
             selectable_text(who)
                 .style(theme::selectable_text::info)
@@ -52,20 +60,45 @@ pub fn view<'a>(
     let content = column![selectable_text(text).style(theme::selectable_text::transparent)]
         .push_maybe(set_by);
 
-    let scrollable = Scrollable::with_direction(
-        container(content).width(Length::Fill).padding(padding()),
-        scrollable::Direction::Vertical(scrollable::Properties::new().width(1).scroller_width(1)),
-    )
-    .style(theme::scrollable::hidden);
+    let banner = if expandable && expanded {
+        container(content)
+            .width(Length::Fill)
+            .padding(padding())
+            .into()
+    } else {
+        // Collapsed expandable banners show a single line
+        let max_lines = if expandable { 1 } else { max_lines };
 
-    // Use double pass to limit layout to `max_lines` of text
-    column![
+        let scrollable = Scrollable::with_direction(
+            container(content).width(Length::Fill).padding(padding()),
+            scrollable::Direction::Vertical(
+                scrollable::Properties::new().width(1).scroller_width(1),
+            ),
+        )
+        .style(theme::scrollable::hidden);
+
+        // Use double pass to limit layout to `max_lines` of text
         double_pass(
             container(column((0..max_lines).map(|_| "".into())))
                 .width(Length::Fill)
                 .padding(padding()),
             column![container(scrollable)].width(Length::Fill),
-        ),
+        )
+    };
+
+    let toggle = expandable.then(|| {
+        button(
+            text(if expanded { "▴" } else { "▾" })
+                .style(theme::text::transparent)
+                .shaping(text::Shaping::Advanced),
+        )
+        .padding([0, 4])
+        .style(theme::button::bare)
+        .on_press(Message::ToggleTopic)
+    });
+
+    column![
+        row![banner].push_maybe(toggle),
         container(horizontal_rule(1))
             .width(Length::Fill)
             .padding([0, 11])
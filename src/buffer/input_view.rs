@@ -1,22 +1,37 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
+use data::buffer::AppearancePart;
+use data::config::buffer::Disconnected;
 use data::input::{Cache, Draft};
 use data::isupport;
 use data::user::{Nick, User};
-use data::{client, history, Buffer, Input};
-use iced::Task;
+use data::{client, history, pastebin, Buffer, Command, Config, Input, Server};
+use iced::widget::{container, text};
+use iced::{Length, Task};
 
+use crate::theme;
 use crate::widget::{input, Element};
 
+/// Delay between messages sent on our behalf, so servers don't flag them as flooding
+const SEND_PACING: Duration = Duration::from_millis(750);
+
 pub enum Event {
     InputSent,
+    JoinChannels(Server, Vec<String>),
+    Appearance(AppearancePart, Option<String>),
+    SearchResults(Vec<data::Message>),
+    NoteChanged,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Input(Draft),
     Send(Input),
+    SendPaced(Vec<Input>),
     Completion(Draft),
+    Paste(Draft),
+    Pasted(Draft, Result<String, String>),
 }
 
 pub fn view<'a>(
@@ -28,6 +43,7 @@ pub fn view<'a>(
     isupport: HashMap<isupport::Kind, isupport::Parameter>,
     buffer_focused: bool,
     disabled: bool,
+    config: &'a Config,
 ) -> Element<'a, Message> {
     input(
         state.input_id.clone(),
@@ -39,15 +55,30 @@ pub fn view<'a>(
         isupport,
         buffer_focused,
         disabled,
+        &config.buffer.pastebin,
+        state.paste_failed.as_deref() == Some(cache.draft),
         Message::Input,
         Message::Send,
         Message::Completion,
+        Message::Paste,
     )
 }
 
+/// Shown in place of the input of a read-only buffer
+pub fn read_only<'a, Message: 'a>() -> Element<'a, Message> {
+    container(
+        text("Read-only, messages can't be sent from this buffer").style(theme::text::transparent),
+    )
+    .width(Length::Fill)
+    .padding(8)
+    .into()
+}
+
 #[derive(Debug, Clone)]
 pub struct State {
     input_id: input::Id,
+    /// Text of a paste which failed to upload, sent as lines instead if sent again unchanged
+    paste_failed: Option<String>,
 }
 
 impl Default for State {
@@ -60,6 +91,7 @@ impl State {
     pub fn new() -> Self {
         Self {
             input_id: input::Id::unique(),
+            paste_failed: None,
         }
     }
 
@@ -68,6 +100,7 @@ impl State {
         message: Message,
         clients: &mut client::Map,
         history: &mut history::Manager,
+        config: &Config,
     ) -> (Task<Message>, Option<Event>) {
         match message {
             Message::Input(draft) => {
@@ -76,32 +109,186 @@ impl State {
                 (Task::none(), None)
             }
             Message::Send(input) => {
-                if let Some(encoded) = input.encoded() {
+                self.paste_failed = None;
+
+                let input = input.with_emojis(config.buffer.emojis.send);
+
+                // Only changes the buffer's settings, so it works while disconnected too
+                if let Some((part, hex)) = input.appearance() {
+                    let event = Event::Appearance(part, hex.map(String::from));
+
+                    history.clear_draft(input.buffer());
+                    record_input(input, clients, history);
+
+                    return (Task::none(), Some(event));
+                }
+
+                // Notes are kept locally, so this works while disconnected too
+                if let Some((nick, note)) = input.note() {
+                    history.record_note(input.server(), nick.into(), note.map(String::from));
+
+                    history.clear_draft(input.buffer());
+                    record_input(input, clients, history);
+
+                    return (Task::none(), Some(Event::NoteChanged));
+                }
+
+                if !clients.status(input.server()).connected() {
+                    if let Some(text) = stage(&input, clients, config.buffer.disconnected) {
+                        history.clear_draft(input.buffer());
+                        history.record_message(
+                            input.server(),
+                            data::Message::staged(input.buffer().clone(), text),
+                        );
+
+                        return (Task::none(), Some(Event::InputSent));
+                    }
+                }
+
+                // Joins of several channels are sent once confirmed
+                let join_channels = input.join_channels().filter(|channels| channels.len() > 1);
+                let mut search_results = None;
+
+                // Fall back to searching local history when the server can't
+                if let Some(channel) = input.resync() {
+                    clients.resync(input.server(), channel);
+                } else if let Some(terms) = input
+                    .search_terms()
+                    .filter(|_| !clients.supports_search(input.server()))
+                {
+                    let results = history.search(input.buffer(), terms);
+                    search_results = Some(data::Message::search_results(
+                        input.buffer().clone(),
+                        results,
+                    ));
+                } else if let Some(encoded) = input.encoded().filter(|_| join_channels.is_none()) {
+                    // The cached list is shown right away, and replaced once the server answers
+                    if let Some(cached) = clients
+                        .get_channel_list(input.server())
+                        .filter(|_| input.is_full_list())
+                    {
+                        for message in data::Message::channel_list(cached) {
+                            history.record_message(input.server(), message);
+                        }
+                    }
+
                     clients.send(input.buffer(), encoded);
                 }
 
-                if let Some(nick) = clients.nickname(input.server()) {
-                    let mut user = nick.to_owned().into();
+                let event = match (join_channels, search_results) {
+                    (Some(channels), _) => Event::JoinChannels(input.server().clone(), channels),
+                    (None, Some(results)) => Event::SearchResults(results),
+                    (None, None) => Event::InputSent,
+                };
+
+                // Messages of a query copied with `/tochannel`
+                let quoted = match (input.buffer(), input.to_channel()) {
+                    (Buffer::Query(server, nick), Some((channel, count))) => Some((
+                        Buffer::Channel(server.clone(), channel.to_string()),
+                        channel.to_string(),
+                        history.quote_query(server, nick, count),
+                    )),
+                    _ => None,
+                };
+
+                record_input(input, clients, history);
+
+                let task = match quoted {
+                    Some((buffer, channel, lines)) => {
+                        let inputs = lines
+                            .iter()
+                            .map(|line| {
+                                Input::command(
+                                    buffer.clone(),
+                                    Command::Msg(channel.clone(), line.clone()),
+                                )
+                            })
+                            .collect::<Vec<_>>();
+
+                        // Sent at once in multiline batches, when the server supports them
+                        if clients.supports_multiline(buffer.server()) {
+                            clients.send_multiline(&buffer, &channel, &lines);
+
+                            for input in inputs {
+                                record_input(input, clients, history);
+                            }
 
-                    // Resolve our attributes if sending this message in a channel
-                    if let Buffer::Channel(server, channel) = input.buffer() {
-                        if let Some(user_with_attributes) =
-                            clients.resolve_user_attributes(server, channel, &user)
-                        {
-                            user = user_with_attributes.clone();
+                            Task::none()
+                        } else {
+                            self.update(Message::SendPaced(inputs), clients, history, config)
+                                .0
                         }
                     }
+                    None => Task::none(),
+                };
 
-                    history.record_input(input, user);
+                (task, Some(event))
+            }
+            Message::SendPaced(mut inputs) => {
+                if inputs.is_empty() {
+                    return (Task::none(), None);
                 }
 
-                (Task::none(), Some(Event::InputSent))
+                let input = inputs.remove(0);
+
+                if let Some(encoded) = input.encoded() {
+                    clients.send(input.buffer(), encoded);
+                }
+
+                record_input(input, clients, history);
+
+                let task = if inputs.is_empty() {
+                    Task::none()
+                } else {
+                    Task::perform(tokio::time::sleep(SEND_PACING), move |_| {
+                        Message::SendPaced(inputs)
+                    })
+                };
+
+                (task, None)
             }
             Message::Completion(draft) => {
                 history.record_draft(draft);
 
                 (input::move_cursor_to_end(self.input_id.clone()), None)
             }
+            Message::Paste(draft) => {
+                let upload = pastebin::upload(config.buffer.pastebin.clone(), draft.text.clone());
+
+                (
+                    Task::perform(upload, move |result| {
+                        Message::Pasted(draft, result.map_err(|error| error.to_string()))
+                    }),
+                    None,
+                )
+            }
+            Message::Pasted(draft, Ok(link)) => {
+                self.paste_failed = None;
+
+                let isupport = clients.get_isupport(draft.buffer.server());
+
+                match data::input::parse(draft.buffer, &link, &isupport) {
+                    Ok(input) => self.update(Message::Send(input), clients, history, config),
+                    Err(error) => {
+                        log::warn!("failed to send paste link: {error}");
+                        (Task::none(), None)
+                    }
+                }
+            }
+            // The paste is put back in the input, to be edited or sent as is
+            Message::Pasted(draft, Err(error)) => {
+                log::warn!("paste upload failed: {error}");
+
+                history.record_message(
+                    draft.buffer.server(),
+                    data::Message::paste_failed(draft.buffer.clone(), &error),
+                );
+
+                self.paste_failed = Some(draft.text.clone());
+                history.record_draft(draft);
+
+                (Task::none(), None)
+            }
         }
     }
 
@@ -133,4 +320,56 @@ impl State {
 
         input::move_cursor_to_end(self.input_id.clone())
     }
+
+    /// Starts a `/note` about `nick` in the input, with their current note to edit
+    pub fn edit_note(
+        &mut self,
+        nick: Nick,
+        buffer: Buffer,
+        history: &mut history::Manager,
+    ) -> Task<Message> {
+        let text = match history.notes().get(buffer.server(), nick.as_ref().into()) {
+            Some(note) => format!("/note {nick} {note}"),
+            None => format!("/note {nick} "),
+        };
+
+        history.record_draft(Draft { buffer, text });
+
+        input::move_cursor_to_end(self.input_id.clone())
+    }
+}
+
+/// Stages commands given while disconnected to be applied on reconnect, describing the change
+fn stage(input: &Input, clients: &mut client::Map, config: Disconnected) -> Option<String> {
+    if let Some(nick) = input.nick().filter(|_| config.stage_nick) {
+        clients.stage_nick(input.server(), nick.to_string());
+
+        Some(format!("Nickname will change to {nick} on reconnect"))
+    } else if let Some(channels) = input.join_channels().filter(|_| config.queue_joins) {
+        let text = format!("Joining {} on reconnect", channels.join(", "));
+
+        clients.queue_joins(input.server(), channels);
+
+        Some(text)
+    } else {
+        None
+    }
+}
+
+/// Records sent `input` in its buffer's history, as sent by us
+pub fn record_input(input: Input, clients: &client::Map, history: &mut history::Manager) {
+    if let Some(nick) = clients.nickname(input.server()) {
+        let mut user = nick.to_owned().into();
+
+        // Resolve our attributes if sending this message in a channel
+        if let Buffer::Channel(server, channel) = input.buffer() {
+            if let Some(user_with_attributes) =
+                clients.resolve_user_attributes(server, channel, &user)
+            {
+                user = user_with_attributes.clone();
+            }
+        }
+
+        history.record_input(input, user);
+    }
 }
@@ -1,7 +1,10 @@
+use chrono::{DateTime, Utc};
+use data::buffer::AppearancePart;
+use data::config::buffer::OwnMessageStyle;
 use data::user::Nick;
 use data::{history, message, Config, Server};
-use iced::widget::{column, container, row, vertical_space};
-use iced::{Task, Length};
+use iced::widget::{column, container, row, text, vertical_space};
+use iced::{alignment, Length, Task};
 
 use super::{input_view, scroll_view, user_context};
 use crate::theme;
@@ -16,6 +19,10 @@ pub enum Message {
 #[derive(Debug, Clone)]
 pub enum Event {
     UserContext(user_context::Event),
+    JoinChannels(Server, Vec<String>),
+    Appearance(AppearancePart, Option<String>),
+    NoteChanged,
+    JumpTo(data::Buffer, DateTime<Utc>),
 }
 
 pub fn view<'a>(
@@ -24,10 +31,12 @@ pub fn view<'a>(
     history: &'a history::Manager,
     config: &'a Config,
     is_focused: bool,
+    read_only: bool,
 ) -> Element<'a, Message> {
     let status = clients.status(&state.server);
     let buffer = state.buffer();
     let input = history.input(&buffer);
+    let our_nick = clients.nickname(&state.server);
 
     let messages = container(
         scroll_view::view(
@@ -35,20 +44,26 @@ pub fn view<'a>(
             scroll_view::Kind::Query(&state.server, &state.nick),
             history,
             config,
-            move |message| {
+            move |message, repeats| {
                 let timestamp =
                     config
                         .buffer
                         .format_timestamp(&message.server_time)
                         .map(|timestamp| {
-                            selectable_text(timestamp).style(theme::selectable_text::transparent)
+                            scroll_view::with_tags(
+                                selectable_text(timestamp)
+                                    .part(selectable_text::Part::Timestamp)
+                                    .style(theme::selectable_text::transparent),
+                                message,
+                            )
                         });
 
                 match message.target.source() {
                     message::Source::User(user) => {
                         let nick = user_context::view(
-                            selectable_text(config.buffer.nickname.brackets.format(user)).style(
-                                |theme| {
+                            selectable_text(config.buffer.nickname.brackets.format(user))
+                                .part(selectable_text::Part::Nickname)
+                                .style(|theme| {
                                     theme::selectable_text::nickname(
                                         theme,
                                         user.nick_color(
@@ -57,17 +72,30 @@ pub fn view<'a>(
                                         ),
                                         false,
                                     )
-                                },
-                            ),
+                                }),
                             user,
                             None,
                             state.buffer(),
                             None,
+                            clients.get_ignored(&state.server),
+                            history.notes(),
                         )
                         .map(scroll_view::Message::UserContext);
 
+                        let is_own = message.is_own(our_nick);
+                        let own_style = config.buffer.own_messages.style;
+
                         let space = selectable_text(" ");
-                        let message = selectable_text(&message.text);
+                        let message = selectable_text(config.buffer.emojis.display(&message.text));
+                        let message = if is_own && own_style == OwnMessageStyle::Accent {
+                            message.style(theme::selectable_text::accent)
+                        } else {
+                            message
+                        };
+                        let repeats = (repeats > 1).then(|| {
+                            selectable_text(format!(" (x{repeats})"))
+                                .style(theme::selectable_text::transparent)
+                        });
 
                         Some(
                             container(
@@ -75,8 +103,16 @@ pub fn view<'a>(
                                     .push_maybe(timestamp)
                                     .push(nick)
                                     .push(space)
-                                    .push(message),
+                                    .push(message)
+                                    .push_maybe(repeats),
                             )
+                            .style(move |theme| {
+                                if is_own && own_style == OwnMessageStyle::Background {
+                                    theme::container::own_message(theme)
+                                } else {
+                                    Default::default()
+                                }
+                            })
                             .into(),
                         )
                     }
@@ -92,11 +128,14 @@ pub fn view<'a>(
                         Some(container(row![].push_maybe(timestamp).push(message)).into())
                     }
                     message::Source::Action => {
-                        let message =
-                            selectable_text(&message.text).style(theme::selectable_text::accent);
+                        let message = selectable_text(config.buffer.emojis.display(&message.text))
+                            .style(theme::selectable_text::accent);
 
                         Some(container(row![].push_maybe(timestamp).push(message)).into())
                     }
+                    message::Source::Internal(message::source::Internal::SearchResult(
+                        found_in,
+                    )) => Some(scroll_view::search_result(timestamp, message, found_in)),
                     message::Source::Internal(message::source::Internal::Status(status)) => {
                         let message = selectable_text(&message.text).style(move |theme| {
                             theme::selectable_text::status(
@@ -125,22 +164,47 @@ pub fn view<'a>(
     let text_input = show_text_input.then(|| {
         column![
             vertical_space().height(4),
-            input_view::view(
-                &state.input_view,
-                buffer,
-                input,
-                &[],
-                channels,
-                clients.get_isupport(&state.server),
-                is_focused,
-                !status.connected()
-            )
-            .map(Message::InputView)
+            if read_only {
+                input_view::read_only()
+            } else {
+                input_view::view(
+                    &state.input_view,
+                    buffer,
+                    input,
+                    &[],
+                    channels,
+                    clients.get_isupport(&state.server),
+                    is_focused,
+                    !status.connected(),
+                    config,
+                )
+                .map(Message::InputView)
+            }
         ]
         .width(Length::Fill)
     });
 
+    let read_receipt = config
+        .buffer
+        .query
+        .read_receipts
+        .show
+        .then(|| clients.get_read_receipt(&state.server, &state.nick))
+        .flatten()
+        .map(|read_at| {
+            container(
+                text(format!(
+                    "Read at {}",
+                    read_at.with_timezone(&chrono::Local).format("%H:%M")
+                ))
+                .style(theme::text::transparent),
+            )
+            .width(Length::Fill)
+            .align_x(alignment::Horizontal::Right)
+        });
+
     let scrollable = column![messages]
+        .push_maybe(read_receipt)
         .push_maybe(text_input)
         .height(Length::Fill);
 
@@ -178,6 +242,7 @@ impl Query {
         message: Message,
         clients: &mut data::client::Map,
         history: &mut history::Manager,
+        config: &Config,
     ) -> (Task<Message>, Option<Event>) {
         match message {
             Message::ScrollView(message) => {
@@ -185,12 +250,15 @@ impl Query {
 
                 let event = event.map(|event| match event {
                     scroll_view::Event::UserContext(event) => Event::UserContext(event),
+                    scroll_view::Event::JumpTo(buffer, server_time) => {
+                        Event::JumpTo(buffer, server_time)
+                    }
                 });
 
                 (command.map(Message::ScrollView), event)
             }
             Message::InputView(message) => {
-                let (command, event) = self.input_view.update(message, clients, history);
+                let (command, event) = self.input_view.update(message, clients, history, config);
                 let command = command.map(Message::InputView);
 
                 match event {
@@ -202,6 +270,23 @@ impl Query {
 
                         (command, None)
                     }
+                    Some(input_view::Event::SearchResults(results)) => {
+                        self.scroll_view.show_search_results(results);
+
+                        let command = Task::batch(vec![
+                            command,
+                            self.scroll_view.scroll_to_end().map(Message::ScrollView),
+                        ]);
+
+                        (command, None)
+                    }
+                    Some(input_view::Event::JoinChannels(server, channels)) => {
+                        (command, Some(Event::JoinChannels(server, channels)))
+                    }
+                    Some(input_view::Event::Appearance(part, hex)) => {
+                        (command, Some(Event::Appearance(part, hex)))
+                    }
+                    Some(input_view::Event::NoteChanged) => (command, Some(Event::NoteChanged)),
                     None => (command, None),
                 }
             }
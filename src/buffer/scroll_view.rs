@@ -1,13 +1,17 @@
+use chrono::{DateTime, Utc};
 use data::message::Limit;
 use data::server::Server;
 use data::user::Nick;
 use data::{history, time, Config};
-use iced::widget::{column, container, horizontal_rule, row, text, scrollable, Scrollable};
+use iced::widget::{
+    button, column, container, horizontal_rule, mouse_area, row, scrollable, text, tooltip,
+    Scrollable,
+};
 use iced::{Task, Length};
 
 use super::user_context;
 use crate::theme;
-use crate::widget::Element;
+use crate::widget::{selectable_text, Element};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -18,27 +22,36 @@ pub enum Message {
         status: Status,
         viewport: scrollable::Viewport,
     },
+    Pressed {
+        oldest: time::Posix,
+    },
+    Released,
+    Selection(bool),
     UserContext(user_context::Message),
+    JumpTo(data::Buffer, DateTime<Utc>),
 }
 
 #[derive(Debug, Clone)]
 pub enum Event {
     UserContext(user_context::Event),
+    /// Open a buffer at its message sent at the given time
+    JumpTo(data::Buffer, DateTime<Utc>),
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Kind<'a> {
     Server(&'a Server),
     Channel(&'a Server, &'a str),
+    Thread(&'a Server, &'a str, &'a str),
     Query(&'a Server, &'a Nick),
 }
 
 pub fn view<'a>(
-    state: &State,
+    state: &'a State,
     kind: Kind,
     history: &'a history::Manager,
     config: &'a Config,
-    format: impl Fn(&'a data::Message) -> Option<Element<'a, Message>> + 'a,
+    format: impl Fn(&'a data::Message, usize) -> Option<Element<'a, Message>> + 'a,
 ) -> Element<'a, Message> {
     let Some(history::View {
         total,
@@ -51,6 +64,9 @@ pub fn view<'a>(
         Kind::Channel(server, channel) => {
             history.get_channel_messages(server, channel, Some(state.limit), &config.buffer)
         }
+        Kind::Thread(server, channel, thread) => {
+            history.get_thread_messages(server, channel, thread, Some(state.limit), &config.buffer)
+        }
         Kind::Query(server, user) => {
             history.get_query_messages(server, user, Some(state.limit), &config.buffer)
         }
@@ -59,23 +75,32 @@ pub fn view<'a>(
         return column![].into();
     };
 
-    let count = old_messages.len() + new_messages.len();
+    let count = old_messages
+        .iter()
+        .chain(&new_messages)
+        .map(|entry| entry.repeats)
+        .sum::<usize>();
     let remaining = count < total;
     let oldest = old_messages
         .iter()
         .chain(&new_messages)
         .next()
-        .map(|message| message.received_at)
+        .map(|entry| entry.message.received_at)
         .unwrap_or_else(time::Posix::now);
     let status = state.status;
 
     let old = old_messages
         .into_iter()
-        .filter_map(&format)
+        .filter_map(|entry| format(entry.message, entry.repeats))
         .collect::<Vec<_>>();
     let new = new_messages
         .into_iter()
-        .filter_map(format)
+        .filter_map(|entry| format(entry.message, entry.repeats))
+        .collect::<Vec<_>>();
+    let search_results = state
+        .search_results
+        .iter()
+        .filter_map(|message| format(message, 1))
         .collect::<Vec<_>>();
 
     let show_divider = !new.is_empty() || matches!(status, Status::Idle(Anchor::Bottom));
@@ -97,12 +122,12 @@ pub fn view<'a>(
         .padding(2)
         .align_items(iced::Alignment::Center);
 
-        column![column(old), divider, column(new)]
+        column![column(old), divider, column(new), column(search_results)]
     } else {
-        column![column(old), column(new)]
+        column![column(old), column(new), column(search_results)]
     };
 
-    Scrollable::with_direction(
+    let scrollable = Scrollable::with_direction(
         container(content).width(Length::Fill).padding([0, 8]),
         scrollable::Direction::Vertical(
             scrollable::Properties::default()
@@ -118,15 +143,64 @@ pub fn view<'a>(
         status,
         viewport,
     })
-    .id(state.scrollable.clone())
+    .id(state.scrollable.clone());
+
+    mouse_area(scrollable)
+        .on_press(Message::Pressed { oldest })
+        .on_release(Message::Released)
+        .into()
+}
+
+/// Shows the tags `message` was received with when hovering `content`
+pub fn with_tags<'a>(
+    content: impl Into<Element<'a, Message>>,
+    message: &'a data::Message,
+) -> Element<'a, Message> {
+    if message.tags.is_empty() {
+        return content.into();
+    }
+
+    let tags = message
+        .tags
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    tooltip(
+        content,
+        container(text(tags).style(theme::text::transparent))
+            .style(theme::container::context)
+            .padding(8),
+        tooltip::Position::Bottom,
+    )
     .into()
 }
 
+/// A search hit, which jumps to the message it was found in when pressed
+pub fn search_result<'a>(
+    timestamp: Option<Element<'a, Message>>,
+    message: &'a data::Message,
+    found_in: &'a data::Buffer,
+) -> Element<'a, Message> {
+    let hit = button(text(&message.text).style(theme::text::info))
+        .padding(0)
+        .style(theme::button::bare)
+        .on_press(Message::JumpTo(found_in.clone(), message.server_time));
+
+    container(row![].push_maybe(timestamp).push(hit)).into()
+}
+
 #[derive(Debug, Clone)]
 pub struct State {
     pub scrollable: scrollable::Id,
     limit: Limit,
     status: Status,
+    /// Messages are held in place while text is pressed or selected
+    paused: bool,
+    at_bottom: bool,
+    /// Results of the last search, shown below the messages but never kept in history
+    search_results: Vec<data::Message>,
 }
 
 impl Default for State {
@@ -135,6 +209,9 @@ impl Default for State {
             scrollable: scrollable::Id::unique(),
             limit: Limit::bottom(),
             status: Status::default(),
+            paused: false,
+            at_bottom: true,
+            search_results: vec![],
         }
     }
 }
@@ -144,6 +221,10 @@ impl State {
         Self::default()
     }
 
+    pub fn show_search_results(&mut self, results: Vec<data::Message>) {
+        self.search_results = results;
+    }
+
     pub fn update(&mut self, message: Message) -> (Task<Message>, Option<Event>) {
         match message {
             Message::Scrolled {
@@ -204,6 +285,14 @@ impl State {
                     Status::Unlocked(_) | Status::Idle(_) => {}
                 }
 
+                self.at_bottom = old_status.is_bottom(relative_offset);
+
+                // Don't follow new messages while paused, so a selection stays in place
+                if self.paused && matches!(self.status, Status::Idle(Anchor::Bottom)) {
+                    self.status = Status::Unlocked(Anchor::Bottom);
+                    self.limit = Limit::Since(oldest);
+                }
+
                 if let Some(new_offset) = self.status.new_offset(old_status, viewport) {
                     return (
                         scrollable::scroll_to(self.scrollable.clone(), new_offset),
@@ -211,12 +300,54 @@ impl State {
                     );
                 }
             }
+            Message::Pressed { oldest } => {
+                self.paused = true;
+
+                if matches!(self.status, Status::Idle(Anchor::Bottom)) {
+                    self.status = Status::Unlocked(Anchor::Bottom);
+                    self.limit = Limit::Since(oldest);
+
+                    // Same position, now aligned to the start
+                    return (
+                        scrollable::snap_to(
+                            self.scrollable.clone(),
+                            scrollable::RelativeOffset { x: 0.0, y: 1.0 },
+                        ),
+                        None,
+                    );
+                }
+            }
+            Message::Released => {
+                return (
+                    selectable_text::selected_in(self.scrollable.clone(), |contents| {
+                        Message::Selection(contents.iter().any(|content| {
+                            content
+                                .selected
+                                .as_ref()
+                                .is_some_and(|text| !text.is_empty())
+                        }))
+                    }),
+                    None,
+                );
+            }
+            Message::Selection(selected) => {
+                if self.paused && !selected {
+                    self.paused = false;
+
+                    if self.at_bottom {
+                        return (self.scroll_to_end(), None);
+                    }
+                }
+            }
             Message::UserContext(message) => {
                 return (
                     Task::none(),
                     Some(Event::UserContext(user_context::update(message))),
                 );
             }
+            Message::JumpTo(buffer, server_time) => {
+                return (Task::none(), Some(Event::JumpTo(buffer, server_time)));
+            }
         }
 
         (Task::none(), None)
@@ -231,7 +362,18 @@ impl State {
         )
     }
 
+    /// Shows the messages from the one received at `received_at`, starting from the top
+    pub fn scroll_to_message(&mut self, received_at: time::Posix) -> Task<Message> {
+        self.status = Status::Unlocked(Anchor::Bottom);
+        self.limit = Limit::Since(received_at);
+        scrollable::snap_to(
+            self.scrollable.clone(),
+            scrollable::RelativeOffset { x: 0.0, y: 1.0 },
+        )
+    }
+
     pub fn scroll_to_end(&mut self) -> Task<Message> {
+        self.paused = false;
         self.status = Status::Idle(Anchor::Bottom);
         self.limit = Limit::bottom();
         scrollable::scroll_to(
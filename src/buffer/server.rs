@@ -1,3 +1,5 @@
+use chrono::{DateTime, Utc};
+use data::buffer::AppearancePart;
 use data::{history, message, Config};
 use iced::widget::{column, container, row, vertical_space};
 use iced::{Task, Length};
@@ -12,12 +14,21 @@ pub enum Message {
     InputView(input_view::Message),
 }
 
+#[derive(Debug, Clone)]
+pub enum Event {
+    JoinChannels(data::Server, Vec<String>),
+    Appearance(AppearancePart, Option<String>),
+    NoteChanged,
+    JumpTo(data::Buffer, DateTime<Utc>),
+}
+
 pub fn view<'a>(
     state: &'a Server,
     clients: &'a data::client::Map,
     history: &'a history::Manager,
     config: &'a Config,
     is_focused: bool,
+    compact: bool,
 ) -> Element<'a, Message> {
     let status = clients.status(&state.server);
     let buffer = state.buffer();
@@ -29,16 +40,31 @@ pub fn view<'a>(
             scroll_view::Kind::Server(&state.server),
             history,
             config,
-            move |message| {
+            move |message, _| {
                 let timestamp =
                     config
                         .buffer
                         .format_timestamp(&message.server_time)
                         .map(|timestamp| {
-                            selectable_text(timestamp).style(theme::selectable_text::transparent)
+                            scroll_view::with_tags(
+                                selectable_text(timestamp)
+                                    .part(selectable_text::Part::Timestamp)
+                                    .style(theme::selectable_text::transparent),
+                                message,
+                            )
                         });
 
                 match message.target.source() {
+                    message::Source::Server(Some(server))
+                        if compact
+                            && matches!(
+                                server.kind(),
+                                message::source::server::Kind::Numeric(numeric)
+                                    if config.buffer.compact_server_buffer.hides(numeric)
+                            ) =>
+                    {
+                        None
+                    }
                     message::Source::Server(server) => {
                         let message = selectable_text(&message.text).style(move |theme| {
                             theme::selectable_text::server(
@@ -50,6 +76,9 @@ pub fn view<'a>(
 
                         Some(container(row![].push_maybe(timestamp).push(message)).into())
                     }
+                    message::Source::Internal(message::source::Internal::SearchResult(
+                        found_in,
+                    )) => Some(scroll_view::search_result(timestamp, message, found_in)),
                     message::Source::Internal(message::source::Internal::Status(status)) => {
                         let message = selectable_text(&message.text).style(move |theme| {
                             theme::selectable_text::status(
@@ -87,7 +116,8 @@ pub fn view<'a>(
                 channels,
                 clients.get_isupport(&state.server),
                 is_focused,
-                !status.connected()
+                !status.connected(),
+                config,
             )
             .map(Message::InputView)
         ]
@@ -130,22 +160,52 @@ impl Server {
         message: Message,
         clients: &mut data::client::Map,
         history: &mut history::Manager,
-    ) -> Task<Message> {
+        config: &Config,
+    ) -> (Task<Message>, Option<Event>) {
         match message {
             Message::ScrollView(message) => {
-                let (command, _) = self.scroll_view.update(message);
-                command.map(Message::ScrollView)
+                let (command, event) = self.scroll_view.update(message);
+
+                let event = event.and_then(|event| match event {
+                    scroll_view::Event::JumpTo(buffer, server_time) => {
+                        Some(Event::JumpTo(buffer, server_time))
+                    }
+                    scroll_view::Event::UserContext(_) => None,
+                });
+
+                (command.map(Message::ScrollView), event)
             }
             Message::InputView(message) => {
-                let (command, event) = self.input_view.update(message, clients, history);
+                let (command, event) = self.input_view.update(message, clients, history, config);
                 let command = command.map(Message::InputView);
 
                 match event {
-                    Some(input_view::Event::InputSent) => Task::batch(vec![
-                        command,
-                        self.scroll_view.scroll_to_end().map(Message::ScrollView),
-                    ]),
-                    None => command,
+                    Some(input_view::Event::InputSent) => {
+                        let command = Task::batch(vec![
+                            command,
+                            self.scroll_view.scroll_to_end().map(Message::ScrollView),
+                        ]);
+
+                        (command, None)
+                    }
+                    Some(input_view::Event::SearchResults(results)) => {
+                        self.scroll_view.show_search_results(results);
+
+                        let command = Task::batch(vec![
+                            command,
+                            self.scroll_view.scroll_to_end().map(Message::ScrollView),
+                        ]);
+
+                        (command, None)
+                    }
+                    Some(input_view::Event::JoinChannels(server, channels)) => {
+                        (command, Some(Event::JoinChannels(server, channels)))
+                    }
+                    Some(input_view::Event::Appearance(part, hex)) => {
+                        (command, Some(Event::Appearance(part, hex)))
+                    }
+                    Some(input_view::Event::NoteChanged) => (command, Some(Event::NoteChanged)),
+                    None => (command, None),
                 }
             }
         }
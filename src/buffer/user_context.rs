@@ -1,3 +1,4 @@
+use data::notes::Notes;
 use data::user::Nick;
 use data::{Buffer, User};
 use iced::widget::{button, container, horizontal_rule, row, text, Space};
@@ -13,33 +14,66 @@ enum Entry {
     ToggleAccessLevelOp,
     ToggleAccessLevelVoice,
     SendFile,
+    ToggleIgnore,
+    EditNote,
+    ToggleAway,
     UserInfo,
+    Note,
     HorizontalRule,
 }
 
 impl Entry {
-    fn list(buffer: &Buffer, our_user: Option<&User>) -> Vec<Self> {
+    fn list(buffer: &Buffer, user: &User, our_user: Option<&User>, has_note: bool) -> Vec<Self> {
+        let is_us = our_user.is_some_and(|our_user| our_user.nickname() == user.nickname());
+        let our_level = our_user.map(User::highest_access_level);
+
         match buffer {
             Buffer::Channel(_, _) => {
-                if our_user.is_some_and(|u| u.has_access_level(data::user::AccessLevel::Oper)) {
-                    vec![
-                        Entry::UserInfo,
-                        Entry::HorizontalRule,
-                        Entry::Whois,
-                        Entry::Query,
-                        Entry::ToggleAccessLevelOp,
-                        Entry::ToggleAccessLevelVoice,
-                        Entry::SendFile,
-                    ]
+                let mut entries = vec![Entry::UserInfo];
+
+                if has_note {
+                    entries.push(Entry::Note);
+                }
+
+                entries.extend([Entry::HorizontalRule, Entry::Whois]);
+
+                if is_us {
+                    entries.push(Entry::ToggleAway);
                 } else {
-                    vec![
-                        Entry::UserInfo,
-                        Entry::HorizontalRule,
-                        Entry::Whois,
-                        Entry::Query,
-                        Entry::SendFile,
-                    ]
+                    entries.push(Entry::Query);
+                }
+
+                // Halfops can only (de)voice
+                if our_level >= Some(data::user::AccessLevel::Oper) {
+                    entries.push(Entry::ToggleAccessLevelOp);
+                }
+                if our_level >= Some(data::user::AccessLevel::HalfOp) {
+                    entries.push(Entry::ToggleAccessLevelVoice);
+                }
+
+                if !is_us {
+                    entries.push(Entry::SendFile);
+                    entries.push(Entry::ToggleIgnore);
+                    entries.push(Entry::EditNote);
+                }
+
+                entries
+            }
+            Buffer::Query(_, _) if !is_us => {
+                let mut entries = vec![];
+
+                if has_note {
+                    entries.extend([Entry::Note, Entry::HorizontalRule]);
                 }
+
+                entries.extend([
+                    Entry::Whois,
+                    Entry::SendFile,
+                    Entry::ToggleIgnore,
+                    Entry::EditNote,
+                ]);
+
+                entries
             }
             Buffer::Server(_) | Buffer::Query(_, _) => vec![Entry::Whois, Entry::SendFile],
         }
@@ -53,6 +87,9 @@ pub enum Message {
     SingleClick(Nick),
     ToggleAccessLevel(Nick, String),
     SendFile(Nick),
+    ToggleIgnore(Nick),
+    EditNote(Nick),
+    ToggleAway(bool),
 }
 
 #[derive(Debug, Clone)]
@@ -62,6 +99,12 @@ pub enum Event {
     SingleClick(Nick),
     ToggleAccessLevel(Nick, String),
     SendFile(Nick),
+    /// Ignore the user, or stop ignoring them
+    ToggleIgnore(Nick),
+    /// Write a note about the user, starting from the current one
+    EditNote(Nick),
+    /// Mark ourselves as away, or back when `false`
+    ToggleAway(bool),
 }
 
 pub fn update(message: Message) -> Event {
@@ -71,6 +114,9 @@ pub fn update(message: Message) -> Event {
         Message::SingleClick(nick) => Event::SingleClick(nick),
         Message::ToggleAccessLevel(nick, mode) => Event::ToggleAccessLevel(nick, mode),
         Message::SendFile(nick) => Event::SendFile(nick),
+        Message::ToggleIgnore(nick) => Event::ToggleIgnore(nick),
+        Message::EditNote(nick) => Event::EditNote(nick),
+        Message::ToggleAway(away) => Event::ToggleAway(away),
     }
 }
 
@@ -80,8 +126,12 @@ pub fn view<'a>(
     current_user: Option<&'a User>,
     buffer: Buffer,
     our_user: Option<&'a User>,
+    ignored: &'a [Nick],
+    notes: &'a Notes,
 ) -> Element<'a, Message> {
-    let entries = Entry::list(&buffer, our_user);
+    let note = notes.get(buffer.server(), user.nickname());
+    let entries = Entry::list(&buffer, user, our_user, note.is_some());
+    let is_ignored = ignored.iter().any(|nick| user.nickname() == *nick);
 
     let content = button(content)
         .padding(0)
@@ -125,7 +175,34 @@ pub fn view<'a>(
                 }
             }
             Entry::SendFile => menu_button("Send File", Message::SendFile(nickname), length),
+            Entry::ToggleIgnore => {
+                if is_ignored {
+                    menu_button("Unignore", Message::ToggleIgnore(nickname), length)
+                } else {
+                    menu_button("Ignore", Message::ToggleIgnore(nickname), length)
+                }
+            }
+            Entry::EditNote => {
+                if note.is_some() {
+                    menu_button("Edit Note", Message::EditNote(nickname), length)
+                } else {
+                    menu_button("Add Note", Message::EditNote(nickname), length)
+                }
+            }
+            Entry::ToggleAway => {
+                if current_user.is_some_and(User::is_away) {
+                    menu_button("Set Back", Message::ToggleAway(false), length)
+                } else {
+                    menu_button("Set Away", Message::ToggleAway(true), length)
+                }
+            }
             Entry::UserInfo => user_info(current_user, length),
+            Entry::Note => {
+                container(text(note.unwrap_or_default()).style(theme::text::transparent))
+                    .padding(5)
+                    .width(length)
+                    .into()
+            }
             Entry::HorizontalRule => match length {
                 Length::Fill => container(horizontal_rule(1)).padding([0, 6]).into(),
                 _ => Space::new(length, 1).into(),
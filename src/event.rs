@@ -3,10 +3,12 @@ use iced::{event, keyboard, window, Subscription};
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Event {
     CloseRequested,
-    Copy,
+    Copy { shift: bool },
     Escape,
     Home,
     End,
+    Focused,
+    Unfocused,
 }
 
 pub fn events() -> Subscription<Event> {
@@ -25,7 +27,9 @@ fn filtered_events(event: iced::Event, status: iced::event::Status, _id: iced::w
             key: keyboard::Key::Character(c),
             modifiers,
             ..
-        }) if c.as_str() == "c" && modifiers.command() => Some(Event::Copy),
+        }) if c.as_str().eq_ignore_ascii_case("c") && modifiers.command() => Some(Event::Copy {
+            shift: modifiers.shift(),
+        }),
         iced::Event::Keyboard(keyboard::Event::KeyPressed {
             key: keyboard::Key::Named(keyboard::key::Named::Home),
             ..
@@ -35,6 +39,8 @@ fn filtered_events(event: iced::Event, status: iced::event::Status, _id: iced::w
             ..
         }) if ignored(status) => Some(Event::End),
         iced::Event::Window(window::Event::CloseRequested) => Some(Event::CloseRequested),
+        iced::Event::Window(window::Event::Focused) => Some(Event::Focused),
+        iced::Event::Window(window::Event::Unfocused) => Some(Event::Unfocused),
         _ => None,
     }
 }
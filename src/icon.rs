@@ -42,6 +42,10 @@ pub fn topic<'a>() -> Text<'a> {
     to_text('\u{f5af}')
 }
 
+pub fn threads<'a>() -> Text<'a> {
+    to_text('\u{f474}')
+}
+
 pub fn search<'a>() -> Text<'a> {
     to_text('\u{f52a}')
 }
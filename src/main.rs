@@ -8,6 +8,7 @@ mod icon;
 mod logger;
 mod modal;
 mod notification;
+mod rules;
 mod screen;
 mod stream;
 mod theme;
@@ -29,6 +30,7 @@ use iced::{executor, Task, Length, Renderer, Subscription};
 use screen::{dashboard, help, migration, welcome};
 
 use self::event::{events, Event};
+use self::modal::confirm_join::ConfirmJoin;
 use self::modal::Modal;
 use self::theme::Theme;
 use self::widget::Element;
@@ -37,15 +39,22 @@ pub fn main() -> iced::Result {
     let mut args = env::args();
     args.next();
 
-    let version = args
-        .next()
-        .map(|s| s == "--version" || s == "-V")
-        .unwrap_or_default();
+    match args.next().as_deref() {
+        Some("--version" | "-V") => {
+            println!("halloy {}", environment::formatted_version());
 
-    if version {
-        println!("halloy {}", environment::formatted_version());
+            return Ok(());
+        }
+        Some("--export-profile") => {
+            let path = args.next();
+            let include_secrets = args.any(|arg| arg == "--include-secrets");
 
-        return Ok(());
+            return exit_with(path.map(|path| export_profile(&path, include_secrets)));
+        }
+        Some("--import-profile") => {
+            return exit_with(args.next().map(|path| import_profile(&path)));
+        }
+        _ => {}
     }
 
     #[cfg(debug_assertions)]
@@ -85,6 +94,61 @@ pub fn main() -> iced::Result {
     }
 }
 
+fn export_profile(path: &str, include_secrets: bool) -> Result<(), String> {
+    let config = config::profile::read_config().map_err(|e| e.to_string())?;
+    let profile = config::profile::export(&config, include_secrets).map_err(|e| e.to_string())?;
+
+    std::fs::write(path, profile).map_err(|e| e.to_string())?;
+
+    if include_secrets {
+        println!("Exported servers to {path}, including their passwords");
+    } else {
+        println!("Exported servers to {path}, without their passwords and commands");
+    }
+
+    Ok(())
+}
+
+fn import_profile(path: &str) -> Result<(), String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let config = config::profile::read_config().map_err(|e| e.to_string())?;
+    let import = config::profile::prepare(&config, &content).map_err(|e| e.to_string())?;
+
+    for server in &import.unchanged {
+        println!("{server}: already configured");
+    }
+    for server in &import.conflicts {
+        println!("{server}: configured differently, left as is");
+    }
+
+    if !import.conflicts.is_empty() {
+        return Err("nothing was imported, rename or remove the conflicting servers".to_string());
+    }
+
+    config::profile::apply(&config, &import).map_err(|e| e.to_string())?;
+
+    for server in import.added() {
+        println!("{server}: added");
+    }
+
+    Ok(())
+}
+
+fn exit_with(result: Option<Result<(), String>>) -> iced::Result {
+    match result {
+        Some(Ok(())) => Ok(()),
+        Some(Err(error)) => {
+            eprintln!("error: {error}");
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("usage: halloy --export-profile <file> [--include-secrets]");
+            eprintln!("       halloy --import-profile <file>");
+            std::process::exit(2);
+        }
+    }
+}
+
 fn settings(
     config_load: Result<Config, config::Error>,
     route_received: Option<data::Url>,
@@ -122,6 +186,8 @@ struct Halloy {
     servers: server::Map,
     modal: Option<Modal>,
     window: Window,
+    notifications: notification::Notifications,
+    rules: data::rules::Engine,
 }
 
 impl Halloy {
@@ -184,6 +250,8 @@ impl Halloy {
                 config,
                 modal: None,
                 window: Window::load().unwrap_or_default(),
+                notifications: notification::Notifications::default(),
+                rules: data::rules::Engine::default(),
             },
             command,
         )
@@ -211,6 +279,7 @@ pub enum Message {
     RouteReceived(String),
     Window(data::window::Event),
     WindowSettingsSaved(Result<(), data::window::Error>),
+    ChannelListSaved(Result<(), data::channel_list::Error>),
 }
 
 impl Application for Halloy {
@@ -258,6 +327,9 @@ impl Application for Halloy {
                 // Retrack after dashboard state changes
                 let track = dashboard.track();
 
+                // Queries opened or focused are now read
+                dashboard.send_read_receipts(&mut self.clients, &self.config);
+
                 if let Some(event) = event {
                     match event {
                         dashboard::Event::ReloadConfiguration => match Config::load() {
@@ -284,6 +356,13 @@ impl Application for Halloy {
                         dashboard::Event::QuitServer(server) => {
                             self.clients.quit(&server, None);
                         }
+                        dashboard::Event::ConfirmJoin(server, channels) => {
+                            self.modal =
+                                Some(Modal::ConfirmJoin(ConfirmJoin::new(server, channels, None)));
+                        }
+                        dashboard::Event::ConfirmLeave(buffer) => {
+                            self.modal = Some(Modal::ConfirmLeave(buffer));
+                        }
                     }
                 }
 
@@ -369,11 +448,8 @@ impl Application for Halloy {
                         // Intial is sent when first trying to connect
                         dashboard.broadcast_connecting(&server, &self.config, sent_time);
                     } else {
-                        let notification = &self.config.notifications.disconnected;
-
-                        if notification.enabled {
-                            notification::show("Disconnected", &server, notification.sound());
-                        };
+                        self.notifications
+                            .disconnected(&self.config.notifications, &server);
 
                         dashboard.broadcast_disconnected(&server, error, &self.config, sent_time);
                     }
@@ -401,11 +477,8 @@ impl Application for Halloy {
 
                         dashboard.broadcast_connected(&server, &self.config, sent_time);
                     } else {
-                        let notification = &self.config.notifications.reconnected;
-
-                        if notification.enabled {
-                            notification::show("Reconnected", &server, notification.sound());
-                        }
+                        self.notifications
+                            .reconnected(&self.config.notifications, &server);
 
                         dashboard.broadcast_reconnected(&server, &self.config, sent_time);
                     }
@@ -417,6 +490,8 @@ impl Application for Halloy {
                     error,
                     sent_time,
                 } => {
+                    self.clients.connection_failed(&server);
+
                     let Screen::Dashboard(dashboard) = &mut self.screen else {
                         return Task::none();
                     };
@@ -430,6 +505,14 @@ impl Application for Halloy {
                         return Task::none();
                     };
 
+                    // Tags are only kept for inspection when enabled for the server
+                    let show_tags = self.clients.shows_tags(&server);
+                    let kept_tags = |encoded: &data::message::Encoded| {
+                        show_tags
+                            .then(|| data::message::tags(encoded))
+                            .unwrap_or_default()
+                    };
+
                     let commands = messages
                         .into_iter()
                         .flat_map(|message| {
@@ -445,16 +528,102 @@ impl Application for Halloy {
 
                                 match event {
                                     data::client::Event::Single(encoded, our_nick) => {
+                                        let tags = kept_tags(&encoded);
+                                        let membership = data::rules::Event::membership(
+                                            &server,
+                                            &encoded,
+                                            our_nick.as_ref().into(),
+                                        );
+
                                         if let Some(message) = data::Message::received(
                                             encoded,
-                                            our_nick,
+                                            our_nick.clone(),
                                             &self.config,
                                             resolve_user_attributes,
                                         ) {
+                                            let message = message.with_tags(tags);
+                                            let is_own =
+                                                message.is_own(Some(our_nick.as_ref().into()));
+
+                                            if let Some(event) = membership.or_else(|| {
+                                                data::rules::Event::message(
+                                                    &server,
+                                                    &message,
+                                                    our_nick.as_ref().into(),
+                                                )
+                                            }) {
+                                                rules::run(
+                                                    &mut self.rules,
+                                                    &self.config,
+                                                    &mut self.clients,
+                                                    dashboard,
+                                                    event,
+                                                );
+                                            }
+
+                                            match &message.target {
+                                                data::message::Target::Channel {
+                                                    channel,
+                                                    source: data::message::Source::User(user),
+                                                } if !is_own => {
+                                                    self.notifications.activity(
+                                                        &self.config.notifications,
+                                                        &server,
+                                                        channel,
+                                                        user,
+                                                        message.server_time,
+                                                    );
+                                                }
+                                                // Messages arriving in an open query are read,
+                                                // others once their query is seen
+                                                data::message::Target::Query {
+                                                    nick,
+                                                    source: data::message::Source::User(_),
+                                                } if !is_own
+                                                    && self
+                                                        .config
+                                                        .buffer
+                                                        .query
+                                                        .read_receipts
+                                                        .send =>
+                                                {
+                                                    if dashboard.is_visible(&data::Buffer::Query(
+                                                        server.clone(),
+                                                        nick.clone(),
+                                                    )) {
+                                                        self.clients.send_read_receipt(
+                                                            &server,
+                                                            nick,
+                                                            message.id.clone(),
+                                                        );
+                                                    } else {
+                                                        self.clients.mark_unread(
+                                                            &server,
+                                                            nick,
+                                                            message.id.clone(),
+                                                        );
+                                                    }
+                                                }
+                                                _ => {}
+                                            }
+
+                                            commands.push(
+                                                dashboard
+                                                    .open_incoming_query(
+                                                        &self.clients,
+                                                        &server,
+                                                        &message,
+                                                        &self.config,
+                                                    )
+                                                    .map(Message::Dashboard),
+                                            );
+
                                             dashboard.record_message(&server, message);
                                         }
                                     }
                                     data::client::Event::WithTarget(encoded, our_nick, target) => {
+                                        let tags = kept_tags(&encoded);
+
                                         if let Some(message) = data::Message::received(
                                             encoded,
                                             our_nick,
@@ -463,7 +632,7 @@ impl Application for Halloy {
                                         ) {
                                             dashboard.record_message(
                                                 &server,
-                                                message.with_target(target),
+                                                message.with_target(target).with_tags(tags),
                                             );
                                         }
                                     }
@@ -519,39 +688,147 @@ impl Application for Halloy {
                                                 sent_time,
                                             );
                                         }
+                                        data::client::Broadcast::ChangeHost {
+                                            old_user,
+                                            new_username,
+                                            new_hostname,
+                                            ourself,
+                                            logged_in,
+                                            channels,
+                                            sent_time,
+                                        } => {
+                                            dashboard.broadcast_change_host(
+                                                &server,
+                                                old_user,
+                                                new_username,
+                                                new_hostname,
+                                                ourself,
+                                                logged_in,
+                                                channels,
+                                                &self.config,
+                                                sent_time,
+                                            );
+                                        }
+                                        data::client::Broadcast::SaslAuthenticated {
+                                            mechanism,
+                                            account,
+                                            sent_time,
+                                        } => {
+                                            dashboard.broadcast_sasl_authenticated(
+                                                &server,
+                                                mechanism,
+                                                account,
+                                                &self.config,
+                                                sent_time,
+                                            );
+                                        }
+                                        data::client::Broadcast::SaslFailed {
+                                            mechanism,
+                                            reason,
+                                            sent_time,
+                                        } => {
+                                            dashboard.broadcast_sasl_failed(
+                                                &server,
+                                                mechanism,
+                                                reason,
+                                                &self.config,
+                                                sent_time,
+                                            );
+                                        }
                                     },
                                     data::client::Event::Notification(
                                         encoded,
                                         our_nick,
                                         notification,
                                     ) => {
+                                        // Author of a relayed message, rather than the relay bot
+                                        let mut author = None;
+                                        let mut text = String::new();
+                                        // Rules for ordinary messages act on highlights too
+                                        let mut message_event = None;
+                                        let tags = kept_tags(&encoded);
+                                        let server_time = data::message::server_time(&encoded);
+
                                         if let Some(message) = data::Message::received(
                                             encoded,
-                                            our_nick,
+                                            our_nick.clone(),
                                             &self.config,
                                             resolve_user_attributes,
                                         ) {
-                                            dashboard.record_message(&server, message);
+                                            let message = message.with_tags(tags);
+
+                                            message_event = data::rules::Event::message(
+                                                &server,
+                                                &message,
+                                                our_nick.as_ref().into(),
+                                            );
+
+                                            if let data::message::Source::User(user) =
+                                                message.target.source()
+                                            {
+                                                author = Some(user.clone());
+                                            }
+
+                                            text.clone_from(&message.text);
+
+                                            commands.push(
+                                                dashboard
+                                                    .open_incoming_query(
+                                                        &self.clients,
+                                                        &server,
+                                                        &message,
+                                                        &self.config,
+                                                    )
+                                                    .map(Message::Dashboard),
+                                            );
+
+                                            dashboard.record_highlight(&server, message);
                                         }
 
+                                        let buffer = notification.buffer(&server);
+
                                         match notification {
                                             data::client::Notification::Highlight(
                                                 user,
                                                 channel,
                                             ) => {
-                                                let notification =
-                                                    &self.config.notifications.highlight;
-                                                if notification.enabled {
-                                                    notification::show(
-                                                        "Highlight",
-                                                        format!(
-                                                            "{} highlighted you in {}",
-                                                            user.nickname(),
-                                                            channel
-                                                        ),
-                                                        notification.sound(),
+                                                let author = author.unwrap_or(user);
+
+                                                self.notifications.highlight(
+                                                    &self.config.notifications,
+                                                    &author,
+                                                    &channel,
+                                                );
+
+                                                rules::run(
+                                                    &mut self.rules,
+                                                    &self.config,
+                                                    &mut self.clients,
+                                                    dashboard,
+                                                    data::rules::Event::highlight(
+                                                        &server,
+                                                        &channel,
+                                                        author.nickname(),
+                                                        &text,
+                                                        server_time,
+                                                    ),
+                                                );
+
+                                                if let Some(event) = message_event {
+                                                    rules::run(
+                                                        &mut self.rules,
+                                                        &self.config,
+                                                        &mut self.clients,
+                                                        dashboard,
+                                                        event,
                                                     );
                                                 }
+
+                                                commands.push(
+                                                    dashboard
+                                                        .focus_mention(&buffer, &self.config)
+                                                        .map(Message::Dashboard),
+                                                );
                                             }
                                         }
                                     }
@@ -564,6 +841,109 @@ impl Application for Halloy {
                                             commands.push(command.map(Message::Dashboard));
                                         }
                                     }
+                                    data::client::Event::SearchResults(
+                                        buffer,
+                                        encoded,
+                                        our_nick,
+                                    ) => {
+                                        let results = encoded
+                                            .into_iter()
+                                            .filter_map(|encoded| {
+                                                data::Message::received(
+                                                    encoded,
+                                                    our_nick.clone(),
+                                                    &self.config,
+                                                    resolve_user_attributes,
+                                                )
+                                            })
+                                            .collect();
+
+                                        dashboard.record_search_results(&buffer, results);
+                                    }
+                                    data::client::Event::Help(topic, lines) => {
+                                        for message in data::Message::help(&topic, lines) {
+                                            dashboard.record_message(&server, message);
+                                        }
+                                    }
+                                    data::client::Event::Links(links) => {
+                                        for message in data::Message::links(links) {
+                                            dashboard.record_message(&server, message);
+                                        }
+                                    }
+                                    data::client::Event::UserModesNotSet(modes, reason) => {
+                                        dashboard.record_message(
+                                            &server,
+                                            data::Message::user_modes_not_set(&modes, &reason),
+                                        );
+                                    }
+                                    data::client::Event::Undelivered(nick, text) => {
+                                        let offline = self.config.buffer.query.offline;
+
+                                        // Delivering once they're back requires tracking them
+                                        let tracked = (offline.monitor
+                                            || offline.deliver_when_online)
+                                            && self.clients.monitor(&server, &nick);
+                                        let queued = tracked && offline.deliver_when_online;
+
+                                        if queued {
+                                            self.clients.queue_for_online(
+                                                &server,
+                                                nick.clone(),
+                                                text,
+                                            );
+                                        }
+
+                                        dashboard.record_message(
+                                            &server,
+                                            data::Message::undelivered(nick, queued),
+                                        );
+                                    }
+                                    data::client::Event::Delivered(nick, count) => {
+                                        dashboard.record_message(
+                                            &server,
+                                            data::Message::delivered(nick, count),
+                                        );
+                                    }
+                                    data::client::Event::Redirect(host, port, info) => {
+                                        self.modal = Some(Modal::ConfirmRedirect(
+                                            server.clone(),
+                                            host,
+                                            port,
+                                            info,
+                                        ));
+                                    }
+                                    data::client::Event::ListStats(stats) => {
+                                        for message in data::Message::list_stats(stats) {
+                                            dashboard.record_message(&server, message);
+                                        }
+                                    }
+                                    data::client::Event::ChannelList(cached) => {
+                                        commands.push(Task::perform(
+                                            data::channel_list::save(server.clone(), cached),
+                                            Message::ChannelListSaved,
+                                        ));
+                                    }
+                                    data::client::Event::Unsent(buffer, text) => {
+                                        if self.config.buffer.channel.cannot_send.restore_input {
+                                            dashboard.restore_unsent(buffer, text);
+                                        }
+                                    }
+                                    data::client::Event::Presence(update) => {
+                                        if let data::presence::Update::Online(users) = update {
+                                            let notification =
+                                                &self.config.notifications.monitored_online;
+
+                                            if notification.enabled {
+                                                for user in users {
+                                                    notification::show(
+                                                        "Online",
+                                                        format!("{} is online", user.nickname()),
+                                                        notification.sound(),
+                                                    );
+                                                }
+                                            }
+                                        }
+                                    }
                                 }
                             }
 
@@ -604,7 +984,7 @@ impl Application for Halloy {
             },
             Message::Event(event) => {
                 if let Screen::Dashboard(dashboard) = &mut self.screen {
-                    dashboard
+                    let task = dashboard
                         .handle_event(
                             event,
                             &self.clients,
@@ -612,7 +992,12 @@ impl Application for Halloy {
                             &self.config,
                             &mut self.theme,
                         )
-                        .map(Message::Dashboard)
+                        .map(Message::Dashboard);
+
+                    // Queries are read again once the window is focused
+                    dashboard.send_read_receipts(&mut self.clients, &self.config);
+
+                    task
                 } else if let event::Event::CloseRequested = event {
                     window::close(window::Id::MAIN)
                 } else {
@@ -621,9 +1006,17 @@ impl Application for Halloy {
             }
             Message::Tick(now) => {
                 self.clients.tick(now);
+                self.notifications.tick(&self.config.notifications, now);
 
                 if let Screen::Dashboard(dashboard) = &mut self.screen {
-                    dashboard.tick(now).map(Message::Dashboard)
+                    // Ban lists are only requested for channels which are being looked at
+                    for buffer in dashboard.open_buffers() {
+                        if let data::Buffer::Channel(server, channel) = buffer {
+                            self.clients.request_ban_list(&server, &channel);
+                        }
+                    }
+
+                    dashboard.tick(now, &self.config).map(Message::Dashboard)
                 } else {
                     Task::none()
                 }
@@ -648,15 +1041,62 @@ impl Application for Halloy {
                                 let existing_entry = self.servers.entries().find(|entry| {
                                     entry.server == server || entry.config.server == config.server
                                 });
+                                let confirm_join = self.config.confirm_join(config.channels.len());
 
                                 // If server already exists, we only want to join the new channels
                                 if let Some(entry) = existing_entry {
-                                    self.clients.join(&entry.server, &config.channels);
+                                    if confirm_join {
+                                        self.modal = Some(Modal::ConfirmJoin(ConfirmJoin::new(
+                                            entry.server,
+                                            config.channels,
+                                            None,
+                                        )));
+                                    } else {
+                                        self.clients.join(&entry.server, &config.channels);
+                                    }
+                                } else if confirm_join {
+                                    self.modal = Some(Modal::ConfirmJoin(ConfirmJoin::new(
+                                        server,
+                                        config.channels.clone(),
+                                        Some(config),
+                                    )));
                                 } else {
                                     self.servers.insert(server, config);
                                 }
                             }
                         }
+                        modal::Event::AcceptJoin => {
+                            if let Some(Modal::ConfirmJoin(confirm)) = self.modal.take() {
+                                let channels = confirm.selected();
+
+                                if let Some(mut config) = confirm.new_server {
+                                    config.channels = channels;
+                                    self.servers.insert(confirm.server, config);
+                                } else {
+                                    self.clients.join(&confirm.server, &channels);
+                                }
+                            }
+                        }
+                        modal::Event::AcceptRedirect => {
+                            if let Some(Modal::ConfirmRedirect(server, host, port, _)) =
+                                self.modal.take()
+                            {
+                                self.servers.redirect(&server, host, port);
+                            }
+                        }
+                        modal::Event::AcceptLeave => {
+                            if let (
+                                Some(Modal::ConfirmLeave(buffer)),
+                                Screen::Dashboard(dashboard),
+                            ) = (self.modal.take(), &mut self.screen)
+                            {
+                                // Only channels and queries are confirmed, which don't emit events
+                                let (task, _) =
+                                    dashboard.leave_buffer(&mut self.clients, buffer, &self.config);
+
+                                return task.map(Message::Dashboard);
+                            }
+                        }
                     }
                 }
 
@@ -681,6 +1121,13 @@ impl Application for Halloy {
                     log::error!("window settings failed to save: {:?}", err)
                 }
 
+                Task::none()
+            }
+            Message::ChannelListSaved(result) => {
+                if let Err(err) = result {
+                    log::error!("channel list failed to save: {:?}", err)
+                }
+
                 Task::none()
             }
         }
@@ -723,11 +1170,13 @@ impl Application for Halloy {
     fn subscription(&self) -> Subscription<Message> {
         let tick = iced::time::every(Duration::from_secs(1)).map(Message::Tick);
 
-        let streams = Subscription::batch(
-            self.servers
-                .entries()
-                .map(|entry| stream::run(entry, self.config.proxy.clone())),
-        )
+        let streams = Subscription::batch(self.servers.entries().map(|entry| {
+            stream::run(
+                entry,
+                self.config.proxy.clone(),
+                self.clients.staged_nicks(),
+            )
+        }))
         .map(Message::Stream);
 
         Subscription::batch(vec![
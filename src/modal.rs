@@ -1,6 +1,11 @@
 use crate::widget::Element;
-use data::{config, Url};
+use data::{config, Server, Url};
 
+use self::confirm_join::ConfirmJoin;
+
+pub mod confirm_join;
+pub mod confirm_leave;
+pub mod confirm_redirect;
 pub mod connect_to_server;
 pub mod reload_configuration_error;
 
@@ -8,6 +13,9 @@ pub mod reload_configuration_error;
 pub enum Modal {
     ReloadConfigurationError(config::Error),
     RouteReceived(Url),
+    ConfirmJoin(ConfirmJoin),
+    ConfirmLeave(data::Buffer),
+    ConfirmRedirect(Server, String, u16, String),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -15,11 +23,18 @@ pub enum Message {
     Cancel,
     AcceptNewServer,
     DangerouslyAcceptInvalidCerts(bool),
+    ToggleJoinChannel(usize, bool),
+    AcceptJoin,
+    AcceptLeave,
+    AcceptRedirect,
 }
 
 pub enum Event {
     CloseModal,
     AcceptNewServer,
+    AcceptJoin,
+    AcceptLeave,
+    AcceptRedirect,
 }
 
 impl Modal {
@@ -34,6 +49,16 @@ impl Modal {
 
                 None
             }
+            Message::ToggleJoinChannel(index, selected) => {
+                if let Modal::ConfirmJoin(confirm) = self {
+                    confirm.toggle(index, selected);
+                }
+
+                None
+            }
+            Message::AcceptJoin => Some(Event::AcceptJoin),
+            Message::AcceptLeave => Some(Event::AcceptLeave),
+            Message::AcceptRedirect => Some(Event::AcceptRedirect),
         }
     }
 
@@ -45,6 +70,11 @@ impl Modal {
                     url: raw, config, ..
                 } => connect_to_server::view(raw, config),
             },
+            Modal::ConfirmJoin(confirm) => confirm_join::view(confirm),
+            Modal::ConfirmLeave(buffer) => confirm_leave::view(buffer),
+            Modal::ConfirmRedirect(server, host, port, info) => {
+                confirm_redirect::view(server, host, *port, info)
+            }
         }
     }
 }
@@ -0,0 +1,95 @@
+use data::{config, Server};
+use iced::{
+    alignment,
+    widget::{button, checkbox, column, container, scrollable, text},
+    Length,
+};
+
+use super::Message;
+use crate::{theme, widget::Element};
+
+#[derive(Debug)]
+pub struct ConfirmJoin {
+    pub server: Server,
+    /// Set when the channels are joined as part of adding a new server
+    pub new_server: Option<config::Server>,
+    channels: Vec<(String, bool)>,
+}
+
+impl ConfirmJoin {
+    pub fn new(server: Server, channels: Vec<String>, new_server: Option<config::Server>) -> Self {
+        Self {
+            server,
+            new_server,
+            channels: channels
+                .into_iter()
+                .map(|channel| (channel, true))
+                .collect(),
+        }
+    }
+
+    pub fn toggle(&mut self, index: usize, selected: bool) {
+        if let Some((_, is_selected)) = self.channels.get_mut(index) {
+            *is_selected = selected;
+        }
+    }
+
+    pub fn selected(&self) -> Vec<String> {
+        self.channels
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .map(|(channel, _)| channel.clone())
+            .collect()
+    }
+}
+
+pub fn view(confirm: &ConfirmJoin) -> Element<Message> {
+    let channels = column(confirm.channels.iter().enumerate().map(
+        |(index, (channel, selected))| {
+            checkbox(channel.as_str(), *selected)
+                .on_toggle(move |selected| Message::ToggleJoinChannel(index, selected))
+                .into()
+        },
+    ))
+    .spacing(4);
+
+    let selected = confirm.selected().len();
+
+    container(
+        column![
+            text(format!(
+                "Join {} channels on {}?",
+                confirm.channels.len(),
+                confirm.server
+            )),
+            container(scrollable(channels)).max_height(200),
+            column![
+                button(
+                    container(text(format!("Join {selected} selected")))
+                        .align_x(alignment::Horizontal::Center)
+                        .width(Length::Fill),
+                )
+                .padding(5)
+                .width(Length::Fixed(250.0))
+                .style(theme::button::primary)
+                .on_press_maybe((selected > 0).then_some(Message::AcceptJoin)),
+                button(
+                    container(text("Cancel"))
+                        .align_x(alignment::Horizontal::Center)
+                        .width(Length::Fill),
+                )
+                .padding(5)
+                .width(Length::Fixed(250.0))
+                .style(theme::button::primary)
+                .on_press(Message::Cancel),
+            ]
+            .spacing(4),
+        ]
+        .spacing(20)
+        .align_items(iced::Alignment::Center),
+    )
+    .width(Length::Shrink)
+    .style(theme::container::default_banner)
+    .padding(25)
+    .into()
+}
@@ -0,0 +1,49 @@
+use iced::{
+    alignment,
+    widget::{button, column, container, text},
+    Length,
+};
+
+use super::Message;
+use crate::{theme, widget::Element};
+
+pub fn view<'a>(buffer: &data::Buffer) -> Element<'a, Message> {
+    let prompt = match buffer {
+        data::Buffer::Server(server) => format!("Disconnect from {server}?"),
+        data::Buffer::Channel(server, channel) => format!("Leave {channel} on {server}?"),
+        data::Buffer::Query(server, nick) => format!("Close query with {nick} on {server}?"),
+    };
+
+    container(
+        column![
+            text(prompt),
+            column![
+                button(
+                    container(text("Leave"))
+                        .align_x(alignment::Horizontal::Center)
+                        .width(Length::Fill),
+                )
+                .padding(5)
+                .width(Length::Fixed(250.0))
+                .style(theme::button::primary)
+                .on_press(Message::AcceptLeave),
+                button(
+                    container(text("Cancel"))
+                        .align_x(alignment::Horizontal::Center)
+                        .width(Length::Fill),
+                )
+                .padding(5)
+                .width(Length::Fixed(250.0))
+                .style(theme::button::primary)
+                .on_press(Message::Cancel),
+            ]
+            .spacing(4),
+        ]
+        .spacing(20)
+        .align_items(iced::Alignment::Center),
+    )
+    .width(Length::Shrink)
+    .style(theme::container::default_banner)
+    .padding(25)
+    .into()
+}
@@ -0,0 +1,53 @@
+use data::Server;
+use iced::{
+    alignment,
+    widget::{button, column, container, text},
+    Length,
+};
+
+use super::Message;
+use crate::{theme, widget::Element};
+
+pub fn view<'a>(server: &Server, host: &str, port: u16, info: &str) -> Element<'a, Message> {
+    // The reason given by the server, e.g. that it's full
+    let info = (!info.is_empty()).then(|| text(info.to_string()).style(theme::text::transparent));
+
+    container(
+        column![
+            column![
+                text(format!("{server} is redirecting to another server")),
+                text(format!("{host}:{port}")).style(theme::text::info),
+            ]
+            .push_maybe(info)
+            .spacing(8)
+            .align_items(iced::Alignment::Center),
+            column![
+                button(
+                    container(text("Reconnect"))
+                        .align_x(alignment::Horizontal::Center)
+                        .width(Length::Fill),
+                )
+                .padding(5)
+                .width(Length::Fixed(250.0))
+                .style(theme::button::primary)
+                .on_press(Message::AcceptRedirect),
+                button(
+                    container(text("Cancel"))
+                        .align_x(alignment::Horizontal::Center)
+                        .width(Length::Fill),
+                )
+                .padding(5)
+                .width(Length::Fixed(250.0))
+                .style(theme::button::primary)
+                .on_press(Message::Cancel),
+            ]
+            .spacing(4),
+        ]
+        .spacing(20)
+        .align_items(iced::Alignment::Center),
+    )
+    .width(Length::Shrink)
+    .style(theme::container::default_banner)
+    .padding(25)
+    .into()
+}
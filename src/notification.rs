@@ -1,3 +1,227 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use data::config;
+use data::{Server, User};
+
+/// Shows highlight notifications, combining them into a periodic digest while
+/// they arrive faster than configured, e.g. from a misbehaving bot
+#[derive(Debug, Default)]
+pub struct Notifications {
+    recent_highlights: VecDeque<Instant>,
+    digest: Option<Digest>,
+    /// Time of the last message in channels with activity notifications
+    last_activity: HashMap<(Server, String), DateTime<Utc>>,
+    connections: HashMap<Server, Connection>,
+}
+
+/// Disconnects of a server, to tell a flapping connection apart from genuine state changes
+#[derive(Debug, Default)]
+struct Connection {
+    recent_disconnects: VecDeque<Instant>,
+    unstable: bool,
+    /// When an unstable connection was last reestablished
+    reconnected_at: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct Digest {
+    since: Instant,
+    highlights: usize,
+}
+
+impl Notifications {
+    pub fn highlight(&mut self, config: &config::Notifications, user: &User, channel: &str) {
+        let notification = &config.highlight;
+
+        if !notification.enabled {
+            return;
+        }
+
+        let now = Instant::now();
+
+        self.recent_highlights.push_back(now);
+        self.forget_highlights(config, now);
+
+        if let Some(digest) = &mut self.digest {
+            digest.highlights += 1;
+        } else if self.recent_highlights.len() > config.highlight_flood.threshold {
+            show(
+                "Highlights",
+                "You are being highlighted rapidly, new highlights will be combined",
+                notification.sound(),
+            );
+
+            self.digest = Some(Digest {
+                since: now,
+                highlights: 0,
+            });
+        } else {
+            show(
+                "Highlight",
+                format!("{} highlighted you in {}", user.nickname(), channel),
+                notification.sound(),
+            );
+        }
+    }
+
+    /// Notifies when `user` speaks in `channel` after it has been quiet for a while
+    pub fn activity(
+        &mut self,
+        config: &config::Notifications,
+        server: &Server,
+        channel: &str,
+        user: &User,
+        server_time: DateTime<Utc>,
+    ) {
+        let activity = &config.activity_resumed;
+
+        if !activity.notification.enabled || !activity.includes(channel) {
+            return;
+        }
+
+        let quiet = chrono::Duration::seconds(activity.quiet as i64);
+        let last = self
+            .last_activity
+            .insert((server.clone(), channel.to_lowercase()), server_time);
+
+        // The first message seen only starts tracking, as it's unknown how long it was quiet
+        let resumed = last.is_some_and(|last| server_time - last >= quiet);
+        // History played back on join isn't new activity
+        let is_recent = Utc::now() - server_time < quiet;
+
+        if resumed && is_recent {
+            show(
+                "Activity",
+                format!("{} spoke in quiet {channel}", user.nickname()),
+                activity.notification.sound(),
+            );
+        }
+    }
+
+    pub fn disconnected(&mut self, config: &config::Notifications, server: &Server) {
+        let notification = &config.disconnected;
+        let unstable = &config.unstable_connection;
+        let window = Duration::from_secs(unstable.window);
+        let now = Instant::now();
+
+        let connection = self.connections.entry(server.clone()).or_default();
+
+        connection.reconnected_at = None;
+        connection.recent_disconnects.push_back(now);
+
+        while connection
+            .recent_disconnects
+            .front()
+            .is_some_and(|disconnect| now.duration_since(*disconnect) > window)
+        {
+            connection.recent_disconnects.pop_front();
+        }
+
+        if connection.unstable {
+            return;
+        }
+
+        // Tracked even with the notification off, so reconnects are held back all the same
+        if connection.recent_disconnects.len() >= unstable.threshold {
+            connection.unstable = true;
+
+            if notification.enabled {
+                show(
+                    "Unstable connection",
+                    format!("{server} keeps disconnecting, you'll be notified once it's stable"),
+                    notification.sound(),
+                );
+            }
+        } else if notification.enabled {
+            show("Disconnected", server, notification.sound());
+        }
+    }
+
+    pub fn reconnected(&mut self, config: &config::Notifications, server: &Server) {
+        let notification = &config.reconnected;
+
+        match self.connections.get_mut(server) {
+            // Notified by `tick` once it has stayed up
+            Some(connection) if connection.unstable => {
+                connection.reconnected_at = Some(Instant::now());
+            }
+            _ => {
+                if notification.enabled {
+                    show("Reconnected", server, notification.sound());
+                }
+            }
+        }
+    }
+
+    pub fn tick(&mut self, config: &config::Notifications, now: Instant) {
+        let stable = Duration::from_secs(config.unstable_connection.stable);
+
+        for (server, connection) in &mut self.connections {
+            let is_stable = connection
+                .reconnected_at
+                .is_some_and(|reconnected_at| now.duration_since(reconnected_at) >= stable);
+
+            if connection.unstable && is_stable {
+                *connection = Connection::default();
+
+                if config.reconnected.enabled {
+                    show(
+                        "Reconnected",
+                        format!("{server} is stable again"),
+                        config.reconnected.sound(),
+                    );
+                }
+            }
+        }
+
+        let window = Duration::from_secs(config.highlight_flood.window);
+
+        let Some(digest) = &self.digest else {
+            return;
+        };
+
+        if now.duration_since(digest.since) < window {
+            return;
+        }
+
+        match digest.highlights {
+            0 => {}
+            1 => show("Highlights", "1 new highlight", config.highlight.sound()),
+            n => show(
+                "Highlights",
+                format!("{n} new highlights"),
+                config.highlight.sound(),
+            ),
+        }
+
+        self.forget_highlights(config, now);
+
+        // Keep combining for as long as the flood goes on
+        if self.recent_highlights.len() > config.highlight_flood.threshold {
+            self.digest = Some(Digest {
+                since: now,
+                highlights: 0,
+            });
+        } else {
+            self.digest = None;
+        }
+    }
+
+    fn forget_highlights(&mut self, config: &config::Notifications, now: Instant) {
+        let window = Duration::from_secs(config.highlight_flood.window);
+
+        while self
+            .recent_highlights
+            .front()
+            .is_some_and(|highlight| now.duration_since(*highlight) > window)
+        {
+            self.recent_highlights.pop_front();
+        }
+    }
+}
+
 #[cfg(target_os = "macos")]
 pub fn prepare() {
     match notify_rust::set_application(data::environment::APPLICATION_ID) {
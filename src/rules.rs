@@ -0,0 +1,49 @@
+//! Runs the actions of rules which match events from servers
+
+use std::process;
+use std::thread;
+use std::time::Instant;
+
+use chrono::Utc;
+use data::rules::{Engine, Event};
+use data::Config;
+
+use crate::notification;
+use crate::screen::Dashboard;
+
+pub fn run(
+    engine: &mut Engine,
+    config: &Config,
+    clients: &mut data::client::Map,
+    dashboard: &mut Dashboard,
+    event: Event,
+) {
+    if !event.is_recent(Utc::now()) {
+        return;
+    }
+
+    for actions in engine.actions(&config.rules, &event, Instant::now()) {
+        // A sound is played with a notification
+        if actions.notify.is_some() || actions.sound.is_some() {
+            notification::show(
+                actions.title,
+                actions.notify.unwrap_or_else(|| event.describe()),
+                actions.sound.as_deref(),
+            );
+        }
+
+        if let Some((program, args)) = actions.run.split_first() {
+            match process::Command::new(program).args(args).spawn() {
+                // Waited on elsewhere so it doesn't linger once it exits
+                Ok(mut child) => {
+                    thread::spawn(move || child.wait());
+                }
+                Err(error) => log::warn!("rule failed to run {program}: {error}"),
+            }
+        }
+
+        if let Some((buffer, text)) = actions.send {
+            dashboard.send_text(clients, buffer, text);
+        }
+    }
+}
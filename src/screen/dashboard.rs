@@ -7,6 +7,8 @@ use data::environment::RELEASE_WEBSITE;
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
+use data::config::buffer::{CopyFormat, LeaveMode};
+use data::config::query::OpenIncoming;
 use data::file_transfer;
 use data::history::manager::Broadcast;
 use data::user::Nick;
@@ -33,13 +35,25 @@ pub struct Dashboard {
     last_changed: Option<Instant>,
     command_bar: Option<CommandBar>,
     file_transfers: file_transfer::Manager,
+    window_focused: bool,
+    last_interaction: Option<Instant>,
+    last_mention_focus: Option<Instant>,
+    last_left: Option<Left>,
+}
+
+/// A channel or query which was left, kept to allow undoing it
+struct Left {
+    buffer: data::Buffer,
+    pane: Option<Pane>,
+    key: Option<String>,
+    at: Instant,
 }
 
 #[derive(Debug)]
 pub enum Message {
     Pane(pane::Message),
     Sidebar(sidebar::Message),
-    SelectedText(Vec<(f32, String)>),
+    SelectedText(CopyFormat, Vec<selectable_text::Selected>),
     History(history::manager::Message),
     Close,
     DashboardSaved(Result<(), data::dashboard::Error>),
@@ -55,6 +69,8 @@ pub enum Message {
 pub enum Event {
     ReloadConfiguration,
     QuitServer(Server),
+    ConfirmJoin(Server, Vec<String>),
+    ConfirmLeave(data::Buffer),
 }
 
 impl Dashboard {
@@ -69,6 +85,10 @@ impl Dashboard {
             last_changed: None,
             command_bar: None,
             file_transfers: file_transfer::Manager::new(config.file_transfer.clone()),
+            window_focused: true,
+            last_interaction: None,
+            last_mention_focus: None,
+            last_left: None,
         };
 
         let command = dashboard.track();
@@ -119,6 +139,8 @@ impl Dashboard {
                     return (self.split_pane(axis, config), None);
                 }
                 pane::Message::Buffer(id, message) => {
+                    self.last_interaction = Some(Instant::now());
+
                     if let Some(pane) = self.panes.get_mut(id) {
                         let (command, event) = pane.buffer.update(
                             message,
@@ -128,7 +150,21 @@ impl Dashboard {
                             config,
                         );
 
-                        if let Some(buffer::Event::UserContext(event)) = event {
+                        if let Some(buffer::Event::JoinChannels(server, channels)) = event {
+                            let event = if config.confirm_join(channels.len()) {
+                                Some(Event::ConfirmJoin(server, channels))
+                            } else {
+                                clients.join(&server, &channels);
+                                None
+                            };
+
+                            return (
+                                command.map(move |message| {
+                                    Message::Pane(pane::Message::Buffer(id, message))
+                                }),
+                                event,
+                            );
+                        } else if let Some(buffer::Event::UserContext(event)) = event {
                             match event {
                                 buffer::user_context::Event::ToggleAccessLevel(nick, mode) => {
                                     let Some(buffer) = pane.buffer.data() else {
@@ -226,7 +262,53 @@ impl Dashboard {
                                         );
                                     }
                                 }
+                                buffer::user_context::Event::ToggleIgnore(nick) => {
+                                    if let Some(buffer) = pane.buffer.data() {
+                                        clients.toggle_ignore(buffer.server(), nick);
+                                    }
+                                }
+                                buffer::user_context::Event::EditNote(nick) => {
+                                    return (
+                                        pane.buffer
+                                            .edit_note_in_input(nick, &mut self.history)
+                                            .map(move |message| {
+                                                Message::Pane(pane::Message::Buffer(id, message))
+                                            }),
+                                        None,
+                                    );
+                                }
+                                buffer::user_context::Event::ToggleAway(away) => {
+                                    if let Some(buffer) = pane.buffer.data() {
+                                        let reason = if away {
+                                            vec!["Away".to_string()]
+                                        } else {
+                                            vec![]
+                                        };
+                                        let command =
+                                            data::Command::Unknown("AWAY".to_string(), reason);
+                                        let input = data::Input::command(buffer.clone(), command);
+
+                                        if let Some(encoded) = input.encoded() {
+                                            clients.send(input.buffer(), encoded);
+                                        }
+                                    }
+                                }
                             }
+                        } else if let Some(buffer::Event::Appearance(part, hex)) = event {
+                            pane.update_settings(|settings| settings.appearance.set(part, hex));
+                            self.last_changed = Some(Instant::now());
+                        } else if let Some(buffer::Event::NoteChanged) = event {
+                            self.last_changed = Some(Instant::now());
+                        } else if let Some(buffer::Event::JumpTo(buffer, server_time)) = event {
+                            return (
+                                Task::batch(vec![
+                                    command.map(move |message| {
+                                        Message::Pane(pane::Message::Buffer(id, message))
+                                    }),
+                                    self.jump_to(buffer, server_time, config),
+                                ]),
+                                None,
+                            );
                         }
 
                         return (
@@ -251,10 +333,28 @@ impl Dashboard {
                         self.last_changed = Some(Instant::now());
                     }
                 }
+                pane::Message::ToggleShowThreads => {
+                    if let Some((_, pane)) = self.get_focused_mut() {
+                        pane.update_settings(|settings| {
+                            settings.channel.threads.toggle_visibility()
+                        });
+                        if let Buffer::Channel(channel) = &mut pane.buffer {
+                            channel.close_thread();
+                        }
+                        self.last_changed = Some(Instant::now());
+                    }
+                }
                 pane::Message::MaximizePane => self.maximize_pane(),
+                pane::Message::TitleHovered(id, hovered) => {
+                    if let Some(pane) = self.panes.get_mut(id) {
+                        pane.set_title_hovered(hovered);
+                    }
+                }
             },
             Message::Sidebar(message) => {
-                let event = self.side_menu.update(message);
+                let Some(event) = self.side_menu.update(message) else {
+                    return (Task::none(), None);
+                };
 
                 match event {
                     sidebar::Event::Open(kind) => {
@@ -285,7 +385,23 @@ impl Dashboard {
                         return (self.focus_pane(from), None);
                     }
                     sidebar::Event::Leave(buffer) => {
-                        return self.leave_buffer(clients, buffer);
+                        return self.request_leave(clients, buffer, config);
+                    }
+                    sidebar::Event::ToggleReadOnly(pane) => {
+                        if let Some(state) = self.panes.get_mut(pane) {
+                            state.update_settings(|settings| {
+                                settings.read_only = !settings.read_only;
+                            });
+                            self.last_changed = Some(Instant::now());
+                        }
+                    }
+                    sidebar::Event::ToggleBotCommands(pane) => {
+                        if let Some(state) = self.panes.get_mut(pane) {
+                            state.update_settings(|settings| {
+                                settings.channel.bot_commands.toggle();
+                            });
+                            self.last_changed = Some(Instant::now());
+                        }
                     }
                     sidebar::Event::ToggleFileTransfers => {
                         return (self.toggle_file_transfers(config), None);
@@ -303,22 +419,8 @@ impl Dashboard {
                     }
                 }
             }
-            Message::SelectedText(contents) => {
-                let mut last_y = None;
-                let contents = contents
-                    .into_iter()
-                    .fold(String::new(), |acc, (y, content)| {
-                        if let Some(_y) = last_y {
-                            let new_line = if y == _y { "" } else { "\n" };
-                            last_y = Some(y);
-
-                            format!("{acc}{new_line}{content}")
-                        } else {
-                            last_y = Some(y);
-
-                            content
-                        }
-                    });
+            Message::SelectedText(format, contents) => {
+                let contents = copied_text(format, contents);
 
                 if !contents.is_empty() {
                     return (clipboard::write(contents), None);
@@ -409,6 +511,17 @@ impl Dashboard {
                                     self.side_menu.toggle_visibility();
                                     (Task::none(), None)
                                 }
+                                command_bar::Ui::ToggleCompactServerBuffer => {
+                                    if let Some((_, pane)) = self.get_focused_mut() {
+                                        if matches!(pane.buffer, Buffer::Server(_)) {
+                                            pane.update_settings(|settings| {
+                                                settings.compact = !settings.compact
+                                            });
+                                            self.last_changed = Some(Instant::now());
+                                        }
+                                    }
+                                    (Task::none(), None)
+                                }
                             },
                             command_bar::Command::Theme(command) => match command {
                                 command_bar::Theme::Switch(new) => {
@@ -513,10 +626,13 @@ impl Dashboard {
                     LeaveBuffer => {
                         if let Some((_, state)) = self.get_focused_mut() {
                             if let Some(buffer) = state.buffer.data() {
-                                return self.leave_buffer(clients, buffer);
+                                return self.request_leave(clients, buffer, config);
                             }
                         }
                     }
+                    UndoLeaveBuffer => {
+                        return (self.undo_leave(clients, config), None);
+                    }
                     ToggleNicklist => {
                         if let Some((_, pane)) = self.get_focused_mut() {
                             pane.update_settings(|settings| {
@@ -618,7 +734,7 @@ impl Dashboard {
                 &self.history,
                 &self.panes,
                 self.focus,
-                config.sidebar,
+                &config.sidebar,
                 config.tooltips,
                 &self.file_transfers,
             )
@@ -697,7 +813,16 @@ impl Dashboard {
                     context_menu::close(Message::CloseContextMenu)
                 }
             }
-            Copy => selectable_text::selected(Message::SelectedText),
+            Copy { shift } => {
+                let format = if shift {
+                    config.buffer.copy.shift_format
+                } else {
+                    config.buffer.copy.format
+                };
+
+                selectable_text::selected(std::convert::identity)
+                    .map(move |contents| Message::SelectedText(format, contents))
+            }
             Home => self
                 .get_focused_mut()
                 .map(|(id, pane)| {
@@ -715,15 +840,24 @@ impl Dashboard {
                         .map(move |message| Message::Pane(pane::Message::Buffer(pane, message)))
                 })
                 .unwrap_or_else(Task::none),
+            Focused => {
+                self.window_focused = true;
+                Task::none()
+            }
+            Unfocused => {
+                self.window_focused = false;
+                Task::none()
+            }
             CloseRequested => {
+                // Saved before history is closed, which takes the drafts with it
+                let save = self.last_changed.is_some() || config.buffer.text_input.persist_drafts;
+                let dashboard = self.saved(config);
                 let history = self.history.close_all();
-                let last_changed = self.last_changed;
-                let dashboard = data::Dashboard::from(&*self);
 
                 let task = async move {
                     history.await;
 
-                    if last_changed.is_some() {
+                    if save {
                         match dashboard.save().await {
                             Ok(_) => {
                                 log::info!("dashboard saved");
@@ -781,6 +915,42 @@ impl Dashboard {
     }
 
     fn open_buffer(&mut self, kind: data::Buffer, config: &Config) -> Task<Message> {
+        self.open_pane(kind, config, true)
+    }
+
+    /// Opens `buffer` at its message sent at `server_time`, or at its end when the message
+    /// isn't in history
+    fn jump_to(
+        &mut self,
+        buffer: data::Buffer,
+        server_time: DateTime<Utc>,
+        config: &Config,
+    ) -> Task<Message> {
+        let received_at = self.history.received_at(&buffer, server_time);
+        let open = self.open_buffer(buffer.clone(), config);
+
+        let Some(received_at) = received_at else {
+            return open;
+        };
+
+        let Some((id, pane)) = self
+            .panes
+            .iter_mut()
+            .find(|(_, pane)| pane.buffer.data().as_ref() == Some(&buffer))
+        else {
+            return open;
+        };
+        let id = *id;
+
+        Task::batch(vec![
+            open,
+            pane.buffer
+                .scroll_to_message(received_at)
+                .map(move |message| Message::Pane(pane::Message::Buffer(id, message))),
+        ])
+    }
+
+    fn open_pane(&mut self, kind: data::Buffer, config: &Config, focus: bool) -> Task<Message> {
         let panes = self.panes.clone();
 
         // If channel already is open, we focus it.
@@ -802,7 +972,11 @@ impl Dashboard {
                         .and_modify(|p| *p = Pane::new(Buffer::from(kind), config));
                     self.last_changed = Some(Instant::now());
 
-                    return self.focus_pane(*id);
+                    return if focus {
+                        self.focus_pane(*id)
+                    } else {
+                        Task::none()
+                    };
                 }
             }
         }
@@ -826,21 +1000,100 @@ impl Dashboard {
         self.last_changed = Some(Instant::now());
 
         if let Some((pane, _)) = result {
-            return self.focus_pane(pane);
+            if focus {
+                return self.focus_pane(pane);
+            }
         }
 
         Task::none()
     }
 
+    /// Opens a query someone else started, as configured by `buffer.query.open_incoming`
+    /// and `buffer.query.open_from`
+    pub fn open_incoming_query(
+        &mut self,
+        clients: &data::client::Map,
+        server: &Server,
+        message: &data::Message,
+        config: &Config,
+    ) -> Task<Message> {
+        let data::message::Target::Query { nick, .. } = &message.target else {
+            return Task::none();
+        };
+
+        let is_new = matches!(message.direction, data::message::Direction::Received)
+            && !self.history.get_unique_queries(server).contains(&nick);
+
+        if !is_new {
+            return Task::none();
+        }
+
+        let query = &config.buffer.query;
+
+        let is_contact = query.is_contact(message.account.as_ref());
+
+        let open_incoming = if query.is_service(nick.as_ref()) {
+            query.services.open_incoming.unwrap_or(query.open_incoming)
+        } else if query.open_from.allows(is_contact, || {
+            !clients
+                .get_user_channels(server, nick.as_ref().into())
+                .is_empty()
+        }) {
+            query.open_incoming
+        } else {
+            OpenIncoming::Sidebar
+        };
+
+        let buffer = data::Buffer::Query(server.clone(), nick.clone());
+
+        match open_incoming {
+            OpenIncoming::Sidebar => Task::none(),
+            OpenIncoming::Background => self.open_pane(buffer, config, false),
+            OpenIncoming::Focus => self.open_pane(buffer, config, true),
+        }
+    }
+
+    /// Leaves `buffer`, after confirmation if configured
+    fn request_leave(
+        &mut self,
+        clients: &mut data::client::Map,
+        buffer: data::Buffer,
+        config: &Config,
+    ) -> (Task<Message>, Option<Event>) {
+        let confirm = matches!(config.buffer.leave.mode, LeaveMode::Confirm)
+            && !matches!(buffer, data::Buffer::Server(_));
+
+        if confirm {
+            (Task::none(), Some(Event::ConfirmLeave(buffer)))
+        } else {
+            self.leave_buffer(clients, buffer, config)
+        }
+    }
+
     pub fn leave_buffer(
         &mut self,
         clients: &mut data::client::Map,
         buffer: data::Buffer,
+        config: &Config,
     ) -> (Task<Message>, Option<Event>) {
         let pane = self.panes.iter().find_map(|(pane, state)| {
             (state.buffer.data().as_ref() == Some(&buffer)).then_some(*pane)
         });
 
+        if matches!(config.buffer.leave.mode, LeaveMode::Undo) {
+            let key = match &buffer {
+                data::Buffer::Channel(server, channel) => clients.get_channel_key(server, channel),
+                _ => None,
+            };
+
+            self.last_left = (!matches!(buffer, data::Buffer::Server(_))).then(|| Left {
+                buffer: buffer.clone(),
+                pane: pane.and_then(|pane| self.panes.get(pane)).cloned(),
+                key,
+                at: Instant::now(),
+            });
+        }
+
         // Close pane
         if let Some(pane) = pane {
             if self.panes.close(pane).is_none() {
@@ -887,10 +1140,143 @@ impl Dashboard {
         }
     }
 
+    /// Rejoins or reopens the last left buffer, if it was left recently enough
+    fn undo_leave(&mut self, clients: &mut data::client::Map, config: &Config) -> Task<Message> {
+        let window = Duration::from_secs(config.buffer.leave.undo_window);
+
+        let Some(left) = self
+            .last_left
+            .take()
+            .filter(|left| left.at.elapsed() <= window)
+        else {
+            return Task::none();
+        };
+
+        if let data::Buffer::Channel(server, channel) = &left.buffer {
+            clients.rejoin(server, channel, left.key);
+        }
+
+        let task = self.open_pane(left.buffer.clone(), config, true);
+
+        // Restore the pane as it was, including its scroll position
+        if let Some(pane) = left.pane {
+            if let Some((_, state)) = self
+                .panes
+                .iter_mut()
+                .find(|(_, state)| state.buffer.data().as_ref() == Some(&left.buffer))
+            {
+                *state = pane;
+            }
+        }
+
+        task
+    }
+
+    /// Focus the pane showing `buffer` after a mention, if the user is
+    /// idle in a focused window and the cooldown has elapsed.
+    pub fn focus_mention(&mut self, buffer: &data::Buffer, config: &Config) -> Task<Message> {
+        let policy = &config.buffer.focus_follows_mention;
+
+        if !policy.enabled || !self.window_focused {
+            return Task::none();
+        }
+
+        let now = Instant::now();
+        let elapsed = |instant: Option<Instant>, seconds| {
+            instant
+                .map(|instant| now.duration_since(instant) >= Duration::from_secs(seconds))
+                .unwrap_or(true)
+        };
+
+        if !elapsed(self.last_interaction, policy.idle)
+            || !elapsed(self.last_mention_focus, policy.cooldown)
+        {
+            return Task::none();
+        }
+
+        let Some(pane) = self.panes.iter().find_map(|(pane, state)| {
+            (state.buffer.data().as_ref() == Some(buffer)).then_some(*pane)
+        }) else {
+            return Task::none();
+        };
+
+        if self.focus == Some(pane) {
+            return Task::none();
+        }
+
+        self.last_mention_focus = Some(now);
+
+        self.focus_pane(pane)
+    }
+
     pub fn record_message(&mut self, server: &Server, message: data::Message) {
         self.history.record_message(server, message);
     }
 
+    pub fn record_highlight(&mut self, server: &Server, message: data::Message) {
+        self.history.record_highlight(server, message);
+    }
+
+    pub fn record_search_results(&mut self, buffer: &data::Buffer, results: Vec<data::Message>) {
+        let results = data::Message::search_results(buffer.clone(), results);
+
+        for (_, pane) in self.panes.iter_mut() {
+            if pane.buffer.data().as_ref() == Some(buffer) {
+                pane.buffer.show_search_results(results.clone());
+            }
+        }
+    }
+
+    pub fn open_buffers(&self) -> Vec<data::Buffer> {
+        open_buffers(self)
+    }
+
+    /// Whether `buffer` is open in a pane of the focused window
+    pub fn is_visible(&self, buffer: &data::Buffer) -> bool {
+        self.window_focused && open_buffers(self).contains(buffer)
+    }
+
+    /// Sends the read receipts held back for queries which are now visible
+    pub fn send_read_receipts(&self, clients: &mut data::client::Map, config: &Config) {
+        if !config.buffer.query.read_receipts.send || !self.window_focused {
+            return;
+        }
+
+        for buffer in open_buffers(self) {
+            if let data::Buffer::Query(server, nick) = &buffer {
+                clients.send_pending_read_receipt(server, nick);
+            }
+        }
+    }
+
+    /// Puts a message the server refused back into the input, unless something
+    /// new has been typed since
+    pub fn restore_unsent(&mut self, buffer: data::Buffer, text: String) {
+        if self.history.input(&buffer).draft.is_empty() {
+            self.history.record_draft(data::input::Draft { buffer, text });
+        }
+    }
+
+    /// Sends `text` to `buffer`, as if it was typed into it
+    pub fn send_text(
+        &mut self,
+        clients: &mut data::client::Map,
+        buffer: data::Buffer,
+        text: String,
+    ) {
+        let Some(target) = buffer.target() else {
+            return;
+        };
+
+        let input = data::Input::command(buffer, data::Command::Msg(target, text));
+
+        if let Some(encoded) = input.encoded() {
+            clients.send(input.buffer(), encoded);
+        }
+
+        buffer::record_input(input, clients, &mut self.history);
+    }
+
     pub fn broadcast_quit(
         &mut self,
         server: &Server,
@@ -956,6 +1342,33 @@ impl Dashboard {
         );
     }
 
+    pub fn broadcast_change_host(
+        &mut self,
+        server: &Server,
+        old_user: User,
+        new_username: String,
+        new_hostname: String,
+        ourself: bool,
+        logged_in: bool,
+        user_channels: Vec<String>,
+        config: &Config,
+        sent_time: DateTime<Utc>,
+    ) {
+        self.history.broadcast(
+            server,
+            Broadcast::ChangeHost {
+                old_user,
+                new_username,
+                new_hostname,
+                ourself,
+                logged_in,
+                user_channels,
+            },
+            config,
+            sent_time,
+        );
+    }
+
     pub fn broadcast_connecting(
         &mut self,
         server: &Server,
@@ -1012,6 +1425,38 @@ impl Dashboard {
         );
     }
 
+    pub fn broadcast_sasl_authenticated(
+        &mut self,
+        server: &Server,
+        mechanism: &'static str,
+        account: Option<String>,
+        config: &Config,
+        sent_time: DateTime<Utc>,
+    ) {
+        self.history.broadcast(
+            server,
+            Broadcast::SaslAuthenticated { mechanism, account },
+            config,
+            sent_time,
+        );
+    }
+
+    pub fn broadcast_sasl_failed(
+        &mut self,
+        server: &Server,
+        mechanism: &'static str,
+        reason: String,
+        config: &Config,
+        sent_time: DateTime<Utc>,
+    ) {
+        self.history.broadcast(
+            server,
+            Broadcast::SaslFailed { mechanism, reason },
+            config,
+            sent_time,
+        );
+    }
+
     fn get_focused_mut(&mut self) -> Option<(pane_grid::Pane, &mut Pane)> {
         let pane = self.focus?;
         self.panes.get_mut(pane).map(|state| (pane, state))
@@ -1142,7 +1587,7 @@ impl Dashboard {
         )
     }
 
-    pub fn tick(&mut self, now: Instant) -> Task<Message> {
+    pub fn tick(&mut self, now: Instant, config: &Config) -> Task<Message> {
         let history = Task::batch(
             self.history
                 .tick(now.into())
@@ -1153,7 +1598,7 @@ impl Dashboard {
 
         if let Some(last_changed) = self.last_changed {
             if now.duration_since(last_changed) >= SAVE_AFTER {
-                let dashboard = data::Dashboard::from(&*self);
+                let dashboard = self.saved(config);
 
                 self.last_changed = None;
 
@@ -1266,6 +1711,17 @@ impl Dashboard {
         }
     }
 
+    /// State to save, along with drafts if they're kept across restarts
+    fn saved(&self, config: &Config) -> data::Dashboard {
+        let mut dashboard = data::Dashboard::from(self);
+
+        if config.buffer.text_input.persist_drafts {
+            dashboard.drafts = self.history.persistable_drafts();
+        }
+
+        dashboard
+    }
+
     fn from_data(dashboard: data::Dashboard, config: &Config) -> Self {
         use pane_grid::Configuration;
 
@@ -1294,14 +1750,28 @@ impl Dashboard {
             }
         }
 
+        let mut history = history::Manager::default();
+
+        history.restore_notes(dashboard.notes);
+
+        if config.buffer.text_input.persist_drafts {
+            for draft in dashboard.drafts {
+                history.record_draft(draft);
+            }
+        }
+
         Self {
             panes: pane_grid::State::with_configuration(configuration(dashboard.pane)),
             focus: None,
             side_menu: Sidebar::new(),
-            history: history::Manager::default(),
+            history,
             last_changed: None,
             command_bar: None,
             file_transfers: file_transfer::Manager::new(config.file_transfer.clone()),
+            window_focused: true,
+            last_interaction: None,
+            last_mention_focus: None,
+            last_left: None,
         }
     }
 }
@@ -1334,7 +1804,9 @@ impl<'a> From<&'a Dashboard> for data::Dashboard {
         let layout = dashboard.panes.layout().clone();
 
         data::Dashboard {
-            pane: from_layout(&dashboard.panes, layout)
+            pane: from_layout(&dashboard.panes, layout),
+            drafts: vec![],
+            notes: dashboard.history.notes().clone(),
         }
     }
 }
@@ -1360,6 +1832,66 @@ fn all_buffers(clients: &client::Map, history: &history::Manager) -> Vec<data::B
         .collect()
 }
 
+/// Joins the fragments of selected text into lines, adding the timestamps and
+/// nicknames of their messages as `format` asks for
+fn copied_text(format: CopyFormat, contents: Vec<selectable_text::Selected>) -> String {
+    use selectable_text::Part;
+
+    // Fragments on the same line of a buffer share their y position
+    let mut rows: Vec<Vec<selectable_text::Selected>> = vec![];
+    for selected in contents {
+        match rows.last_mut() {
+            Some(row) if row[0].y == selected.y => row.push(selected),
+            _ => rows.push(vec![selected]),
+        }
+    }
+
+    let lines = rows
+        .into_iter()
+        .filter_map(|row| {
+            if row.iter().all(|fragment| fragment.selected.is_none()) {
+                return None;
+            }
+
+            let part = |part: Part| {
+                row.iter()
+                    .filter(|fragment| fragment.part == part)
+                    .map(|fragment| fragment.text.as_str())
+                    .collect::<String>()
+            };
+            let text = row
+                .iter()
+                .filter(|fragment| fragment.part == Part::Text)
+                .filter_map(|fragment| fragment.selected.as_deref())
+                .collect::<String>();
+            let text = text.trim();
+
+            let line = match format {
+                CopyFormat::Selection => row
+                    .iter()
+                    .filter_map(|fragment| fragment.selected.as_deref())
+                    .collect(),
+                CopyFormat::Text => text.to_string(),
+                CopyFormat::Nicknames => format!("{} {text}", part(Part::Nickname)),
+                CopyFormat::Full | CopyFormat::Markdown => {
+                    format!("{}{} {text}", part(Part::Timestamp), part(Part::Nickname))
+                }
+            };
+
+            Some(line.trim().to_string())
+        })
+        .collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        return String::new();
+    }
+
+    match format {
+        CopyFormat::Markdown => format!("```\n{}\n```", lines.join("\n")),
+        _ => lines.join("\n"),
+    }
+}
+
 fn open_buffers(dashboard: &Dashboard) -> Vec<data::Buffer> {
     dashboard
         .panes
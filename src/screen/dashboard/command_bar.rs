@@ -137,6 +137,7 @@ pub enum Configuration {
 #[derive(Debug, Clone)]
 pub enum Ui {
     ToggleSidebarVisibility,
+    ToggleCompactServerBuffer,
 }
 
 #[derive(Debug, Clone)]
@@ -229,7 +230,7 @@ impl Configuration {
 
 impl Ui {
     fn list() -> Vec<Self> {
-        vec![Ui::ToggleSidebarVisibility]
+        vec![Ui::ToggleSidebarVisibility, Ui::ToggleCompactServerBuffer]
     }
 }
 
@@ -304,6 +305,7 @@ impl std::fmt::Display for Ui {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Ui::ToggleSidebarVisibility => write!(f, "Toggle sidebar visibility"),
+            Ui::ToggleCompactServerBuffer => write!(f, "Toggle compact server buffer"),
         }
     }
 }
@@ -1,5 +1,6 @@
 use data::{file_transfer, history, Config};
-use iced::widget::{button, center, container, pane_grid, row, text};
+use iced::widget::{button, center, container, mouse_area, pane_grid, row, text, Themer};
+use iced::Length;
 use uuid::Uuid;
 
 use crate::buffer::{self, Buffer};
@@ -17,6 +18,8 @@ pub enum Message {
     MaximizePane,
     ToggleShowUserList,
     ToggleShowTopic,
+    ToggleShowThreads,
+    TitleHovered(pane_grid::Pane, bool),
 }
 
 #[derive(Clone)]
@@ -28,7 +31,9 @@ pub struct Pane {
 }
 
 #[derive(Debug, Clone, Default)]
-pub struct TitleBar {}
+pub struct TitleBar {
+    hovered: bool,
+}
 
 impl Pane {
     pub fn new(buffer: Buffer, config: &Config) -> Self {
@@ -64,7 +69,17 @@ impl Pane {
                     .get_channel_users(&state.server, &state.channel)
                     .len();
 
-                format!("{channel} @ {server} - {users} users")
+                let modes = clients
+                    .get_channel_modes(&state.server, &state.channel)
+                    .map(|modes| format!(" ({modes})"))
+                    .unwrap_or_default();
+
+                let bans = clients
+                    .get_channel_bans(&state.server, &state.channel)
+                    .map(|bans| format!(", {} bans", bans.len()))
+                    .unwrap_or_default();
+
+                format!("{channel}{modes} @ {server} - {users} users{bans}")
             }
             Buffer::Server(state) => state.server.to_string(),
             Buffer::Query(state) => {
@@ -76,9 +91,23 @@ impl Pane {
             Buffer::FileTransfers(_) => "File Transfers".to_string(),
         };
 
+        // Computed from history only while the title is hovered, as that's when they're shown
+        let stats = match &self.buffer {
+            Buffer::Channel(state) if config.tooltips => Some(if self.title_bar.hovered {
+                stats_text(
+                    history.get_channel_stats(&state.server, &state.channel),
+                    clients.get_channel_bans(&state.server, &state.channel),
+                )
+            } else {
+                String::new()
+            }),
+            _ => None,
+        };
+
         let title_bar = self.title_bar.view(
             &self.buffer,
             title_bar_text,
+            stats,
             id,
             panes,
             is_focused,
@@ -100,6 +129,22 @@ impl Pane {
             )
             .map(move |msg| Message::Buffer(id, msg));
 
+        // Colors set for this buffer, falling back to the theme's
+        let content = if self.settings.appearance.is_empty() {
+            content
+        } else {
+            let appearance = self.settings.appearance.clone();
+
+            Themer::new(
+                move |theme: &theme::Theme| theme.with_appearance(&appearance),
+                container(content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .style(theme::container::pane_body),
+            )
+            .into()
+        };
+
         widget::Content::new(content)
             .style(if is_focused {
                 theme::container::pane_body_selected
@@ -128,9 +173,21 @@ impl Pane {
         }
     }
 
+    pub fn is_read_only(&self) -> bool {
+        self.settings.read_only
+    }
+
+    pub fn are_bot_commands_collapsed(&self) -> bool {
+        self.settings.channel.bot_commands.collapsed
+    }
+
     pub fn update_settings(&mut self, f: impl FnOnce(&mut buffer::Settings)) {
         f(&mut self.settings);
     }
+
+    pub fn set_title_hovered(&mut self, hovered: bool) {
+        self.title_bar.hovered = hovered;
+    }
 }
 
 impl TitleBar {
@@ -138,7 +195,8 @@ impl TitleBar {
         &'a self,
         buffer: &Buffer,
         value: String,
-        _id: pane_grid::Pane,
+        stats: Option<String>,
+        id: pane_grid::Pane,
         panes: usize,
         _is_focused: bool,
         maximized: bool,
@@ -172,6 +230,23 @@ impl TitleBar {
                 }
             }
 
+            let threads_button = button(center(icon::threads()))
+                .padding(5)
+                .width(22)
+                .height(22)
+                .on_press(Message::ToggleShowThreads)
+                .style(|theme, status| {
+                    theme::button::tertiary(theme, status, settings.channel.threads.enabled)
+                });
+
+            let threads_button_with_tooltip = tooltip(
+                threads_button,
+                show_tooltips.then_some("Threads"),
+                tooltip::Position::Bottom,
+            );
+
+            controls = controls.push(threads_button_with_tooltip);
+
             let nicklist_button = button(center(icon::people()))
                 .padding(5)
                 .width(22)
@@ -245,10 +320,48 @@ impl TitleBar {
         .padding([0, 4])
         .align_y(iced::alignment::Vertical::Center);
 
+        let title: widget::Element<'a, Message> = match stats {
+            Some(stats) => mouse_area(iced::widget::tooltip(
+                title,
+                container(text(stats).style(theme::text::transparent))
+                    .style(theme::container::context)
+                    .padding(8),
+                tooltip::Position::Bottom,
+            ))
+            .on_enter(Message::TitleHovered(id, true))
+            .on_exit(Message::TitleHovered(id, false))
+            .into(),
+            None => title.into(),
+        };
+
         widget::TitleBar::new(title).controls(controls).padding(6)
     }
 }
 
+fn stats_text(stats: history::Stats, bans: Option<&[String]>) -> String {
+    let mut text = format!(
+        "{} messages in the last hour\n{} messages in the last day",
+        stats.last_hour, stats.last_day
+    );
+
+    if !stats.top_users.is_empty() {
+        let users = stats
+            .top_users
+            .iter()
+            .map(|(nick, count)| format!("{nick} ({count})"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        text.push_str(&format!("\nMost active: {users}"));
+    }
+
+    if let Some(bans) = bans.filter(|bans| !bans.is_empty()) {
+        text.push_str(&format!("\nBans: {}", bans.join(", ")));
+    }
+
+    text
+}
+
 impl From<Pane> for data::Pane {
     fn from(pane: Pane) -> Self {
         let buffer = match pane.buffer {
@@ -1,5 +1,8 @@
+use std::collections::HashSet;
+
+use data::client::Status;
 use data::dashboard::DefaultAction;
-use data::{file_transfer, history, Buffer};
+use data::{file_transfer, history, Buffer, Server};
 use iced::widget::{
     button, center, column, container, horizontal_space, pane_grid, row, scrollable, text,
     vertical_space, Scrollable,
@@ -17,8 +20,11 @@ pub enum Message {
     Close(pane_grid::Pane),
     Swap(pane_grid::Pane, pane_grid::Pane),
     Leave(Buffer),
+    ToggleReadOnly(pane_grid::Pane),
+    ToggleBotCommands(pane_grid::Pane),
     ToggleFileTransfers,
     ToggleCommandBar,
+    ToggleNetwork(String),
 }
 
 #[derive(Debug, Clone)]
@@ -28,6 +34,8 @@ pub enum Event {
     Close(pane_grid::Pane),
     Swap(pane_grid::Pane, pane_grid::Pane),
     Leave(Buffer),
+    ToggleReadOnly(pane_grid::Pane),
+    ToggleBotCommands(pane_grid::Pane),
     ToggleFileTransfers,
     ToggleCommandBar,
 }
@@ -35,26 +43,39 @@ pub enum Event {
 #[derive(Clone)]
 pub struct Sidebar {
     hidden: bool,
+    collapsed_networks: HashSet<String>,
 }
 
 impl Sidebar {
     pub fn new() -> Self {
-        Self { hidden: false }
+        Self {
+            hidden: false,
+            collapsed_networks: HashSet::new(),
+        }
     }
 
     pub fn toggle_visibility(&mut self) {
         self.hidden = !self.hidden
     }
 
-    pub fn update(&mut self, message: Message) -> Event {
+    pub fn update(&mut self, message: Message) -> Option<Event> {
         match message {
-            Message::Open(source) => Event::Open(source),
-            Message::Replace(source, pane) => Event::Replace(source, pane),
-            Message::Close(pane) => Event::Close(pane),
-            Message::Swap(from, to) => Event::Swap(from, to),
-            Message::Leave(buffer) => Event::Leave(buffer),
-            Message::ToggleFileTransfers => Event::ToggleFileTransfers,
-            Message::ToggleCommandBar => Event::ToggleCommandBar,
+            Message::Open(source) => Some(Event::Open(source)),
+            Message::Replace(source, pane) => Some(Event::Replace(source, pane)),
+            Message::Close(pane) => Some(Event::Close(pane)),
+            Message::Swap(from, to) => Some(Event::Swap(from, to)),
+            Message::Leave(buffer) => Some(Event::Leave(buffer)),
+            Message::ToggleReadOnly(pane) => Some(Event::ToggleReadOnly(pane)),
+            Message::ToggleBotCommands(pane) => Some(Event::ToggleBotCommands(pane)),
+            Message::ToggleFileTransfers => Some(Event::ToggleFileTransfers),
+            Message::ToggleCommandBar => Some(Event::ToggleCommandBar),
+            Message::ToggleNetwork(network) => {
+                if !self.collapsed_networks.remove(&network) {
+                    self.collapsed_networks.insert(network);
+                }
+
+                None
+            }
         }
     }
 
@@ -64,7 +85,7 @@ impl Sidebar {
         history: &'a history::Manager,
         panes: &pane_grid::State<Pane>,
         focus: Option<pane_grid::Pane>,
-        config: data::config::Sidebar,
+        config: &data::config::Sidebar,
         show_tooltips: bool,
         file_transfers: &'a file_transfer::Manager,
     ) -> Option<Element<'a, Message>> {
@@ -73,66 +94,36 @@ impl Sidebar {
         }
 
         let mut column = column![].spacing(1);
+        let mut shown_networks = HashSet::new();
 
         for (server, state) in clients.iter() {
-            match state {
-                data::client::State::Disconnected => {
-                    column = column.push(buffer_button(
-                        panes,
-                        focus,
-                        Buffer::Server(server.clone()),
-                        false,
-                        false,
-                        config.default_action,
-                    ));
-                }
-                data::client::State::Ready(connection) => {
-                    column = column.push(buffer_button(
-                        panes,
-                        focus,
-                        Buffer::Server(server.clone()),
-                        true,
-                        false,
-                        config.default_action,
-                    ));
-
-                    for channel in connection.channels() {
-                        column = column.push(buffer_button(
-                            panes,
-                            focus,
-                            Buffer::Channel(server.clone(), channel.clone()),
-                            true,
-                            config
-                                .show_unread_indicators
-                                .then(|| {
-                                    history.has_unread(
-                                        server,
-                                        &history::Kind::Channel(channel.clone()),
-                                    )
-                                })
-                                .unwrap_or(false),
-                            config.default_action,
-                        ));
-                    }
-
-                    let queries = history.get_unique_queries(server);
-                    for user in queries {
-                        column = column.push(buffer_button(
-                            panes,
-                            focus,
-                            Buffer::Query(server.clone(), user.clone()),
-                            true,
-                            config
-                                .show_unread_indicators
-                                .then(|| {
-                                    history.has_unread(server, &history::Kind::Query(user.clone()))
-                                })
-                                .unwrap_or(false),
-                            config.default_action,
-                        ));
-                    }
-
-                    column = column.push(vertical_space().height(12));
+            let Some(network) = config.network(server) else {
+                column = column.push(server_entries(server, state, history, panes, focus, config));
+                continue;
+            };
+
+            // Every server of a network is listed under its first occurrence
+            if !shown_networks.insert(network) {
+                continue;
+            }
+
+            let members = clients
+                .iter()
+                .filter(|(server, _)| config.network(server) == Some(network));
+            let collapsed = self.collapsed_networks.contains(network);
+            let has_unread = config.show_unread_indicators
+                && members
+                    .clone()
+                    .any(|(server, _)| history.server_has_unread(server));
+
+            column = column.push(network_button(network, collapsed, has_unread));
+
+            if collapsed {
+                column = column.push(vertical_space().height(12));
+            } else {
+                for (server, state) in members {
+                    column =
+                        column.push(server_entries(server, state, history, panes, focus, config));
                 }
             }
         }
@@ -209,12 +200,144 @@ impl Sidebar {
     }
 }
 
+fn server_entries<'a>(
+    server: &Server,
+    state: &data::client::State,
+    history: &history::Manager,
+    panes: &pane_grid::State<Pane>,
+    focus: Option<pane_grid::Pane>,
+    config: &data::config::Sidebar,
+) -> Element<'a, Message> {
+    let mut column = column![].spacing(1);
+
+    match state {
+        data::client::State::Disconnected | data::client::State::Failed => {
+            column = column.push(buffer_button(
+                panes,
+                focus,
+                Buffer::Server(server.clone()),
+                if matches!(state, data::client::State::Failed) {
+                    Status::Failed
+                } else {
+                    Status::Disconnected
+                },
+                Activity::None,
+                config.default_action,
+            ));
+        }
+        data::client::State::Ready(connection) => {
+            column = column.push(buffer_button(
+                panes,
+                focus,
+                Buffer::Server(server.clone()),
+                Status::Connected,
+                Activity::None,
+                config.default_action,
+            ));
+
+            for channel in connection.channels() {
+                column = column.push(buffer_button(
+                    panes,
+                    focus,
+                    Buffer::Channel(server.clone(), channel.clone()),
+                    Status::Connected,
+                    Activity::new(
+                        history,
+                        server,
+                        &history::Kind::Channel(channel.clone()),
+                        config,
+                    ),
+                    config.default_action,
+                ));
+            }
+
+            let queries = history.get_unique_queries(server);
+            for user in queries {
+                column = column.push(buffer_button(
+                    panes,
+                    focus,
+                    Buffer::Query(server.clone(), user.clone()),
+                    Status::Connected,
+                    Activity::new(history, server, &history::Kind::Query(user.clone()), config),
+                    config.default_action,
+                ));
+            }
+
+            column = column.push(vertical_space().height(12));
+        }
+    }
+
+    column.into()
+}
+
+fn network_button<'a>(network: &str, collapsed: bool, has_unread: bool) -> Element<'a, Message> {
+    let row = row![]
+        .push(
+            text(if collapsed { "▸" } else { "▾" })
+                .style(theme::text::transparent)
+                .shaping(text::Shaping::Advanced),
+        )
+        .push(
+            text(network.to_string())
+                .style(theme::text::primary)
+                .shaping(text::Shaping::Advanced),
+        )
+        .push_maybe(has_unread.then_some(icon::dot().size(6).style(theme::text::unread)))
+        .spacing(8)
+        .align_items(iced::Alignment::Center);
+
+    button(row)
+        .padding(5)
+        .width(Length::Fill)
+        .style(theme::button::side_menu)
+        .on_press(Message::ToggleNetwork(network.to_string()))
+        .into()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Activity {
+    None,
+    Unread,
+    Highlight,
+}
+
+impl Activity {
+    fn new(
+        history: &history::Manager,
+        server: &Server,
+        kind: &history::Kind,
+        config: &data::config::Sidebar,
+    ) -> Self {
+        if !config.show_unread_indicators {
+            Activity::None
+        } else if history.has_unread_highlight(server, kind) {
+            Activity::Highlight
+        } else if history.has_unread(server, kind) {
+            Activity::Unread
+        } else {
+            Activity::None
+        }
+    }
+
+    fn indicator<'a>(self) -> Option<Element<'a, Message>> {
+        let style = match self {
+            Activity::None => return None,
+            Activity::Unread => theme::text::unread,
+            Activity::Highlight => theme::text::highlight,
+        };
+
+        Some(icon::dot().size(6).style(style).into())
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum Entry {
     NewPane,
     Replace(pane_grid::Pane),
     Close(pane_grid::Pane),
     Swap(pane_grid::Pane, pane_grid::Pane),
+    ReadOnly(pane_grid::Pane, bool),
+    BotCommands(pane_grid::Pane, bool),
     Leave,
 }
 
@@ -246,20 +369,23 @@ fn buffer_button<'a>(
     panes: &pane_grid::State<Pane>,
     focus: Option<pane_grid::Pane>,
     buffer: Buffer,
-    connected: bool,
-    has_unread: bool,
+    status: Status,
+    activity: Activity,
     default_action: DefaultAction,
 ) -> Element<'a, Message> {
+    let connected = status.connected();
+    let has_activity = activity != Activity::None;
+
     let open = panes
         .iter()
         .find_map(|(pane, state)| (state.buffer.data().as_ref() == Some(&buffer)).then_some(*pane));
 
     let row = match &buffer {
         Buffer::Server(server) => row![
-            if connected {
-                icon::globe()
-            } else {
-                icon::wifi_off()
+            match status {
+                Status::Connected | Status::Unavailable => icon::globe(),
+                Status::Disconnected => icon::wifi_off().style(theme::text::connecting),
+                Status::Failed => icon::wifi_off().style(theme::text::connection_error),
             },
             text(server.to_string())
                 .style(theme::text::primary)
@@ -269,8 +395,8 @@ fn buffer_button<'a>(
         .align_items(iced::Alignment::Center),
         Buffer::Channel(_, channel) => row![]
             .push(horizontal_space().width(3))
-            .push_maybe(has_unread.then_some(icon::dot().size(6).style(theme::text::info)))
-            .push(horizontal_space().width(if has_unread { 10 } else { 16 }))
+            .push_maybe(activity.indicator())
+            .push(horizontal_space().width(if has_activity { 10 } else { 16 }))
             .push(
                 text(channel.clone())
                     .style(theme::text::primary)
@@ -279,8 +405,8 @@ fn buffer_button<'a>(
             .align_items(iced::Alignment::Center),
         Buffer::Query(_, nick) => row![]
             .push(horizontal_space().width(3))
-            .push_maybe(has_unread.then_some(icon::dot().size(6).style(theme::text::info)))
-            .push(horizontal_space().width(if has_unread { 10 } else { 16 }))
+            .push_maybe(activity.indicator())
+            .push(horizontal_space().width(if has_activity { 10 } else { 16 }))
             .push(
                 text(nick.to_string())
                     .style(theme::text::primary)
@@ -305,7 +431,22 @@ fn buffer_button<'a>(
             },
         });
 
-    let entries = Entry::list(panes.len(), open, focus);
+    let mut entries = Entry::list(panes.len(), open, focus);
+
+    // Server buffers are left out, since commands are sent from there
+    if let (Some(open), false) = (open, matches!(buffer, Buffer::Server(_))) {
+        let read_only = panes.get(open).is_some_and(Pane::is_read_only);
+
+        entries.insert(entries.len() - 1, Entry::ReadOnly(open, read_only));
+    }
+
+    if let (Some(open), Buffer::Channel(..)) = (open, &buffer) {
+        let collapsed = panes
+            .get(open)
+            .is_some_and(Pane::are_bot_commands_collapsed);
+
+        entries.insert(entries.len() - 1, Entry::BotCommands(open, collapsed));
+    }
 
     if entries.is_empty() || !connected {
         base.into()
@@ -319,6 +460,22 @@ fn buffer_button<'a>(
                 ),
                 Entry::Close(pane) => ("Close pane", Message::Close(pane)),
                 Entry::Swap(from, to) => ("Swap with current pane", Message::Swap(from, to)),
+                Entry::ReadOnly(pane, read_only) => (
+                    if read_only {
+                        "Allow sending"
+                    } else {
+                        "Make read-only"
+                    },
+                    Message::ToggleReadOnly(pane),
+                ),
+                Entry::BotCommands(pane, collapsed) => (
+                    if collapsed {
+                        "Show bot commands"
+                    } else {
+                        "Collapse bot commands"
+                    },
+                    Message::ToggleBotCommands(pane),
+                ),
                 Entry::Leave => (
                     match &buffer {
                         Buffer::Server(_) => "Leave server",
@@ -2,10 +2,21 @@ pub use data::stream::{self, *};
 use data::{config, server};
 use iced::{subscription, Subscription};
 
-pub fn run(entry: server::Entry, proxy: Option<config::Proxy>) -> Subscription<stream::Update> {
+pub fn run(
+    entry: server::Entry,
+    proxy: Option<config::Proxy>,
+    staged_nicks: server::StagedNicks,
+) -> Subscription<stream::Update> {
+    // Identified by the address too, so following a redirect reconnects
+    let id = (
+        entry.server.clone(),
+        entry.config.server.clone(),
+        entry.config.port,
+    );
+
     // Channel messages are batched every 50ms so channel size 10 ~= 500ms which
     // app thread should more than easily keep up with
-    subscription::channel(entry.server.clone(), 10, move |sender| {
-        stream::run(entry, proxy, sender)
+    subscription::channel(id, 10, move |sender| {
+        stream::run(entry, proxy, staged_nicks, sender)
     })
 }
@@ -47,6 +47,22 @@ impl Theme {
         }
     }
 
+    /// The theme with the colors set for a buffer in place of its own
+    pub fn with_appearance(&self, appearance: &data::buffer::Appearance) -> Self {
+        let apply = |theme: &data::Theme| data::Theme {
+            colors: appearance.apply(&theme.colors),
+            ..theme.clone()
+        };
+
+        match self {
+            Theme::Selected(selected) => Theme::Selected(apply(selected)),
+            Theme::Preview { selected, preview } => Theme::Preview {
+                selected: apply(selected),
+                preview: apply(preview),
+            },
+        }
+    }
+
     pub fn colors(&self) -> &Colors {
         match self {
             Theme::Selected(selected) => &selected.colors,
@@ -115,6 +115,17 @@ pub fn highlight(theme: &Theme) -> Style {
     }
 }
 
+pub fn own_message(theme: &Theme) -> Style {
+    Style {
+        background: Some(Background::Color(theme.colors().accent.high_alpha)),
+        border: Border {
+            radius: 0.0.into(),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
 pub fn semi_transparent(theme: &Theme) -> Style {
     Style {
         background: Some(
@@ -56,6 +56,8 @@ pub fn server(
         message::source::server::Kind::Part => config.part.hex.as_deref().and_then(hex_to_color),
         message::source::server::Kind::Quit => config.quit.hex.as_deref().and_then(hex_to_color),
         message::source::server::Kind::ReplyTopic => config.topic.hex.as_deref().and_then(hex_to_color),
+        message::source::server::Kind::ChangeHost => config.change_host.hex.as_deref().and_then(hex_to_color),
+        message::source::server::Kind::Numeric(_) => None,
     }).or_else(|| text::info(theme).color);
 
     Style {
@@ -67,6 +67,30 @@ pub fn transparent_accent(theme: &Theme) -> Style {
     }
 }
 
+pub fn unread(theme: &Theme) -> Style {
+    Style {
+        color: Some(theme.colors().sidebar.unread),
+    }
+}
+
+pub fn highlight(theme: &Theme) -> Style {
+    Style {
+        color: Some(theme.colors().sidebar.highlight),
+    }
+}
+
+pub fn connection_error(theme: &Theme) -> Style {
+    Style {
+        color: Some(theme.colors().sidebar.error),
+    }
+}
+
+pub fn connecting(theme: &Theme) -> Style {
+    Style {
+        color: Some(theme.colors().sidebar.connecting),
+    }
+}
+
 pub fn nickname(theme: &Theme, nick_color: NickColor, transparent: bool) -> Style {
     let dark_theme = theme.colors().is_dark_theme();
     let NickColor { color, seed } = nick_color;
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use data::config::buffer::Pastebin;
 use data::user::User;
 use data::{input, isupport, Buffer, Command};
 use iced::advanced::widget::{self, Operation};
@@ -25,9 +26,12 @@ pub fn input<'a, Message>(
     isupport: HashMap<isupport::Kind, isupport::Parameter>,
     buffer_focused: bool,
     disabled: bool,
+    pastebin: &'a Pastebin,
+    paste_failed: bool,
     on_input: impl Fn(input::Draft) -> Message + 'a,
     on_submit: impl Fn(data::Input) -> Message + 'a,
     on_completion: impl Fn(input::Draft) -> Message + 'a,
+    on_paste: impl Fn(input::Draft) -> Message + 'a,
 ) -> Element<'a, Message>
 where
     Message: 'a + Clone,
@@ -42,9 +46,12 @@ where
         history,
         buffer_focused,
         disabled,
+        pastebin,
+        paste_failed,
         on_input: Box::new(on_input),
         on_submit: Box::new(on_submit),
         on_completion: Box::new(on_completion),
+        on_paste: Box::new(on_paste),
     }
     .into()
 }
@@ -74,9 +81,13 @@ pub struct Input<'a, Message> {
     history: &'a [String],
     buffer_focused: bool,
     disabled: bool,
+    pastebin: &'a Pastebin,
+    /// The input failed to upload, so it's sent as lines instead
+    paste_failed: bool,
     on_input: Box<dyn Fn(data::input::Draft) -> Message + 'a>,
     on_submit: Box<dyn Fn(data::Input) -> Message + 'a>,
     on_completion: Box<dyn Fn(data::input::Draft) -> Message + 'a>,
+    on_paste: Box<dyn Fn(data::input::Draft) -> Message + 'a>,
 }
 
 #[derive(Default)]
@@ -84,6 +95,7 @@ pub struct State {
     error: Option<String>,
     completion: Completion,
     selected_history: Option<usize>,
+    paste_offered: bool,
 }
 
 impl<'a, Message> Component<Message, Theme, Renderer> for Input<'a, Message>
@@ -100,6 +112,8 @@ where
                 state.error = None;
                 // Reset selected history
                 state.selected_history = None;
+                // Offer the pastebin again for the changed input
+                state.paste_offered = false;
 
                 state
                     .completion
@@ -123,11 +137,30 @@ where
                         buffer: self.buffer.clone(),
                         text: new_input,
                     }))
+                } else if !self.paste_failed && self.pastebin.exceeds(self.input) {
+                    state.completion.reset();
+
+                    // Ask first, sending uploads it
+                    if self.pastebin.auto || state.paste_offered {
+                        state.paste_offered = false;
+
+                        Some((self.on_paste)(input::Draft {
+                            buffer: self.buffer.clone(),
+                            text: self.input.to_string(),
+                        }))
+                    } else {
+                        state.paste_offered = true;
+                        state.error =
+                            Some("Large paste, send again to upload it to the pastebin".into());
+
+                        None
+                    }
                 } else if !self.input.is_empty() {
                     state.completion.reset();
 
                     // Parse input
-                    let input = match input::parse(self.buffer.clone(), self.input) {
+                    let input = match input::parse(self.buffer.clone(), self.input, &self.isupport)
+                    {
                         Ok(input) => input,
                         Err(error) => {
                             state.error = Some(error.to_string());
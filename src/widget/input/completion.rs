@@ -723,6 +723,58 @@ static COMMAND_LIST: Lazy<Vec<Command>> = Lazy::new(|| {
                 },
             ],
         },
+        Command {
+            title: "SEARCH",
+            args: vec![Arg {
+                text: "terms",
+                optional: false,
+                tooltip: Some(String::from(
+                    "searches the current buffer, or all buffers from the server buffer",
+                )),
+            }],
+        },
+        Command {
+            title: "TOCHANNEL",
+            args: vec![
+                Arg {
+                    text: "channel",
+                    optional: false,
+                    tooltip: None,
+                },
+                Arg {
+                    text: "count",
+                    optional: true,
+                    tooltip: Some(String::from(
+                        "number of recent query messages to copy (default: 10)",
+                    )),
+                },
+            ],
+        },
+        Command {
+            title: "APPEARANCE",
+            args: vec![
+                Arg {
+                    text: "part",
+                    optional: false,
+                    tooltip: Some(String::from("background, accent or nickname")),
+                },
+                Arg {
+                    text: "color",
+                    optional: true,
+                    tooltip: Some(String::from(
+                        "hex color for this buffer, left out to use the theme's",
+                    )),
+                },
+            ],
+        },
+        Command {
+            title: "HELP",
+            args: vec![Arg {
+                text: "topic",
+                optional: true,
+                tooltip: Some(String::from("help from the server, shown in the server buffer")),
+            }],
+        },
     ]
 });
 
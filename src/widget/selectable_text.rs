@@ -39,6 +39,7 @@ where
     font: Option<Renderer::Font>,
     shaping: Shaping,
     class: Theme::Class<'a>,
+    part: Part,
 }
 
 impl<'a, Theme, Renderer> Text<'a, Theme, Renderer>
@@ -61,6 +62,7 @@ where
             #[cfg(not(debug_assertions))]
             shaping: Shaping::Advanced,
             class: Theme::default(),
+            part: Part::default(),
         }
     }
 
@@ -116,6 +118,11 @@ where
         self.class = class.into();
         self
     }
+
+    pub fn part(mut self, part: Part) -> Self {
+        self.part = part;
+        self
+    }
 }
 
 impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer> for Text<'a, Theme, Renderer>
@@ -386,13 +393,24 @@ where
 
         let bounds = layout.bounds();
         let value = Value::new(&self.fragment);
-        if let Some(selection) = state
+        let selected = state
             .interaction
             .selection()
             .and_then(|raw| selection(raw, bounds, &state.paragraph, &value))
-        {
-            let content = value.select(selection.start, selection.end).to_string();
-            operation.custom(&mut (bounds.y, content), None);
+            .map(|selection| value.select(selection.start, selection.end).to_string());
+
+        // Unselected timestamps and nicknames are reported too, so they can be
+        // copied along with the text of their message
+        if selected.is_some() || self.part != Part::Text {
+            operation.custom(
+                &mut Selected {
+                    y: bounds.y,
+                    part: self.part,
+                    text: self.fragment.to_string(),
+                    selected,
+                },
+                None,
+            );
         }
     }
 }
@@ -479,25 +497,70 @@ impl Interaction {
 //     renderer.measure_width(&value.to_string(), size, font, text::Shaping::Advanced)
 // }
 
-pub fn selected<Message: Send + 'static>(f: fn(Vec<(f32, String)>) -> Message) -> Task<Message> {
-    struct Selected<T> {
-        contents: Vec<(f32, String)>,
-        f: fn(Vec<(f32, String)>) -> T,
+/// What a fragment of text is part of, which decides how it's copied
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Part {
+    Timestamp,
+    Nickname,
+    #[default]
+    Text,
+}
+
+/// A fragment of text found when copying
+#[derive(Debug, Clone)]
+pub struct Selected {
+    pub y: f32,
+    pub part: Part,
+    pub text: String,
+    pub selected: Option<String>,
+}
+
+pub fn selected<Message: Send + 'static>(f: fn(Vec<Selected>) -> Message) -> Task<Message> {
+    collect(None, f)
+}
+
+/// Like [`selected`], but only collects the text inside the widget with `id`
+pub fn selected_in<Message: Send + 'static>(
+    id: impl Into<widget::Id>,
+    f: fn(Vec<Selected>) -> Message,
+) -> Task<Message> {
+    collect(Some(id.into()), f)
+}
+
+fn collect<Message: Send + 'static>(
+    scope: Option<widget::Id>,
+    f: fn(Vec<Selected>) -> Message,
+) -> Task<Message> {
+    struct Collect<T> {
+        contents: Vec<Selected>,
+        scope: Option<widget::Id>,
+        in_scope: bool,
+        f: fn(Vec<Selected>) -> T,
     }
 
-    impl<T> Operation<T> for Selected<T> {
+    impl<T> Operation<T> for Collect<T> {
         fn container(
             &mut self,
-            _id: Option<&widget::Id>,
+            id: Option<&widget::Id>,
             _bounds: Rectangle,
             operate_on_children: &mut dyn FnMut(&mut dyn Operation<T>),
         ) {
-            operate_on_children(self)
+            if !self.in_scope && id.is_some() && id == self.scope.as_ref() {
+                self.in_scope = true;
+                operate_on_children(self);
+                self.in_scope = false;
+            } else {
+                operate_on_children(self)
+            }
         }
 
         fn custom(&mut self, state: &mut dyn std::any::Any, _id: Option<&widget::Id>) {
-            if let Some(content) = state.downcast_ref::<(f32, String)>() {
-                self.contents.push(content.clone());
+            if !self.in_scope {
+                return;
+            }
+
+            if let Some(selected) = state.downcast_ref::<Selected>() {
+                self.contents.push(selected.clone());
             }
         }
 
@@ -506,8 +569,10 @@ pub fn selected<Message: Send + 'static>(f: fn(Vec<(f32, String)>) -> Message) -
         }
     }
 
-    Task::widget(Selected {
+    Task::widget(Collect {
         contents: vec![],
+        in_scope: scope.is_none(),
+        scope,
         f,
     })
 }